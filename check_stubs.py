@@ -0,0 +1,78 @@
+import ast
+import inspect
+import sys
+from pathlib import Path
+from typing import Optional
+
+import repo_scan_rs
+
+STUB = Path(__file__).with_name('repo_scan_rs.pyi')
+
+
+def stub_parameters(node: ast.FunctionDef) -> list[str]:
+    args = node.args
+    names = [a.arg for a in args.posonlyargs + args.args + args.kwonlyargs]
+    return [name for name in names if name != 'self']
+
+
+def runtime_parameters(obj: object) -> Optional[list[str]]:
+    # Classes and functions without a text signature cannot be inspected
+    try:
+        signature = inspect.signature(obj)
+    except (TypeError, ValueError):
+        return None
+    return [
+        name for name, p in signature.parameters.items()
+        if p.kind not in (p.VAR_POSITIONAL, p.VAR_KEYWORD)
+    ]
+
+
+def check() -> list[str]:
+    """
+    Compare repo_scan_rs.pyi with the built module.
+
+    Returns:
+        One line per mismatch; empty when the stub is in step
+    """
+    tree = ast.parse(STUB.read_text())
+    problems = []
+
+    # Type aliases are assignments and exist only in the stub
+    functions = {n.name: n for n in tree.body if isinstance(n, ast.FunctionDef)}
+    classes = {n.name: n for n in tree.body if isinstance(n, ast.ClassDef)}
+    public = {name for name in dir(repo_scan_rs) if not name.startswith('_')}
+    for name in sorted(public - functions.keys() - classes.keys()):
+        problems.append(f'{name} is missing from the stub')
+    for name in sorted((functions.keys() | classes.keys()) - public):
+        problems.append(f'{name} is in the stub but not in the module')
+
+    for name, node in functions.items():
+        runtime = runtime_parameters(getattr(repo_scan_rs, name, None))
+        if runtime is not None and runtime != stub_parameters(node):
+            problems.append(f'{name} takes {runtime}, the stub says {stub_parameters(node)}')
+
+    options = classes['AnalysisOptions']
+    fields = set(repo_scan_rs.AnalysisOptions().to_dict())
+    attributes = {
+        n.target.id for n in options.body
+        if isinstance(n, ast.AnnAssign) and isinstance(n.target, ast.Name)
+    }
+    init = next(n for n in options.body if isinstance(n, ast.FunctionDef) and n.name == '__init__')
+    for label, names in (('attributes', attributes), ('__init__ arguments', set(stub_parameters(init)))):
+        for name in sorted(fields - names):
+            problems.append(f'AnalysisOptions.{name} is missing from the stub {label}')
+        for name in sorted(names - fields):
+            problems.append(f'AnalysisOptions.{name} is in the stub {label} but not an option')
+
+    return problems
+
+
+def main() -> None:
+    problems = check()
+    for problem in problems:
+        print(problem)
+    sys.exit(1 if problems else 0)
+
+
+if __name__ == '__main__':
+    main()
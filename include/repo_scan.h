@@ -0,0 +1,28 @@
+/* C interface to repo-scan-rs, built with
+ *   cargo build --release --no-default-features --features capi
+ * which produces target/release/librepo_scan_rs.so (.dylib, .dll).
+ *
+ * Requests are JSON objects naming the analysis like the Python function,
+ * e.g. {"analysis": "analyze_git_repo", "repo_path": ".", "options": {...}}.
+ * Responses are {"ok": true, "result": ...} or {"ok": false, "error": "..."}.
+ */
+#ifndef REPO_SCAN_H
+#define REPO_SCAN_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Returns a string owned by the library; release it with repo_scan_free_string. */
+char *repo_scan_run(const char *request_json);
+
+void repo_scan_free_string(char *value);
+
+/* Static string, must not be freed. */
+const char *repo_scan_version(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* REPO_SCAN_H */
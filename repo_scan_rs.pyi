@@ -0,0 +1,379 @@
+# Type stubs for the repo_scan_rs extension module, kept in step with the
+# #[pyfunction] signatures in src/python.rs and the AnalysisOptions fields in
+# src/options.rs; check_stubs.py compares the two. maturin ships this file as
+# the package's __init__.pyi next to a py.typed marker it writes itself.
+
+from typing import Any, Callable, Literal, Mapping, Sequence
+
+# extension -> counters ("lines", "files", "additions", "deletions",
+# "modifications", "repos" and any enabled per-extension metrics)
+Stats = dict[str, int]
+ExtensionStats = dict[str, Stats]
+# month -> extension -> counters
+MonthlyStats = dict[str, ExtensionStats]
+# With output_format="envelope" the data moves under a key next to
+# "metadata" and the enabled report sections
+Result = dict[str, Any]
+
+ProgressCallback = Callable[[int, int | None], object]
+ManifestEntry = str | Mapping[str, str]
+
+class RepoScanError(ValueError):
+    repo_path: str | None
+    commit: str | None
+
+class GitOpenError(RepoScanError): ...
+class PatternError(RepoScanError): ...
+class Cancelled(RepoScanError): ...
+
+class PartialResultError(RepoScanError):
+    partial: Any
+
+class AnalysisOptions:
+    patterns: list[str]
+    show_progress: bool
+    progress_template: str | None
+    progress_refresh_hz: int | None
+    progress_fd: int | None
+    quiet: bool
+    on_error: Literal["raise", "skip", "collect"]
+    partial_results: bool
+    time_budget_seconds: float | None
+    ignore_replacements: bool
+    fetch_missing_objects: bool
+    commit_buffer_mb: int | None
+    max_parallel_repos: int | None
+    since: str | None
+    until: str | None
+    bucket: Literal["month", "iso_week", "fiscal_quarter", "fiscal_year"]
+    fiscal_year_start_month: int
+    bucket_format: str | None
+    output_format: Literal["nested", "envelope"]
+    pivot: Literal["month", "author", "extension", "pattern"]
+    groups: dict[str, list[str]]
+    pattern_labels: list[str]
+    use_ignore_file: bool
+    pathspecs: list[str]
+    ignore_whitespace: bool
+    ignore_whitespace_change: bool
+    ignore_blank_lines: bool
+    include_typechange: bool
+    context_lines: int | None
+    word_diff: bool
+    detect_formatting: bool
+    classify_lines: bool
+    classify_tests: bool
+    test_patterns: list[str]
+    docs_churn: bool
+    track_markers: bool
+    markers: list[str]
+    track_dependencies: bool
+    dependency_manifests: list[str]
+    parse_dependencies: bool
+    track_ci: bool
+    ci_patterns: list[str]
+    classify_infra: bool
+    infra_patterns: list[str]
+    track_licenses: bool
+    scan_secrets: bool
+    secret_patterns: dict[str, str]
+    track_large_binaries: bool
+    large_binary_threshold: int
+    cumulative_lines: bool
+    language_share: bool
+    language_diversity: bool
+    churn_metrics: bool
+    merge_diff: Literal["first_parent", "all_parents", "skip"]
+    include_empty: bool
+    min_lines_changed: int | None
+    max_lines_changed: int | None
+    max_files_changed: int | None
+    bulk_changes: Literal["exclude", "separate"]
+    include_no_extension: bool
+    file_detection: Literal["extension", "content"]
+    normalize_authors: bool
+    match_author_on: Literal["identity", "name", "email"]
+    ignore_author_case: bool
+    exact_author_match: bool
+    detect_duplicate_authors: bool
+    include_notes: bool
+    notes_refs: list[str]
+    notes_pattern: str | None
+    message_metrics: bool
+    classify_gitmoji: bool
+    gitmoji_categories: dict[str, str]
+    detect_anomalies: bool
+    anomaly_method: Literal["zscore", "iqr"]
+    anomaly_threshold: float | None
+    collaboration_network: bool
+    collaboration_window_days: int
+    ownership_timeline: bool
+    estimate_effort: bool
+    cocomo_mode: Literal["organic", "semi_detached", "embedded"]
+    cocomo_coefficients: dict[str, float] | None
+    cost_per_person_month: float | None
+    contributor_report: bool
+    dormancy_months: int
+    new_contributors: bool
+    contributor_growth: bool
+    timezone_distribution: bool
+    out_of_hours: bool
+    working_hours: list[int]
+    commit_latency: bool
+    latency_threshold_hours: float
+    files_touched: bool
+    commit_size: bool
+    review_trailers: bool
+    self_merges: bool
+    detect_squash_merges: bool
+    squash_patterns: list[str]
+    squash_min_lines: int | None
+    message_hygiene: bool
+    hygiene_weights: dict[str, float]
+    nesting_depth: bool
+    indent_width: int
+    function_churn: bool
+    public_api: bool
+    import_churn: bool
+    rolling_window_days: int | None
+    decayed_churn: bool
+    churn_half_life_days: float
+    file_counts: bool
+    file_lengths: bool
+    long_file_lines: int
+    detect_renames: bool
+    rename_lineage: bool
+    include_files: bool
+    git_dir: str | None
+    clone_remote: bool
+    clone_bare: bool
+    clone_depth: int | None
+    clone_since: str | None
+    ssh_use_agent: bool
+    ssh_key_path: str | None
+    ssh_passphrase: str | None
+    https_username: str | None
+    https_token: str | None
+
+    def __init__(
+        self,
+        *,
+        patterns: Sequence[str] = ...,
+        show_progress: bool = ...,
+        progress_template: str | None = ...,
+        progress_refresh_hz: int | None = ...,
+        progress_fd: int | None = ...,
+        quiet: bool = ...,
+        on_error: Literal["raise", "skip", "collect"] = ...,
+        partial_results: bool = ...,
+        time_budget_seconds: float | None = ...,
+        ignore_replacements: bool = ...,
+        fetch_missing_objects: bool = ...,
+        commit_buffer_mb: int | None = ...,
+        max_parallel_repos: int | None = ...,
+        since: str | None = ...,
+        until: str | None = ...,
+        bucket: Literal["month", "iso_week", "fiscal_quarter", "fiscal_year"] = ...,
+        fiscal_year_start_month: int = ...,
+        bucket_format: str | None = ...,
+        output_format: Literal["nested", "envelope"] = ...,
+        pivot: Literal["month", "author", "extension", "pattern"] = ...,
+        groups: Mapping[str, Sequence[str]] = ...,
+        pattern_labels: Sequence[str] = ...,
+        use_ignore_file: bool = ...,
+        pathspecs: Sequence[str] = ...,
+        ignore_whitespace: bool = ...,
+        ignore_whitespace_change: bool = ...,
+        ignore_blank_lines: bool = ...,
+        include_typechange: bool = ...,
+        context_lines: int | None = ...,
+        word_diff: bool = ...,
+        detect_formatting: bool = ...,
+        classify_lines: bool = ...,
+        classify_tests: bool = ...,
+        test_patterns: Sequence[str] = ...,
+        docs_churn: bool = ...,
+        track_markers: bool = ...,
+        markers: Sequence[str] = ...,
+        track_dependencies: bool = ...,
+        dependency_manifests: Sequence[str] = ...,
+        parse_dependencies: bool = ...,
+        track_ci: bool = ...,
+        ci_patterns: Sequence[str] = ...,
+        classify_infra: bool = ...,
+        infra_patterns: Sequence[str] = ...,
+        track_licenses: bool = ...,
+        scan_secrets: bool = ...,
+        secret_patterns: Mapping[str, str] = ...,
+        track_large_binaries: bool = ...,
+        large_binary_threshold: int = ...,
+        cumulative_lines: bool = ...,
+        language_share: bool = ...,
+        language_diversity: bool = ...,
+        churn_metrics: bool = ...,
+        merge_diff: Literal["first_parent", "all_parents", "skip"] = ...,
+        include_empty: bool = ...,
+        min_lines_changed: int | None = ...,
+        max_lines_changed: int | None = ...,
+        max_files_changed: int | None = ...,
+        bulk_changes: Literal["exclude", "separate"] = ...,
+        include_no_extension: bool = ...,
+        file_detection: Literal["extension", "content"] = ...,
+        normalize_authors: bool = ...,
+        match_author_on: Literal["identity", "name", "email"] = ...,
+        ignore_author_case: bool = ...,
+        exact_author_match: bool = ...,
+        detect_duplicate_authors: bool = ...,
+        include_notes: bool = ...,
+        notes_refs: Sequence[str] = ...,
+        notes_pattern: str | None = ...,
+        message_metrics: bool = ...,
+        classify_gitmoji: bool = ...,
+        gitmoji_categories: Mapping[str, str] = ...,
+        detect_anomalies: bool = ...,
+        anomaly_method: Literal["zscore", "iqr"] = ...,
+        anomaly_threshold: float | None = ...,
+        collaboration_network: bool = ...,
+        collaboration_window_days: int = ...,
+        ownership_timeline: bool = ...,
+        estimate_effort: bool = ...,
+        cocomo_mode: Literal["organic", "semi_detached", "embedded"] = ...,
+        cocomo_coefficients: Mapping[str, float] | None = ...,
+        cost_per_person_month: float | None = ...,
+        contributor_report: bool = ...,
+        dormancy_months: int = ...,
+        new_contributors: bool = ...,
+        contributor_growth: bool = ...,
+        timezone_distribution: bool = ...,
+        out_of_hours: bool = ...,
+        working_hours: tuple[int, int] = ...,
+        commit_latency: bool = ...,
+        latency_threshold_hours: float = ...,
+        files_touched: bool = ...,
+        commit_size: bool = ...,
+        review_trailers: bool = ...,
+        self_merges: bool = ...,
+        detect_squash_merges: bool = ...,
+        squash_patterns: Sequence[str] = ...,
+        squash_min_lines: int | None = ...,
+        message_hygiene: bool = ...,
+        hygiene_weights: Mapping[str, float] = ...,
+        nesting_depth: bool = ...,
+        indent_width: int = ...,
+        function_churn: bool = ...,
+        public_api: bool = ...,
+        import_churn: bool = ...,
+        rolling_window_days: int | None = ...,
+        decayed_churn: bool = ...,
+        churn_half_life_days: float = ...,
+        file_counts: bool = ...,
+        file_lengths: bool = ...,
+        long_file_lines: int = ...,
+        detect_renames: bool = ...,
+        rename_lineage: bool = ...,
+        include_files: bool = ...,
+        git_dir: str | None = ...,
+        clone_remote: bool = ...,
+        clone_bare: bool = ...,
+        clone_depth: int | None = ...,
+        clone_since: str | None = ...,
+        ssh_use_agent: bool = ...,
+        ssh_key_path: str | None = ...,
+        ssh_passphrase: str | None = ...,
+        https_username: str | None = ...,
+        https_token: str | None = ...,
+    ) -> None: ...
+    def replace(self, **kwargs: Any) -> AnalysisOptions: ...
+    def to_dict(self) -> dict[str, Any]: ...
+    def __getstate__(self) -> dict[str, Any]: ...
+    def __setstate__(self, state: dict[str, Any]) -> None: ...
+
+class RepoAnalyzer:
+    closed: bool
+    def __init__(self, repo_path: str, options: AnalysisOptions | None = None) -> None: ...
+    def analyze(self, on_progress: ProgressCallback | None = None) -> Result: ...
+    def analyze_commits(self, on_progress: ProgressCallback | None = None) -> Result: ...
+    def close(self) -> None: ...
+    def __enter__(self) -> RepoAnalyzer: ...
+    def __exit__(self, exc_type: object, exc_value: object, traceback: object) -> bool: ...
+
+class AnalysisJob:
+    def progress(self) -> tuple[int, int | None]: ...
+    def done(self) -> bool: ...
+    def cancel(self) -> None: ...
+    def result(self, timeout: float | None = None) -> Result: ...
+
+class CommitChunks:
+    # Scan metadata, set once the iteration is over
+    metadata: dict[str, Any] | None
+    def __iter__(self) -> CommitChunks: ...
+    def __next__(self) -> dict[str, dict[str, Any]]: ...
+
+def start_analysis(repo_path: str, options: AnalysisOptions | None = None) -> AnalysisJob: ...
+def analyze_git_repo(
+    repo_path: str,
+    patterns: Sequence[str] = ...,
+    show_progress: bool | None = None,
+    options: AnalysisOptions | None = None,
+    on_progress: ProgressCallback | None = None,
+) -> Result: ...
+def analyze_git_commits(
+    repo_path: str,
+    patterns: Sequence[str] = ...,
+    show_progress: bool | None = None,
+    options: AnalysisOptions | None = None,
+    on_progress: ProgressCallback | None = None,
+) -> Result: ...
+def iter_git_commits(
+    repo_path: str,
+    options: AnalysisOptions | None = None,
+    on_progress: ProgressCallback | None = None,
+) -> CommitChunks: ...
+def analyze_working_tree(
+    repo_path: str,
+    options: AnalysisOptions | None = None,
+    include_untracked: bool = False,
+    split_staged: bool = False,
+) -> Result: ...
+def analyze_stashes(repo_path: str, options: AnalysisOptions | None = None) -> Result: ...
+def compare_windows(
+    repo_path: str,
+    window_a: tuple[str, str],
+    window_b: tuple[str, str],
+    options: AnalysisOptions | None = None,
+) -> Result: ...
+def export_commit_graph(
+    repo_path: str,
+    format: Literal["dot", "graphml"] = "dot",
+    simplify: bool = False,
+    options: AnalysisOptions | None = None,
+) -> str: ...
+def branching_metrics(repo_path: str, options: AnalysisOptions | None = None) -> Result: ...
+def code_survival(
+    repo_path: str,
+    options: AnalysisOptions | None = None,
+    checkpoints_days: Sequence[int] | None = None,
+) -> Result: ...
+def storage_growth(
+    repo_path: str,
+    period: Literal["month", "year"] = "month",
+    options: AnalysisOptions | None = None,
+) -> Result: ...
+def author_extension_matrix(
+    repo_path: str,
+    metric: Literal["commits", "additions", "deletions", "churn"] = "additions",
+    options: AnalysisOptions | None = None,
+) -> Result: ...
+def scan_directory(
+    root: str,
+    max_depth: int | None = None,
+    options: AnalysisOptions | None = None,
+) -> Result: ...
+def scan_fleet(
+    manifest: Sequence[ManifestEntry],
+    workers: int | None = None,
+    on_complete: Callable[[dict[str, Any]], object] | None = None,
+    options: AnalysisOptions | None = None,
+) -> Result: ...
+def merge_results(results: list[Result]) -> Result: ...
+def diff_results(old: Result, new: Result) -> dict[str, Any]: ...
@@ -0,0 +1,108 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnomalyMethod {
+    // |value - mean| / standard deviation at or above the threshold (default 3)
+    #[default]
+    Zscore,
+    // Outside [Q1 - k * IQR, Q3 + k * IQR] with k the threshold (default 1.5)
+    Iqr,
+}
+
+impl AnomalyMethod {
+    #[cfg(feature = "python")]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AnomalyMethod::Zscore => "zscore",
+            AnomalyMethod::Iqr => "iqr",
+        }
+    }
+
+    pub fn default_threshold(&self) -> f64 {
+        match self {
+            AnomalyMethod::Zscore => 3.0,
+            AnomalyMethod::Iqr => 1.5,
+        }
+    }
+}
+
+// Fewer months than this make any outlier test meaningless
+const MIN_MONTHS: usize = 4;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Anomaly {
+    pub month: String,
+    pub metric: String,
+    pub value: f64,
+    // z-score, or the distance beyond the quartile in IQRs
+    pub score: f64,
+    pub direction: &'static str,
+}
+
+// Months of `series` whose value is an outlier under `method`
+pub fn detect(
+    metric: &str,
+    series: &BTreeMap<String, f64>,
+    method: AnomalyMethod,
+    threshold: f64,
+) -> Vec<Anomaly> {
+    if series.len() < MIN_MONTHS {
+        return Vec::new();
+    }
+    let values: Vec<f64> = series.values().copied().collect();
+    let score_of: Box<dyn Fn(f64) -> Option<f64>> = match method {
+        AnomalyMethod::Zscore => {
+            let mean = values.iter().sum::<f64>() / values.len() as f64;
+            let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64;
+            let deviation = variance.sqrt();
+            if deviation == 0.0 {
+                return Vec::new();
+            }
+            Box::new(move |value| {
+                let score = (value - mean) / deviation;
+                (score.abs() >= threshold).then_some(score)
+            })
+        }
+        AnomalyMethod::Iqr => {
+            let mut sorted = values.clone();
+            sorted.sort_by(f64::total_cmp);
+            let (q1, q3) = (quantile(&sorted, 0.25), quantile(&sorted, 0.75));
+            // When most months are identical the IQR is zero; fall back to raw
+            // units so a lone spike is still reported
+            let iqr = if q3 > q1 { q3 - q1 } else { 1.0 };
+            Box::new(move |value| {
+                if value > q3 + threshold * iqr {
+                    Some((value - q3) / iqr)
+                } else if value < q1 - threshold * iqr {
+                    Some((value - q1) / iqr)
+                } else {
+                    None
+                }
+            })
+        }
+    };
+
+    series
+        .iter()
+        .filter_map(|(month, &value)| {
+            let score = score_of(value)?;
+            Some(Anomaly {
+                month: month.clone(),
+                metric: metric.to_string(),
+                value,
+                score: (score * 100.0).round() / 100.0,
+                direction: if score > 0.0 { "high" } else { "low" },
+            })
+        })
+        .collect()
+}
+
+// Linear interpolation between closest ranks of sorted values
+pub fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let position = q * (sorted.len() - 1) as f64;
+    let (lower, upper) = (position.floor() as usize, position.ceil() as usize);
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (position - lower as f64)
+}
@@ -0,0 +1,385 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use tracing::{info, warn};
+
+use crate::fleet::{self, ManifestEntry};
+use crate::options::{AnalysisOptions, OutputFormat};
+use crate::pivot;
+use crate::report::Sections;
+use crate::{
+    analyze_commits_internal, analyze_repo_internal, branching, compare, convert_to_python_format,
+    directory, graph, matrix, messages, remote, stash, storage, survival, worktree, AnalyzerError, CommitData,
+    FileStats, ScanMetadata,
+};
+
+// One analysis request, named after the Python function it mirrors, e.g.
+// {"analysis": "analyze_git_repo", "repo_path": ".", "options": {...}}.
+// The command-line tool runs these and prints the JSON result.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "analysis", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Request {
+    AnalyzeGitRepo {
+        repo_path: String,
+        #[serde(default)]
+        options: AnalysisOptions,
+    },
+    AnalyzeGitCommits {
+        repo_path: String,
+        #[serde(default)]
+        options: AnalysisOptions,
+    },
+    AnalyzeWorkingTree {
+        repo_path: String,
+        #[serde(default)]
+        options: AnalysisOptions,
+        #[serde(default)]
+        include_untracked: bool,
+        #[serde(default)]
+        split_staged: bool,
+    },
+    AnalyzeStashes {
+        repo_path: String,
+        #[serde(default)]
+        options: AnalysisOptions,
+    },
+    CompareWindows {
+        repo_path: String,
+        window_a: (String, String),
+        window_b: (String, String),
+        #[serde(default)]
+        options: AnalysisOptions,
+    },
+    ExportCommitGraph {
+        repo_path: String,
+        #[serde(default = "default_graph_format")]
+        format: String,
+        #[serde(default)]
+        simplify: bool,
+        #[serde(default)]
+        options: AnalysisOptions,
+    },
+    BranchingMetrics {
+        repo_path: String,
+        #[serde(default)]
+        options: AnalysisOptions,
+    },
+    CodeSurvival {
+        repo_path: String,
+        #[serde(default)]
+        checkpoints_days: Option<Vec<u32>>,
+        #[serde(default)]
+        options: AnalysisOptions,
+    },
+    StorageGrowth {
+        repo_path: String,
+        #[serde(default = "default_storage_period")]
+        period: String,
+        #[serde(default)]
+        options: AnalysisOptions,
+    },
+    AuthorExtensionMatrix {
+        repo_path: String,
+        #[serde(default = "default_matrix_metric")]
+        metric: String,
+        #[serde(default)]
+        options: AnalysisOptions,
+    },
+    ScanDirectory {
+        root: String,
+        #[serde(default)]
+        max_depth: Option<usize>,
+        #[serde(default)]
+        options: AnalysisOptions,
+    },
+    ScanFleet {
+        manifest: Vec<ManifestEntry>,
+        #[serde(default)]
+        workers: Option<usize>,
+        #[serde(default)]
+        options: AnalysisOptions,
+    },
+}
+
+fn default_graph_format() -> String {
+    "dot".to_string()
+}
+
+fn default_matrix_metric() -> String {
+    "additions".to_string()
+}
+
+fn default_storage_period() -> String {
+    "month".to_string()
+}
+
+impl Request {
+    pub fn options(&self) -> &AnalysisOptions {
+        match self {
+            Request::AnalyzeGitRepo { options, .. }
+            | Request::AnalyzeGitCommits { options, .. }
+            | Request::AnalyzeWorkingTree { options, .. }
+            | Request::AnalyzeStashes { options, .. }
+            | Request::CompareWindows { options, .. }
+            | Request::ExportCommitGraph { options, .. }
+            | Request::BranchingMetrics { options, .. }
+            | Request::CodeSurvival { options, .. }
+            | Request::StorageGrowth { options, .. }
+            | Request::AuthorExtensionMatrix { options, .. }
+            | Request::ScanDirectory { options, .. }
+            | Request::ScanFleet { options, .. } => options,
+        }
+    }
+}
+
+// Runs a request and returns the same structure the Python function does;
+// the commit graph, which is text there, comes back as a JSON string
+pub fn run(request: Request) -> Result<Value, AnalyzerError> {
+    let options = request.options().clone().validated()?;
+    match request {
+        Request::AnalyzeGitRepo { repo_path, .. } => {
+            let mut analysis =
+                remote::with_local_repo(&repo_path, &options, |path| analyze_repo_internal(path, &options))?;
+            analysis.metadata.repo_path = remote::redacted_url(&repo_path);
+            let months = serde_json::to_value(pivot::pivot(
+                &analysis.months,
+                analysis.sections.group_months.as_ref(),
+                options.pivot,
+            ))?;
+            output(&options, "months", months, &analysis.metadata, &analysis.sections)
+        }
+        Request::AnalyzeGitCommits { repo_path, .. } => {
+            let (commits, mut metadata) =
+                remote::with_local_repo(&repo_path, &options, |path| analyze_commits_internal(path, &options))?;
+            metadata.repo_path = remote::redacted_url(&repo_path);
+            let commits: Map<String, Value> = commits
+                .into_iter()
+                .map(|(id, data)| (id, commit_json(data)))
+                .collect();
+            output(&options, "commits", Value::Object(commits), &metadata, &Sections::default())
+        }
+        Request::AnalyzeWorkingTree { repo_path, include_untracked, split_staged, .. } => {
+            let stats = if split_staged {
+                let breakdown = worktree::working_tree_breakdown(&repo_path, &options, include_untracked)?;
+                json!({
+                    "staged": stats_json(&breakdown.staged),
+                    "unstaged": stats_json(&breakdown.unstaged),
+                })
+            } else {
+                stats_json(&worktree::working_tree_stats(&repo_path, &options, include_untracked)?)
+            };
+            let metadata = ScanMetadata {
+                repo_path,
+                ..Default::default()
+            };
+            output(&options, "stats", stats, &metadata, &Sections::default())
+        }
+        Request::AnalyzeStashes { repo_path, .. } => {
+            let entries: Vec<Value> = stash::stash_entries(&repo_path, &options)?
+                .into_iter()
+                .map(|entry| {
+                    json!({
+                        "index": entry.index,
+                        "commit": entry.oid.to_string(),
+                        "message": entry.message,
+                        "timestamp": entry.timestamp,
+                        "author": entry.author,
+                        "stats": stats_json(&entry.stats),
+                    })
+                })
+                .collect();
+            let metadata = ScanMetadata {
+                repo_path,
+                ..Default::default()
+            };
+            output(&options, "stashes", Value::Array(entries), &metadata, &Sections::default())
+        }
+        Request::CompareWindows { repo_path, window_a, window_b, .. } => {
+            let (comparison, mut metadata) = remote::with_local_repo(&repo_path, &options, |path| {
+                compare::compare_windows(
+                    path,
+                    &options,
+                    (&window_a.0, &window_a.1),
+                    (&window_b.0, &window_b.1),
+                )
+            })?;
+            metadata.repo_path = remote::redacted_url(&repo_path);
+            output(&options, "comparison", serde_json::to_value(comparison)?, &metadata, &Sections::default())
+        }
+        Request::ExportCommitGraph { repo_path, format, simplify, .. } => {
+            let format = graph::GraphFormat::parse(&format)?;
+            let text =
+                remote::with_local_repo(&repo_path, &options, |path| graph::export(path, &options, format, simplify))?;
+            Ok(Value::String(text))
+        }
+        Request::BranchingMetrics { repo_path, .. } => {
+            let (months, mut metadata) = remote::with_local_repo(&repo_path, &options, |path| {
+                branching::branching_metrics(path, &options)
+            })?;
+            metadata.repo_path = remote::redacted_url(&repo_path);
+            output(&options, "months", serde_json::to_value(months)?, &metadata, &Sections::default())
+        }
+        Request::CodeSurvival { repo_path, checkpoints_days, .. } => {
+            let checkpoints_days = checkpoints_days.unwrap_or_else(|| survival::DEFAULT_CHECKPOINTS_DAYS.to_vec());
+            let report = remote::with_local_repo(&repo_path, &options, |path| {
+                survival::code_survival(path, &options, &checkpoints_days)
+            })?;
+            let metadata = ScanMetadata {
+                repo_path: remote::redacted_url(&repo_path),
+                ..Default::default()
+            };
+            output(&options, "survival", serde_json::to_value(report)?, &metadata, &Sections::default())
+        }
+        Request::StorageGrowth { repo_path, period, .. } => {
+            let period = storage::StoragePeriod::parse(&period)?;
+            let (report, mut metadata) = remote::with_local_repo(&repo_path, &options, |path| {
+                storage::storage_growth(path, &options, period)
+            })?;
+            metadata.repo_path = remote::redacted_url(&repo_path);
+            output(&options, "storage", serde_json::to_value(report)?, &metadata, &Sections::default())
+        }
+        Request::AuthorExtensionMatrix { repo_path, metric, .. } => {
+            let metric = matrix::MatrixMetric::parse(&metric)?;
+            let (matrix, mut metadata) = remote::with_local_repo(&repo_path, &options, |path| {
+                matrix::author_extension_matrix(path, &options, metric)
+            })?;
+            metadata.repo_path = remote::redacted_url(&repo_path);
+            output(&options, "matrix", serde_json::to_value(matrix)?, &metadata, &Sections::default())
+        }
+        Request::ScanDirectory { root, max_depth, .. } => {
+            let scan = directory::scan_directory(&root, &options, max_depth.unwrap_or(directory::DEFAULT_MAX_DEPTH))?;
+            let mut repos = Map::new();
+            for (name, analysis) in scan.repos {
+                let months = serde_json::to_value(convert_to_python_format(&analysis.months))?;
+                repos.insert(name, output(&options, "months", months, &analysis.metadata, &analysis.sections)?);
+            }
+            let data = json!({
+                "merged": convert_to_python_format(&scan.merged),
+                "repos": repos,
+                "errors": scan.errors,
+            });
+            output(&options, "results", data, &scan.metadata, &Sections::default())
+        }
+        Request::ScanFleet { manifest, workers, .. } => {
+            scan_fleet(fleet::parse_manifest(manifest)?, workers, &options)
+        }
+    }
+}
+
+// Each finished repository is logged as the scans complete
+fn scan_fleet(
+    entries: Vec<fleet::FleetEntry>,
+    workers: Option<usize>,
+    options: &AnalysisOptions,
+) -> Result<Value, AnalyzerError> {
+    let mut repos = Map::new();
+    let mut errors = BTreeMap::new();
+    let mut merged = HashMap::new();
+    let mut metadata = ScanMetadata::default();
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::scope(|scope| -> Result<(), AnalyzerError> {
+        let worker = scope.spawn(|| {
+            fleet::scan_fleet(&entries, options, fleet::worker_count(workers, options), sender)
+        });
+        for (completed, event) in receiver.into_iter().enumerate() {
+            let entry = &entries[event.index];
+            let label = entry.label.clone();
+            let progress = format!("{}/{}", completed + 1, entries.len());
+            let elapsed = format!("{:.1}s", event.elapsed_seconds);
+            match &event.result {
+                Ok(_) => info!(progress, repo = label, elapsed, "repository scanned"),
+                Err(e) => {
+                    let error = entry.error_message(e);
+                    warn!(progress, repo = label, elapsed, error, "repository scan failed");
+                }
+            }
+            match event.result {
+                Ok(analysis) => {
+                    metadata.absorb(&analysis.metadata);
+                    directory::merge_months(&mut merged, &analysis.months);
+                    let months = serde_json::to_value(convert_to_python_format(&analysis.months))?;
+                    repos.insert(label, output(options, "months", months, &analysis.metadata, &analysis.sections)?);
+                }
+                Err(e) => {
+                    errors.insert(label, entry.error_message(&e));
+                }
+            }
+        }
+        worker
+            .join()
+            .map_err(|_| AnalyzerError::InvalidOption("fleet worker pool panicked".to_string()))?
+    })?;
+
+    let data = json!({
+        "merged": convert_to_python_format(&merged),
+        "repos": repos,
+        "errors": errors,
+    });
+    output(options, "results", data, &metadata, &Sections::default())
+}
+
+// JSON counterpart of wrap_output
+fn output(
+    options: &AnalysisOptions,
+    key: &str,
+    data: Value,
+    metadata: &ScanMetadata,
+    sections: &Sections,
+) -> Result<Value, AnalyzerError> {
+    match options.output_format {
+        OutputFormat::Nested => Ok(data),
+        OutputFormat::Envelope => {
+            let mut envelope = serde_json::to_value(sections)?;
+            if let Value::Object(fields) = &mut envelope {
+                fields.insert(key.to_string(), data);
+                fields.insert("metadata".to_string(), serde_json::to_value(metadata)?);
+            }
+            Ok(envelope)
+        }
+    }
+}
+
+fn stats_json(stats: &HashMap<String, FileStats>) -> Value {
+    stats
+        .iter()
+        .map(|(ext, stats)| (ext.clone(), json!(stats.to_map(false))))
+        .collect::<Map<String, Value>>()
+        .into()
+}
+
+// Same fields as the per-commit dicts of analyze_git_commits
+fn commit_json(data: CommitData) -> Value {
+    let (subject, body) = messages::split(&data.message);
+    let mut commit = json!({
+        "timestamp": data.timestamp,
+        "subject": subject,
+        "body": body,
+        "message": data.message,
+        "author": data.author,
+        "parent_count": data.parents.len(),
+        "is_merge": data.parents.len() > 1,
+        "parents": data.parents,
+        "tree": data.tree,
+        "stats": stats_json(&data.stats),
+    });
+    if let Value::Object(fields) = &mut commit {
+        if let Some(gitmoji) = data.gitmoji {
+            fields.insert("gitmoji".to_string(), json!(gitmoji));
+        }
+        if let Some(squash_merge) = data.squash_merge {
+            fields.insert("squash_merge".to_string(), json!(squash_merge));
+        }
+        if let Some(notes) = data.notes {
+            fields.insert("notes".to_string(), json!(notes));
+        }
+        if let Some(touches_docs) = data.touches_docs {
+            fields.insert("touches_docs".to_string(), json!(touches_docs));
+        }
+        if let Some(files) = data.files {
+            fields.insert("files".to_string(), json!(files));
+        }
+    }
+    commit
+}
@@ -0,0 +1,70 @@
+use git2::Signature;
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+
+// Part of the author signature that author patterns are matched against
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthorField {
+    // "Name <email>"
+    #[default]
+    Identity,
+    Name,
+    Email,
+}
+
+impl AuthorField {
+    #[cfg(feature = "python")]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuthorField::Identity => "identity",
+            AuthorField::Name => "name",
+            AuthorField::Email => "email",
+        }
+    }
+}
+
+// "Name <email>", the string author patterns are matched against and authors
+// are grouped by. With `normalize`, both parts are NFC-normalized and their
+// whitespace trimmed and collapsed, so composed and decomposed spellings of
+// the same name compare equal.
+pub fn identity(signature: &Signature, normalize: bool) -> String {
+    let name = signature.name().unwrap_or("");
+    let email = signature.email().unwrap_or("");
+    if normalize {
+        format!("{} <{}>", normalize_part(name), normalize_part(email))
+    } else {
+        format!("{} <{}>", name, email)
+    }
+}
+
+// An identity written out in a message, such as a trailer value, in the form
+// `identity` gives signatures
+pub fn parse_identity(value: &str, normalize: bool) -> String {
+    let Some((name, rest)) = value.split_once('<') else {
+        return if normalize { normalize_part(value) } else { value.trim().to_string() };
+    };
+    let email = rest.split('>').next().unwrap_or("");
+    if normalize {
+        format!("{} <{}>", normalize_part(name), normalize_part(email))
+    } else {
+        format!("{} <{}>", name.trim(), email.trim())
+    }
+}
+
+pub fn field(signature: &Signature, field: AuthorField, normalize: bool) -> String {
+    let value = match field {
+        AuthorField::Identity => return identity(signature, normalize),
+        AuthorField::Name => signature.name().unwrap_or(""),
+        AuthorField::Email => signature.email().unwrap_or(""),
+    };
+    if normalize {
+        normalize_part(value)
+    } else {
+        value.to_string()
+    }
+}
+
+fn normalize_part(value: &str) -> String {
+    value.nfc().collect::<String>().split_whitespace().collect::<Vec<_>>().join(" ")
+}
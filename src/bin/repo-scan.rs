@@ -0,0 +1,3 @@
+fn main() {
+    std::process::exit(repo_scan_rs::cli::main());
+}
@@ -0,0 +1,158 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::Read;
+
+use git2::{Commit, Delta, Diff, ErrorCode, Odb, Oid, Repository};
+use path_slash::PathExt;
+use serde::Serialize;
+use tracing::trace;
+
+use crate::paths::extension_of;
+use crate::promisor;
+use crate::AnalyzerError;
+
+pub const DEFAULT_LARGE_BINARY_THRESHOLD: u64 = 1024 * 1024;
+
+// git calls content binary when its first 8000 bytes hold a NUL
+const SNIFF_BYTES: usize = 8000;
+
+// Extension groups used to label assets; anything else is "other"
+const ASSET_KINDS: &[(&str, &[&str])] = &[
+    ("image", &[".png", ".jpg", ".jpeg", ".gif", ".bmp", ".tiff", ".webp", ".ico", ".psd"]),
+    ("archive", &[".zip", ".tar", ".gz", ".tgz", ".bz2", ".xz", ".7z", ".rar", ".jar", ".whl"]),
+    (
+        "model",
+        &[".bin", ".pt", ".pth", ".ckpt", ".onnx", ".h5", ".pb", ".safetensors", ".gguf", ".tflite"],
+    ),
+    ("media", &[".mp3", ".mp4", ".wav", ".mov", ".avi", ".mkv", ".flac"]),
+    ("document", &[".pdf", ".docx", ".xlsx", ".pptx"]),
+];
+
+fn kind_of(ext: &str) -> &'static str {
+    ASSET_KINDS
+        .iter()
+        .find(|(_, exts)| exts.contains(&ext))
+        .map(|(kind, _)| *kind)
+        .unwrap_or("other")
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct BinaryMonth {
+    pub files: i32,
+    pub bytes: u64,
+    pub by_kind: BTreeMap<String, i32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LargeBinary {
+    pub commit: String,
+    pub timestamp: i64,
+    pub author: String,
+    pub path: String,
+    pub kind: String,
+    pub size: u64,
+    pub status: String,
+}
+
+// Binary blobs at or above the size threshold that commits added or modified
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct LargeBinaryReport {
+    pub months: BTreeMap<String, BinaryMonth>,
+    pub files: Vec<LargeBinary>,
+    // Binary verdicts of the blobs over the threshold seen so far
+    #[serde(skip)]
+    verdicts: HashMap<Oid, bool>,
+}
+
+impl LargeBinaryReport {
+    // Large binaries a commit added or modified; observe records them
+    pub fn scan(
+        &mut self,
+        repo: &Repository,
+        commit: &Commit,
+        author: &str,
+        diff: &Diff,
+        threshold: u64,
+    ) -> Result<Vec<LargeBinary>, AnalyzerError> {
+        let odb = repo.odb()?;
+        let mut files = Vec::new();
+        for delta in diff.deltas() {
+            if !matches!(delta.status(), Delta::Added | Delta::Modified) {
+                continue;
+            }
+            let new_file = delta.new_file();
+            let Some(path) = new_file.path() else {
+                continue;
+            };
+            // Reading only the object header keeps small blobs from being loaded
+            let size = match odb.read_header(new_file.id()) {
+                Ok((size, _)) => size,
+                // As for tracked files, a blob a partial clone left out is only
+                // known by its status; elsewhere it means a damaged repository
+                Err(e) if e.code() == ErrorCode::NotFound && promisor::promisor_remote(repo).is_some() => {
+                    trace!(path = %path.to_slash_lossy(), "blob not in partial clone");
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+            if (size as u64) < threshold {
+                continue;
+            }
+            let binary = match self.verdicts.get(&new_file.id()) {
+                Some(binary) => *binary,
+                None => {
+                    let binary = is_binary(repo, &odb, new_file.id())?;
+                    self.verdicts.insert(new_file.id(), binary);
+                    binary
+                }
+            };
+            if !binary {
+                continue;
+            }
+
+            files.push(LargeBinary {
+                commit: commit.id().to_string(),
+                timestamp: commit.author().when().seconds(),
+                author: author.to_string(),
+                path: path.to_slash_lossy().into_owned(),
+                kind: kind_of(&extension_of(path)).to_string(),
+                size: size as u64,
+                status: format!("{:?}", delta.status()).to_lowercase(),
+            });
+        }
+        Ok(files)
+    }
+
+    pub fn observe(&mut self, month_key: &str, files: Vec<LargeBinary>) {
+        for file in &files {
+            let month = self.months.entry(month_key.to_string()).or_default();
+            month.files += 1;
+            month.bytes += file.size;
+            *month.by_kind.entry(file.kind.clone()).or_default() += 1;
+        }
+        self.files.extend(files);
+    }
+
+    pub fn finish(&mut self) {
+        self.files.sort_by_key(|f| f.timestamp);
+    }
+}
+
+// Sniffs the start of a blob. Only loose objects can be streamed; libgit2 has
+// to inflate a packed one whole, which the caller's cache does once per blob.
+// git2's reader reports every read as filling the buffer, so exactly as many
+// bytes as the object holds are asked for, which libgit2 then does fill.
+pub fn is_binary(repo: &Repository, odb: &Odb, oid: Oid) -> Result<bool, AnalyzerError> {
+    match odb.reader(oid) {
+        Ok((mut reader, size, _)) => {
+            let mut head = vec![0; size.min(SNIFF_BYTES)];
+            reader.read_exact(&mut head).map_err(|e| git2::Error::from_str(&e.to_string()))?;
+            Ok(head.contains(&0))
+        }
+        Err(e) if e.code() == ErrorCode::NotFound => {
+            let blob = repo.find_blob(oid)?;
+            let content = blob.content();
+            Ok(content[..content.len().min(SNIFF_BYTES)].contains(&0))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::history::Replacements;
+use crate::options::AnalysisOptions;
+use crate::{open_repository, AnalyzerError, ScanMetadata};
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct BranchingMonth {
+    pub commits: i32,
+    pub merges: i32,
+    // merges / commits
+    pub merge_ratio: f64,
+    // Mean days from merge-base to merge over the branches merged this month
+    pub avg_branch_lifetime_days: Option<f64>,
+    // Most branches open at the same time during the month
+    pub max_concurrent_branches: i32,
+    #[serde(skip)]
+    lifetimes: Vec<i64>,
+}
+
+// Merge frequency, branch lifetimes and concurrency per bucket (month by
+// default). Every merged branch, one per non-first parent of a merge, is open
+// from its merge-base with the first parent until the merge.
+pub fn branching_metrics(
+    repo_path: &str,
+    options: &AnalysisOptions,
+) -> Result<(BTreeMap<String, BranchingMonth>, ScanMetadata), AnalyzerError> {
+    let repo = open_repository(repo_path, options)?;
+    let mut metadata = ScanMetadata {
+        repo_path: repo_path.to_string(),
+        ..Default::default()
+    };
+    let mut months: BTreeMap<String, BranchingMonth> = BTreeMap::new();
+    let mut branches: Vec<(i64, i64)> = Vec::new();
+
+    let replacements = Replacements::load(&repo, !options.ignore_replacements)?;
+    for oid in replacements.walk(&repo)? {
+        let oid = oid?;
+        let commit = replacements.find_commit(&repo, oid)?;
+        metadata.commits_scanned += 1;
+        if replacements.affects(oid) {
+            metadata.commits_replaced += 1;
+        }
+        let timestamp = commit.author().when().seconds();
+        if !options.matches_author(&commit.author()) || !options.in_date_range(timestamp) {
+            continue;
+        }
+        let parent_ids = replacements.parent_ids(oid, &commit);
+        metadata.record_match(parent_ids.len());
+
+        let month = months.entry(options.bucket_key(timestamp)).or_default();
+        month.commits += 1;
+        if parent_ids.len() < 2 {
+            continue;
+        }
+        month.merges += 1;
+
+        for parent in &parent_ids[1..] {
+            // Unrelated histories have no merge-base and no measurable branch
+            let Some(base) = replacements.merge_base(&repo, parent_ids[0], *parent)? else {
+                continue;
+            };
+            let start = replacements.find_commit(&repo, base)?.author().when().seconds();
+            month.lifetimes.push(timestamp - start);
+            branches.push((start, timestamp));
+        }
+    }
+
+    for month in months.values_mut() {
+        if month.commits > 0 {
+            month.merge_ratio =
+                (f64::from(month.merges) * 10000.0 / f64::from(month.commits)).round() / 10000.0;
+        }
+        if !month.lifetimes.is_empty() {
+            let mean = month.lifetimes.iter().sum::<i64>() as f64 / month.lifetimes.len() as f64;
+            month.avg_branch_lifetime_days = Some((mean / 864.0).round() / 100.0);
+        }
+    }
+
+    // Sweep over branch openings and merges, carrying the open count into
+    // every bucket each constant stretch overlaps
+    let mut events: Vec<(i64, i32)> = branches
+        .iter()
+        .flat_map(|&(start, end)| [(start, 1), (end, -1)])
+        .collect();
+    events.sort();
+    let mut open = 0;
+    for pair in events.windows(2) {
+        let ((time, change), (next_time, _)) = (pair[0], pair[1]);
+        open += change;
+        if open <= 0 || next_time <= time {
+            continue;
+        }
+        for key in buckets_between(time, next_time, options) {
+            if let Some(month) = months.get_mut(&key) {
+                month.max_concurrent_branches = month.max_concurrent_branches.max(open);
+            }
+        }
+    }
+
+    Ok((months, metadata))
+}
+
+// Keys of every bucket touched by [start, end), found a day at a time, which
+// every bucket is at least as long as
+fn buckets_between(start: i64, end: i64, options: &AnalysisOptions) -> Vec<String> {
+    const DAY: i64 = 24 * 60 * 60;
+    let mut keys: Vec<String> = Vec::new();
+    let times = (start..end).step_by(DAY as usize).chain(std::iter::once(end - 1));
+    for time in times {
+        let key = options.bucket_key(time);
+        if keys.last() != Some(&key) {
+            keys.push(key);
+        }
+    }
+    keys
+}
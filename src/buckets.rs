@@ -0,0 +1,143 @@
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Datelike, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::AnalyzerError;
+
+// Calendar unit results are bucketed by, in UTC
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Bucket {
+    // "2024-07"
+    #[default]
+    Month,
+    // ISO-8601 weeks starting on Monday, keyed by the ISO week-numbering year:
+    // 2024-12-30 falls in "2025-W01" and 2021-01-03 in "2020-W53"
+    IsoWeek,
+    // Quarters and years of a fiscal year starting in fiscal_year_start_month,
+    // named after the calendar year it ends in: with an October start,
+    // 2023-11 is in "FY24-Q1" and "FY24"
+    FiscalQuarter,
+    FiscalYear,
+}
+
+impl Bucket {
+    pub fn key(&self, timestamp: i64, fiscal_year_start_month: u32) -> String {
+        match self {
+            Bucket::Month => crate::month_key(timestamp),
+            Bucket::IsoWeek => {
+                let date: DateTime<Utc> = Utc.timestamp_opt(timestamp, 0).single().unwrap_or_default();
+                let week = date.iso_week();
+                format!("{}-W{:02}", week.year(), week.week())
+            }
+            Bucket::FiscalQuarter | Bucket::FiscalYear => {
+                let date: DateTime<Utc> = Utc.timestamp_opt(timestamp, 0).single().unwrap_or_default();
+                let start = fiscal_year_start_month - 1;
+                let year = if start > 0 && date.month0() >= start {
+                    date.year() + 1
+                } else {
+                    date.year()
+                };
+                let quarter = (date.month0() + 12 - start) % 12 / 3 + 1;
+                match self {
+                    Bucket::FiscalYear => format!("FY{:02}", year.rem_euclid(100)),
+                    _ => format!("FY{:02}-Q{}", year.rem_euclid(100), quarter),
+                }
+            }
+        }
+    }
+}
+
+// A strftime pattern bucket keys are formatted with instead of "%Y-%m", in
+// UTC like the default keys. On top of chrono's specifiers, %q is the
+// quarter (1-4), so "%Y-Q%q" gives "2024-Q3".
+#[derive(Debug, Clone)]
+pub struct BucketFormat {
+    pattern: String,
+}
+
+impl BucketFormat {
+    pub fn parse(pattern: &str) -> Result<Self, AnalyzerError> {
+        let invalid = |reason: &str| {
+            AnalyzerError::InvalidOption(format!("bucket_format {:?}: {}", pattern, reason))
+        };
+        if pattern.is_empty() {
+            return Err(invalid("must not be empty"));
+        }
+        let format = BucketFormat {
+            pattern: pattern.to_string(),
+        };
+        if StrftimeItems::new(&format.expand(1)).any(|item| matches!(item, Item::Error)) {
+            return Err(invalid("not a valid strftime pattern"));
+        }
+        Ok(format)
+    }
+
+    pub fn key(&self, timestamp: i64) -> String {
+        let date: DateTime<Utc> = Utc.timestamp_opt(timestamp, 0).single().unwrap_or_default();
+        date.format(&self.expand(date.month0() / 3 + 1)).to_string()
+    }
+
+    // The pattern with every %q replaced by the quarter; %% stays escaped
+    fn expand(&self, quarter: u32) -> String {
+        let mut expanded = String::with_capacity(self.pattern.len());
+        let mut chars = self.pattern.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                expanded.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('q') => expanded.push_str(&quarter.to_string()),
+                Some(next) => {
+                    expanded.push('%');
+                    expanded.push(next);
+                }
+                None => expanded.push('%'),
+            }
+        }
+        expanded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(date: &str) -> i64 {
+        DateTime::parse_from_rfc3339(&format!("{}T12:00:00Z", date)).unwrap().timestamp()
+    }
+
+    #[test]
+    fn month_and_iso_week_keys() {
+        assert_eq!(Bucket::Month.key(at("2024-07-15"), 1), "2024-07");
+        assert_eq!(Bucket::IsoWeek.key(at("2024-12-30"), 1), "2025-W01");
+        assert_eq!(Bucket::IsoWeek.key(at("2021-01-03"), 1), "2020-W53");
+    }
+
+    #[test]
+    fn calendar_fiscal_year() {
+        assert_eq!(Bucket::FiscalQuarter.key(at("2023-01-01"), 1), "FY23-Q1");
+        assert_eq!(Bucket::FiscalQuarter.key(at("2023-12-31"), 1), "FY23-Q4");
+        assert_eq!(Bucket::FiscalYear.key(at("2023-12-31"), 1), "FY23");
+    }
+
+    #[test]
+    fn fiscal_year_rolls_over_at_the_start_month() {
+        // An October start names the year after the calendar year it ends in
+        assert_eq!(Bucket::FiscalQuarter.key(at("2023-09-30"), 10), "FY23-Q4");
+        assert_eq!(Bucket::FiscalQuarter.key(at("2023-10-01"), 10), "FY24-Q1");
+        assert_eq!(Bucket::FiscalQuarter.key(at("2023-11-15"), 10), "FY24-Q1");
+        assert_eq!(Bucket::FiscalQuarter.key(at("2024-01-01"), 10), "FY24-Q2");
+        assert_eq!(Bucket::FiscalYear.key(at("2023-11-15"), 10), "FY24");
+        assert_eq!(Bucket::FiscalYear.key(at("2099-10-01"), 10), "FY00");
+    }
+
+    #[test]
+    fn bucket_format_expands_the_quarter() {
+        let format = BucketFormat::parse("%Y-Q%q").unwrap();
+        assert_eq!(format.key(at("2024-08-01")), "2024-Q3");
+        assert_eq!(BucketFormat::parse("%%q").unwrap().key(at("2024-08-01")), "%q");
+        assert!(BucketFormat::parse("").is_err());
+    }
+}
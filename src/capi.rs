@@ -0,0 +1,62 @@
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use serde_json::{json, Value};
+
+use crate::api::{self, Request};
+use crate::AnalyzerError;
+
+// C interface for hosts without Python, declared in include/repo_scan.h.
+// Requests and responses are the JSON documents the `repo-scan run` command
+// reads and prints; responses are wrapped as {"ok": true, "result": ...} or
+// {"ok": false, "error": "..."}.
+
+static VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), "\0");
+
+/// Runs one JSON analysis request and returns the JSON response.
+///
+/// # Safety
+///
+/// `request` must be null or point to a NUL-terminated string. The returned
+/// string is owned by the library and must be released with
+/// `repo_scan_free_string`.
+#[no_mangle]
+pub unsafe extern "C" fn repo_scan_run(request: *const c_char) -> *mut c_char {
+    let response = if request.is_null() {
+        json!({"ok": false, "error": "request is null"})
+    } else {
+        let request = CStr::from_ptr(request).to_string_lossy().into_owned();
+        // A panic must not unwind into the caller's frames
+        match catch_unwind(AssertUnwindSafe(|| run_request(&request))) {
+            Ok(Ok(result)) => json!({"ok": true, "result": result}),
+            Ok(Err(e)) => json!({"ok": false, "error": e.to_string()}),
+            Err(_) => json!({"ok": false, "error": "internal error: analysis panicked"}),
+        }
+    };
+    // serde_json escapes control characters, so the text holds no NUL byte
+    CString::new(response.to_string()).unwrap_or_default().into_raw()
+}
+
+/// Releases a string returned by `repo_scan_run`.
+///
+/// # Safety
+///
+/// `value` must be null or a pointer returned by `repo_scan_run` that has not
+/// been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn repo_scan_free_string(value: *mut c_char) {
+    if !value.is_null() {
+        drop(CString::from_raw(value));
+    }
+}
+
+/// The library version as a static NUL-terminated string.
+#[no_mangle]
+pub extern "C" fn repo_scan_version() -> *const c_char {
+    VERSION.as_ptr().cast()
+}
+
+fn run_request(request: &str) -> Result<Value, AnalyzerError> {
+    let request: Request = serde_json::from_str(request)?;
+    api::run(request)
+}
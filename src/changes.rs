@@ -0,0 +1,104 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::merge::result_parts;
+use crate::AnalyzerError;
+
+type Counters = BTreeMap<String, i64>;
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Change {
+    pub old: i64,
+    pub new: i64,
+    pub delta: i64,
+}
+
+// What changed between two stored analyze_git_repo results, e.g. the last
+// two runs of a scheduled scan
+#[derive(Debug, Default, Serialize)]
+pub struct ResultChanges {
+    pub has_changes: bool,
+    pub new_months: Vec<String>,
+    pub removed_months: Vec<String>,
+    // month -> extension -> counter, for months in both results; an
+    // extension missing from one side counts as zeros
+    pub changed: BTreeMap<String, BTreeMap<String, BTreeMap<String, Change>>>,
+    // Counters summed over every month and extension
+    pub totals: BTreeMap<String, Change>,
+    // Authors of the "contributors" section only in `new`; None unless both
+    // results are envelopes with that section
+    pub new_authors: Option<Vec<String>>,
+}
+
+pub fn diff_results(old: &Value, new: &Value) -> Result<ResultChanges, AnalyzerError> {
+    let old_months = months(old, "old")?;
+    let new_months = months(new, "new")?;
+    let mut changes = ResultChanges {
+        new_months: new_months.keys().filter(|m| !old_months.contains_key(*m)).cloned().collect(),
+        removed_months: old_months.keys().filter(|m| !new_months.contains_key(*m)).cloned().collect(),
+        ..Default::default()
+    };
+
+    let empty = BTreeMap::new();
+    for (month, new_extensions) in &new_months {
+        let Some(old_extensions) = old_months.get(month) else {
+            continue;
+        };
+        let extensions: BTreeSet<&String> = old_extensions.keys().chain(new_extensions.keys()).collect();
+        for ext in extensions {
+            let counters = compare(
+                old_extensions.get(ext).unwrap_or(&empty),
+                new_extensions.get(ext).unwrap_or(&empty),
+            );
+            if !counters.is_empty() {
+                changes.changed.entry(month.clone()).or_default().insert(ext.clone(), counters);
+            }
+        }
+    }
+    changes.totals = compare(&totals(&old_months), &totals(&new_months));
+
+    if let (Some(old_authors), Some(new_authors)) = (authors(old), authors(new)) {
+        changes.new_authors = Some(new_authors.difference(&old_authors).cloned().collect());
+    }
+    changes.has_changes = !changes.new_months.is_empty()
+        || !changes.removed_months.is_empty()
+        || !changes.changed.is_empty()
+        || changes.new_authors.as_ref().is_some_and(|authors| !authors.is_empty());
+    Ok(changes)
+}
+
+fn months(result: &Value, side: &str) -> Result<BTreeMap<String, BTreeMap<String, Counters>>, AnalyzerError> {
+    let (months, _) = result_parts(result);
+    serde_json::from_value(months.clone())
+        .map_err(|_| AnalyzerError::InvalidOption(format!("{} is not an analyze_git_repo result", side)))
+}
+
+fn totals(months: &BTreeMap<String, BTreeMap<String, Counters>>) -> Counters {
+    let mut totals = Counters::new();
+    for counters in months.values().flat_map(BTreeMap::values) {
+        for (name, value) in counters {
+            *totals.entry(name.clone()).or_default() += value;
+        }
+    }
+    totals
+}
+
+// Counters whose value differs between the two sides
+fn compare(old: &Counters, new: &Counters) -> BTreeMap<String, Change> {
+    let names: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let old = old.get(name).copied().unwrap_or(0);
+            let new = new.get(name).copied().unwrap_or(0);
+            (old != new).then(|| (name.clone(), Change { old, new, delta: new - old }))
+        })
+        .collect()
+}
+
+fn authors(result: &Value) -> Option<BTreeSet<String>> {
+    let authors = result.get("contributors")?.as_object()?;
+    Some(authors.keys().cloned().collect())
+}
@@ -0,0 +1,50 @@
+use std::collections::BTreeSet;
+
+use git2::Commit;
+use serde::Serialize;
+
+use crate::diff::FileDelta;
+
+// Monthly churn scaled by activity, so dashboards share one definition
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct ChurnMonth {
+    pub commits: i32,
+    pub active_authors: i32,
+    pub active_days: i32,
+    // additions + deletions over tracked files
+    pub churn: i64,
+    pub churn_per_author: f64,
+    pub churn_per_day: f64,
+    pub avg_commit_size: f64,
+    #[serde(skip)]
+    authors: BTreeSet<String>,
+    #[serde(skip)]
+    days: BTreeSet<i64>,
+}
+
+impl ChurnMonth {
+    pub fn observe(&mut self, commit: &Commit, author: &str, deltas: &[FileDelta]) {
+        self.commits += 1;
+        self.authors.insert(author.to_string());
+        self.days.insert(commit.author().when().seconds().div_euclid(86_400));
+        self.churn += deltas
+            .iter()
+            .map(|d| i64::from(d.additions) + i64::from(d.deletions))
+            .sum::<i64>();
+    }
+
+    pub fn finish(&mut self) {
+        self.active_authors = self.authors.len() as i32;
+        self.active_days = self.days.len() as i32;
+        self.churn_per_author = ratio(self.churn, self.active_authors);
+        self.churn_per_day = ratio(self.churn, self.active_days);
+        self.avg_commit_size = ratio(self.churn, self.commits);
+    }
+}
+
+fn ratio(churn: i64, count: i32) -> f64 {
+    if count == 0 {
+        return 0.0;
+    }
+    (churn as f64 * 100.0 / f64::from(count)).round() / 100.0
+}
@@ -0,0 +1,83 @@
+use std::collections::BTreeMap;
+
+use git2::{Commit, Diff};
+use ignore::gitignore::Gitignore;
+use path_slash::PathExt;
+use serde::Serialize;
+
+use crate::paths::{build_matcher, matches};
+use crate::AnalyzerError;
+
+// Gitignore-style patterns for CI configuration, used unless the caller
+// supplies its own `ci_patterns`
+pub const DEFAULT_CI_PATTERNS: &[&str] = &[
+    ".github/workflows/", ".github/actions/", ".gitlab-ci.yml", ".gitlab-ci/", "Jenkinsfile",
+    ".circleci/", ".travis.yml", "azure-pipelines.yml", ".azure-pipelines/",
+    "bitbucket-pipelines.yml", ".buildkite/", ".drone.yml", "appveyor.yml", ".woodpecker/",
+];
+
+pub fn ci_matcher(patterns: &[String]) -> Result<Gitignore, AnalyzerError> {
+    let result = if patterns.is_empty() {
+        build_matcher(DEFAULT_CI_PATTERNS.iter().copied())
+    } else {
+        build_matcher(patterns.iter().map(String::as_str))
+    };
+    result.map_err(|e| AnalyzerError::InvalidOption(format!("ci_patterns: {}", e)))
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct CiMonth {
+    pub commits: i32,
+    pub files_changed: i32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CiCommit {
+    pub commit: String,
+    pub timestamp: i64,
+    pub author: String,
+    pub paths: Vec<String>,
+}
+
+// Pipeline configuration churn, kept apart from product code
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct CiTimeline {
+    pub months: BTreeMap<String, CiMonth>,
+    pub commits: Vec<CiCommit>,
+}
+
+impl CiTimeline {
+    pub fn observe(
+        &mut self,
+        month_key: &str,
+        commit: &Commit,
+        author: &str,
+        diff: &Diff,
+        matcher: &Gitignore,
+    ) {
+        let paths: Vec<String> = diff
+            .deltas()
+            .filter_map(|delta| delta.new_file().path().or(delta.old_file().path()))
+            .map(|path| path.to_slash_lossy().into_owned())
+            .filter(|path| matches(matcher, path))
+            .collect();
+        if paths.is_empty() {
+            return;
+        }
+
+        let month = self.months.entry(month_key.to_string()).or_default();
+        month.commits += 1;
+        month.files_changed += paths.len() as i32;
+
+        self.commits.push(CiCommit {
+            commit: commit.id().to_string(),
+            timestamp: commit.author().when().seconds(),
+            author: author.to_string(),
+            paths,
+        });
+    }
+
+    pub fn finish(&mut self) {
+        self.commits.sort_by_key(|c| c.timestamp);
+    }
+}
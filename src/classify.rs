@@ -0,0 +1,130 @@
+use ignore::gitignore::Gitignore;
+use serde::Serialize;
+
+use std::path::Path;
+
+use crate::diff::FileDelta;
+use crate::options::{AnalysisOptions, FileDetection};
+use crate::paths::{build_matcher, extension_of, matches, TEXT_EXTENSIONS};
+use crate::AnalyzerError;
+
+pub const INFRA_BUCKET: &str = "infra";
+// Paths without an extension, e.g. scripts, Makefile, .gitignore
+pub const NO_EXTENSION_BUCKET: &str = "<none>";
+// Every file of a commit over max_files_changed with bulk_changes="separate"
+pub const BULK_BUCKET: &str = "<bulk>";
+
+// Built-in infrastructure-as-code rules; `infra_patterns` adds to these
+pub const DEFAULT_INFRA_PATTERNS: &[&str] = &[
+    "Dockerfile", "Dockerfile.*", "*.dockerfile", "Containerfile", ".dockerignore",
+    "docker-compose*.yml", "docker-compose*.yaml", "compose.yml", "compose.yaml",
+    "*.tf", "*.tfvars", "*.tf.json", "*.hcl", "terragrunt.hcl",
+    "k8s/", "kubernetes/", "kustomization.yaml", "kustomization.yml",
+    "charts/", "Chart.yaml", "helmfile.yaml", "values.yaml",
+];
+
+pub const DOCS_EXTENSIONS: &[&str] = &[".md", ".rst", ".adoc", ".asciidoc"];
+
+// Gitignore-style patterns marking test code, used unless the caller supplies
+// its own `test_patterns`
+pub const DEFAULT_TEST_PATTERNS: &[&str] = &[
+    "test/", "tests/", "__tests__/", "spec/", "testdata/",
+    "*_test.go", "test_*.py", "*_test.py", "conftest.py",
+    "*.spec.ts", "*.test.ts", "*.spec.tsx", "*.test.tsx",
+    "*.spec.js", "*.test.js", "*.spec.jsx", "*.test.jsx",
+    "*Test.java", "*Tests.java", "*_spec.rb", "*_test.rb",
+];
+
+pub fn test_matcher(patterns: &[String]) -> Result<Gitignore, AnalyzerError> {
+    let result = if patterns.is_empty() {
+        build_matcher(DEFAULT_TEST_PATTERNS.iter().copied())
+    } else {
+        build_matcher(patterns.iter().map(String::as_str))
+    };
+    result.map_err(|e| AnalyzerError::InvalidOption(format!("test_patterns: {}", e)))
+}
+
+pub fn infra_matcher(extra_patterns: &[String]) -> Result<Gitignore, AnalyzerError> {
+    build_matcher(
+        DEFAULT_INFRA_PATTERNS
+            .iter()
+            .copied()
+            .chain(extra_patterns.iter().map(String::as_str)),
+    )
+    .map_err(|e| AnalyzerError::InvalidOption(format!("infra_patterns: {}", e)))
+}
+
+// Bucket a changed path is counted under, or None if it is not tracked at all.
+// Infrastructure files go to the "infra" bucket ahead of their extension.
+// With content detection every extension is tracked here and binary content
+// is left out once it is read.
+pub fn bucket_for(path: &Path, slash_path: &str, options: &AnalysisOptions) -> Option<String> {
+    if let Some(matcher) = &options.compiled.infra_matcher {
+        if matches(matcher, slash_path) {
+            return Some(INFRA_BUCKET.to_string());
+        }
+    }
+
+    let ext = extension_of(path);
+    if ext.is_empty() && options.include_no_extension {
+        return Some(NO_EXTENSION_BUCKET.to_string());
+    }
+    match options.file_detection {
+        FileDetection::Extension => TEXT_EXTENSIONS.contains(&ext.as_str()).then_some(ext),
+        FileDetection::Content => (!ext.is_empty()).then_some(ext),
+    }
+}
+
+pub fn is_test_path(matcher: &Gitignore, path: &str) -> bool {
+    matches(matcher, path)
+}
+
+pub fn is_docs(ext: &str) -> bool {
+    DOCS_EXTENSIONS.contains(&ext)
+}
+
+// Documentation files bucket_for leaves out, e.g. .rst under extension
+// detection; only the docs section counts them
+pub fn untracked_docs_bucket(path: &Path, slash_path: &str, options: &AnalysisOptions) -> Option<String> {
+    if bucket_for(path, slash_path, options).is_some() {
+        return None;
+    }
+    let ext = extension_of(path);
+    is_docs(&ext).then_some(ext)
+}
+
+// Documentation vs code churn (additions + deletions) for one month
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct DocsMonth {
+    pub docs_churn: i32,
+    pub code_churn: i32,
+    // docs_churn / code_churn, or null when there was no code churn
+    pub docs_to_code_ratio: Option<f64>,
+    pub commits: i32,
+    pub docs_commits: i32,
+}
+
+impl DocsMonth {
+    // `docs_deltas` are the documentation files outside the tracked deltas
+    pub fn observe(&mut self, deltas: &[FileDelta], docs_deltas: &[FileDelta]) {
+        self.commits += 1;
+        let mut touched_docs = false;
+        for delta in deltas.iter().chain(docs_deltas) {
+            let churn = delta.additions + delta.deletions;
+            if is_docs(&delta.ext) {
+                self.docs_churn += churn;
+                touched_docs = true;
+            } else {
+                self.code_churn += churn;
+            }
+        }
+        if touched_docs {
+            self.docs_commits += 1;
+        }
+    }
+
+    pub fn finish(&mut self) {
+        self.docs_to_code_ratio =
+            (self.code_churn > 0).then(|| self.docs_churn as f64 / self.code_churn as f64);
+    }
+}
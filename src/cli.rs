@@ -0,0 +1,284 @@
+use std::collections::BTreeSet;
+use std::io::{Read, Write};
+
+use clap::{ArgAction, Args, Parser, Subcommand, ValueEnum};
+use serde_json::{Map, Value};
+use tracing::Level;
+
+use crate::api::{self, Request};
+use crate::options::{AnalysisOptions, OutputFormat};
+use crate::AnalyzerError;
+
+// Entry point of the `repo-scan` binary, built with `--features cli`. It runs
+// the same analyses as the Python module and prints JSON or CSV to stdout.
+#[derive(Parser)]
+#[command(name = "repo-scan", version, about = "Git history statistics by month and file extension")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+    /// Log filter decisions and timings to stderr; repeat for more detail
+    #[arg(short, long, action = ArgAction::Count, global = true)]
+    verbose: u8,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Monthly stats per extension, as analyze_git_repo
+    Repo {
+        repo_path: String,
+        #[command(flatten)]
+        common: Common,
+    },
+    /// Per-commit stats, as analyze_git_commits
+    Commits {
+        repo_path: String,
+        #[command(flatten)]
+        common: Common,
+    },
+    /// Run a JSON request such as {"analysis": "code_survival", "repo_path": "."}
+    /// read from a file, or from stdin when the path is "-"
+    Run {
+        request: String,
+        #[arg(long)]
+        pretty: bool,
+    },
+    /// Serve analysis requests over HTTP/JSON, caching results per repository state
+    #[cfg(feature = "server")]
+    Serve {
+        #[arg(long, default_value = crate::server::DEFAULT_ADDRESS)]
+        address: String,
+        #[arg(long, default_value_t = 4)]
+        workers: usize,
+        /// Results kept in memory before the oldest is dropped
+        #[arg(long, default_value_t = crate::server::DEFAULT_CACHE_ENTRIES)]
+        cache_entries: usize,
+    },
+}
+
+#[derive(Args)]
+struct Common {
+    /// AnalysisOptions as a JSON object, or @path to read them from a file
+    #[arg(long)]
+    options: Option<String>,
+    #[arg(long)]
+    since: Option<String>,
+    #[arg(long)]
+    until: Option<String>,
+    /// Author regex; may be repeated
+    #[arg(long = "author")]
+    authors: Vec<String>,
+    /// Wrap the result with metadata and report sections (JSON only)
+    #[arg(long)]
+    envelope: bool,
+    #[arg(long, value_enum, default_value_t = Format::Json)]
+    format: Format,
+    /// Show a progress bar on stderr
+    #[arg(long)]
+    progress: bool,
+    #[arg(long)]
+    pretty: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    Json,
+    Csv,
+}
+
+impl Common {
+    fn options(&self) -> Result<AnalysisOptions, AnalyzerError> {
+        let mut value = match &self.options {
+            Some(text) => serde_json::from_str(&read_argument(text)?)?,
+            None => Value::Object(Map::new()),
+        };
+        let Value::Object(fields) = &mut value else {
+            return Err(AnalyzerError::InvalidOption("--options must be a JSON object".to_string()));
+        };
+        if let Some(since) = &self.since {
+            fields.insert("since".to_string(), since.clone().into());
+        }
+        if let Some(until) = &self.until {
+            fields.insert("until".to_string(), until.clone().into());
+        }
+        if !self.authors.is_empty() {
+            fields.insert("patterns".to_string(), self.authors.clone().into());
+        }
+        if self.progress {
+            fields.insert("show_progress".to_string(), true.into());
+        }
+        if self.envelope {
+            if self.format == Format::Csv {
+                return Err(AnalyzerError::InvalidOption("--envelope only works with JSON output".to_string()));
+            }
+            fields.insert("output_format".to_string(), "envelope".into());
+        }
+        let options: AnalysisOptions = serde_json::from_value(value)?;
+        // CSV rows are built from the nested month or commit maps
+        if self.format == Format::Csv && options.output_format == OutputFormat::Envelope {
+            return Err(AnalyzerError::InvalidOption(
+                "output_format \"envelope\" only works with JSON output".to_string(),
+            ));
+        }
+        Ok(options)
+    }
+}
+
+// "@path" reads the file, "-" reads stdin, anything else is taken literally
+fn read_argument(text: &str) -> Result<String, AnalyzerError> {
+    let io_error = |source| AnalyzerError::Io {
+        context: text.to_string(),
+        source,
+    };
+    if text == "-" {
+        let mut input = String::new();
+        std::io::stdin().read_to_string(&mut input).map_err(io_error)?;
+        Ok(input)
+    } else if let Some(path) = text.strip_prefix('@') {
+        std::fs::read_to_string(path).map_err(io_error)
+    } else {
+        Ok(text.to_string())
+    }
+}
+
+pub fn main() -> i32 {
+    let cli = Cli::parse();
+    match execute(cli.command, cli.verbose) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("repo-scan: {}", e);
+            1
+        }
+    }
+}
+
+fn execute(command: Command, verbose: u8) -> Result<(), AnalyzerError> {
+    if !matches!(command, Command::Run { .. }) {
+        init_logging(verbose);
+    }
+    match command {
+        Command::Repo { repo_path, common } => {
+            let request = Request::AnalyzeGitRepo {
+                repo_path,
+                options: common.options()?,
+            };
+            let result = api::run(request)?;
+            match common.format {
+                Format::Json => print_json(&result, common.pretty),
+                Format::Csv => print_csv(&["month", "extension"], &month_rows(&result)),
+            }
+        }
+        Command::Commits { repo_path, common } => {
+            let request = Request::AnalyzeGitCommits {
+                repo_path,
+                options: common.options()?,
+            };
+            let result = api::run(request)?;
+            match common.format {
+                Format::Json => print_json(&result, common.pretty),
+                Format::Csv => print_csv(&["commit", "timestamp", "author", "extension"], &commit_rows(&result)),
+            }
+        }
+        Command::Run { request, pretty } => {
+            let source = if request == "-" { request } else { format!("@{}", request) };
+            let request: Request = serde_json::from_str(&read_argument(&source)?)?;
+            // Fleet progress is logged per repository at info level
+            init_logging(if request.options().show_progress { verbose.max(1) } else { verbose });
+            print_json(&api::run(request)?, pretty)
+        }
+        #[cfg(feature = "server")]
+        Command::Serve { address, workers, cache_entries } => {
+            crate::server::AnalysisServer::new(cache_entries).serve(&address, workers)
+        }
+    }
+}
+
+// Warnings by default, then info, debug and trace with each -v
+fn init_logging(verbose: u8) {
+    let level = match verbose {
+        0 => Level::WARN,
+        1 => Level::INFO,
+        2 => Level::DEBUG,
+        _ => Level::TRACE,
+    };
+    tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(std::io::stderr)
+        .with_target(false)
+        .without_time()
+        .init();
+}
+
+fn print_json(value: &Value, pretty: bool) -> Result<(), AnalyzerError> {
+    let text = if pretty {
+        serde_json::to_string_pretty(value)?
+    } else {
+        serde_json::to_string(value)?
+    };
+    output_result(writeln!(std::io::stdout().lock(), "{}", text))
+}
+
+fn output_result(result: std::io::Result<()>) -> Result<(), AnalyzerError> {
+    match result {
+        // The reader went away, e.g. `repo-scan ... | head`
+        Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+        result => result.map_err(|source| AnalyzerError::Io {
+            context: "writing output".to_string(),
+            source,
+        }),
+    }
+}
+
+// One row per month and extension: the key columns, then each stat
+type Row = (Vec<String>, Map<String, Value>);
+
+fn month_rows(months: &Value) -> Vec<Row> {
+    let mut rows = Vec::new();
+    for (month, extensions) in months.as_object().into_iter().flatten() {
+        for (ext, stats) in extensions.as_object().into_iter().flatten() {
+            rows.push((vec![month.clone(), ext.clone()], stats.as_object().cloned().unwrap_or_default()));
+        }
+    }
+    rows.sort_by(|a, b| a.0.cmp(&b.0));
+    rows
+}
+
+fn commit_rows(commits: &Value) -> Vec<Row> {
+    let mut rows = Vec::new();
+    for (id, commit) in commits.as_object().into_iter().flatten() {
+        let timestamp = commit["timestamp"].as_i64().unwrap_or_default().to_string();
+        let author = commit["author"].as_str().unwrap_or_default().to_string();
+        for (ext, stats) in commit["stats"].as_object().into_iter().flatten() {
+            rows.push((
+                vec![id.clone(), timestamp.clone(), author.clone(), ext.clone()],
+                stats.as_object().cloned().unwrap_or_default(),
+            ));
+        }
+    }
+    rows
+}
+
+fn print_csv(keys: &[&str], rows: &[Row]) -> Result<(), AnalyzerError> {
+    // Optional metrics vary by options, so the stat columns are their union
+    let stat_columns: BTreeSet<&String> = rows.iter().flat_map(|(_, stats)| stats.keys()).collect();
+    let mut writer = csv::Writer::from_writer(std::io::stdout().lock());
+    let written = (|| {
+        let header: Vec<&str> = keys.iter().copied().chain(stat_columns.iter().map(|c| c.as_str())).collect();
+        writer.write_record(&header)?;
+        for (key_values, stats) in rows {
+            let record = key_values.iter().cloned().chain(
+                stat_columns
+                    .iter()
+                    .map(|column| stats.get(*column).map(|v| v.to_string()).unwrap_or_default()),
+            );
+            writer.write_record(record)?;
+        }
+        Ok::<_, csv::Error>(())
+    })();
+    match written.and_then(|()| Ok(writer.flush()?)) {
+        Err(e) if matches!(e.kind(), csv::ErrorKind::Io(e) if e.kind() == std::io::ErrorKind::BrokenPipe) => Ok(()),
+        result => result.map_err(|e| AnalyzerError::Io {
+            context: "writing CSV".to_string(),
+            source: e.into(),
+        }),
+    }
+}
@@ -0,0 +1,80 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use serde::Serialize;
+
+use crate::diff::FileDelta;
+
+pub const DEFAULT_COLLABORATION_WINDOW_DAYS: u32 = 90;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CollaborationEdge {
+    pub source: String,
+    pub target: String,
+    // Files both authors changed within the window of each other
+    pub weight: i32,
+}
+
+// Undirected author-author graph as an edge list
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct CollaborationNetwork {
+    pub window_days: u32,
+    pub edges: Vec<CollaborationEdge>,
+    #[serde(skip)]
+    touches: HashMap<String, Vec<(i64, String)>>,
+}
+
+impl CollaborationNetwork {
+    pub fn new(window_days: u32) -> Self {
+        CollaborationNetwork {
+            window_days,
+            ..Default::default()
+        }
+    }
+
+    pub fn observe(&mut self, timestamp: i64, author: &str, deltas: &[FileDelta]) {
+        for delta in deltas {
+            self.touches
+                .entry(delta.path.clone())
+                .or_default()
+                .push((timestamp, author.to_string()));
+        }
+    }
+
+    pub fn finish(&mut self) {
+        let window = i64::from(self.window_days) * 86_400;
+        let mut shared: BTreeMap<(String, String), BTreeSet<&str>> = BTreeMap::new();
+        for (path, touches) in &mut self.touches {
+            touches.sort();
+            for (i, (time, author)) in touches.iter().enumerate() {
+                for (earlier, other) in touches[..i].iter().rev() {
+                    if time - earlier > window {
+                        break;
+                    }
+                    if other == author {
+                        continue;
+                    }
+                    let pair = if author < other {
+                        (author.clone(), other.clone())
+                    } else {
+                        (other.clone(), author.clone())
+                    };
+                    shared.entry(pair).or_default().insert(path);
+                }
+            }
+        }
+
+        self.edges = shared
+            .into_iter()
+            .map(|((source, target), files)| CollaborationEdge {
+                source,
+                target,
+                weight: files.len() as i32,
+            })
+            .collect();
+        self.edges.sort_by(|a, b| {
+            b.weight
+                .cmp(&a.weight)
+                .then_with(|| (&a.source, &a.target).cmp(&(&b.source, &b.target)))
+        });
+    }
+}
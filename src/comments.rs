@@ -0,0 +1,87 @@
+use crate::diff::Hunk;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineKind {
+    Code,
+    Comment,
+    Blank,
+}
+
+struct CommentSyntax {
+    line: &'static [&'static str],
+    block: Option<(&'static str, &'static str)>,
+}
+
+const C_STYLE: CommentSyntax = CommentSyntax { line: &["//"], block: Some(("/*", "*/")) };
+const HASH: CommentSyntax = CommentSyntax { line: &["#"], block: None };
+const MARKUP: CommentSyntax = CommentSyntax { line: &[], block: Some(("<!--", "-->")) };
+const NONE: CommentSyntax = CommentSyntax { line: &[], block: None };
+
+fn syntax_for(ext: &str) -> CommentSyntax {
+    match ext {
+        ".rs" | ".js" | ".ts" | ".jsx" | ".tsx" | ".c" | ".cpp" | ".h" | ".hpp" | ".java"
+        | ".go" | ".scss" => C_STYLE,
+        ".css" => CommentSyntax { line: &[], block: Some(("/*", "*/")) },
+        ".php" => CommentSyntax { line: &["//", "#"], block: Some(("/*", "*/")) },
+        ".py" | ".rb" | ".yaml" | ".yml" | ".toml" => HASH,
+        ".html" | ".md" => MARKUP,
+        _ => NONE,
+    }
+}
+
+// Classifies a run of consecutive lines. Block comments are tracked across the
+// run, but since diffs only show fragments of a file, a hunk that starts inside
+// a block comment is only recognised through the leading `*` convention.
+pub fn classify(lines: &[String], ext: &str) -> Vec<LineKind> {
+    let syntax = syntax_for(ext);
+    let mut in_block = false;
+
+    lines
+        .iter()
+        .map(|line| {
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                return LineKind::Blank;
+            }
+
+            if let Some((start, end)) = syntax.block {
+                if in_block {
+                    if trimmed.contains(end) {
+                        in_block = false;
+                    }
+                    return LineKind::Comment;
+                }
+                if let Some(rest) = trimmed.strip_prefix(start) {
+                    in_block = !rest.contains(end);
+                    return LineKind::Comment;
+                }
+                if start == "/*" && (trimmed.starts_with("* ") || trimmed == "*" || trimmed == "*/") {
+                    return LineKind::Comment;
+                }
+            }
+
+            if syntax.line.iter().any(|prefix| trimmed.starts_with(prefix)) {
+                LineKind::Comment
+            } else {
+                LineKind::Code
+            }
+        })
+        .collect()
+}
+
+// (code, comment, blank) counts for the added and the removed side of a file
+pub fn count_kinds(hunks: &[Hunk], ext: &str) -> ([i32; 3], [i32; 3]) {
+    let mut added = [0; 3];
+    let mut removed = [0; 3];
+
+    for hunk in hunks {
+        for kind in classify(&hunk.added, ext) {
+            added[kind as usize] += 1;
+        }
+        for kind in classify(&hunk.removed, ext) {
+            removed[kind as usize] += 1;
+        }
+    }
+
+    (added, removed)
+}
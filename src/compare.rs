@@ -0,0 +1,167 @@
+use std::collections::{BTreeMap, HashSet};
+
+use serde::Serialize;
+
+use crate::authors;
+use crate::diff::{commit_deltas, parent_diff};
+use crate::history::Replacements;
+use crate::options::{parse_date, AnalysisOptions};
+use crate::paths::PathFilter;
+use crate::{open_repository, stats_by_extension, AnalyzerError, ScanMetadata};
+
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+struct Totals {
+    commits: i64,
+    additions: i64,
+    deletions: i64,
+    churn: i64,
+}
+
+impl Totals {
+    fn add(&mut self, additions: i32, deletions: i32) {
+        self.additions += i64::from(additions);
+        self.deletions += i64::from(deletions);
+        self.churn += i64::from(additions) + i64::from(deletions);
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Delta {
+    pub a: i64,
+    pub b: i64,
+    // (b - a) / a in percent, or null when a is zero
+    pub delta_pct: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Window {
+    pub since: String,
+    pub until: String,
+    pub commits: i64,
+}
+
+// Per-extension and per-author changes from window_a to window_b
+#[derive(Debug, Clone, Serialize)]
+pub struct WindowComparison {
+    pub window_a: Window,
+    pub window_b: Window,
+    pub extensions: BTreeMap<String, BTreeMap<&'static str, Delta>>,
+    pub authors: BTreeMap<String, BTreeMap<&'static str, Delta>>,
+}
+
+// Both windows are filled in one walk, so the same filters and diff settings
+// apply to each. Windows may overlap.
+pub fn compare_windows(
+    repo_path: &str,
+    options: &AnalysisOptions,
+    window_a: (&str, &str),
+    window_b: (&str, &str),
+) -> Result<(WindowComparison, ScanMetadata), AnalyzerError> {
+    let ranges = [
+        (parse_date(window_a.0, false)?, parse_date(window_a.1, true)?),
+        (parse_date(window_b.0, false)?, parse_date(window_b.1, true)?),
+    ];
+    if ranges.iter().any(|(since, until)| since > until) {
+        return Err(AnalyzerError::InvalidOption(
+            "window start must not be later than its end".to_string(),
+        ));
+    }
+
+    let repo = open_repository(repo_path, options)?;
+    let path_filter = PathFilter::for_repo(&repo, options.use_ignore_file)?;
+    let mut metadata = ScanMetadata {
+        repo_path: repo_path.to_string(),
+        ..Default::default()
+    };
+    let mut commits = [0i64; 2];
+    let mut extensions: [BTreeMap<String, Totals>; 2] = Default::default();
+    let mut authors: [BTreeMap<String, Totals>; 2] = Default::default();
+
+    let replacements = Replacements::load(&repo, !options.ignore_replacements)?;
+    for oid in replacements.walk(&repo)? {
+        let oid = oid?;
+        let commit = replacements.find_commit(&repo, oid)?;
+        metadata.commits_scanned += 1;
+        if replacements.affects(oid) {
+            metadata.commits_replaced += 1;
+        }
+        let parent_count = replacements.parent_ids(oid, &commit).len();
+        let timestamp = commit.author().when().seconds();
+        let windows: Vec<usize> = (0..2)
+            .filter(|&i| timestamp >= ranges[i].0 && timestamp <= ranges[i].1)
+            .collect();
+        if windows.is_empty()
+            || !options.matches_author(&commit.author())
+            || !options.in_date_range(timestamp)
+            || options.skips_commit(parent_count)
+        {
+            continue;
+        }
+        metadata.record_match(parent_count);
+
+        let author = authors::identity(&commit.author(), options.normalize_authors);
+        let parents = replacements.parents(&repo, oid, &commit);
+        let diff = parent_diff(&repo, parents.first(), &commit, options)?;
+        let deltas = commit_deltas(&repo, &commit, &parents, &diff, &path_filter, options)?;
+        let touched: HashSet<&str> = deltas.iter().map(|d| d.ext.as_str()).collect();
+        let stats = stats_by_extension(&deltas);
+
+        for i in windows {
+            commits[i] += 1;
+            let author_totals = authors[i].entry(author.clone()).or_default();
+            author_totals.commits += 1;
+            for (ext, ext_stats) in &stats {
+                extensions[i].entry(ext.clone()).or_default().add(ext_stats.additions, ext_stats.deletions);
+                author_totals.add(ext_stats.additions, ext_stats.deletions);
+            }
+            for ext in &touched {
+                extensions[i].entry(ext.to_string()).or_default().commits += 1;
+            }
+        }
+    }
+
+    let [extensions_a, extensions_b] = extensions;
+    let [authors_a, authors_b] = authors;
+    let comparison = WindowComparison {
+        window_a: Window {
+            since: window_a.0.to_string(),
+            until: window_a.1.to_string(),
+            commits: commits[0],
+        },
+        window_b: Window {
+            since: window_b.0.to_string(),
+            until: window_b.1.to_string(),
+            commits: commits[1],
+        },
+        extensions: deltas_between(extensions_a, extensions_b),
+        authors: deltas_between(authors_a, authors_b),
+    };
+    Ok((comparison, metadata))
+}
+
+fn deltas_between(
+    a: BTreeMap<String, Totals>,
+    mut b: BTreeMap<String, Totals>,
+) -> BTreeMap<String, BTreeMap<&'static str, Delta>> {
+    let mut keys: Vec<String> = a.keys().chain(b.keys()).cloned().collect();
+    keys.sort();
+    keys.dedup();
+    keys.into_iter()
+        .map(|key| {
+            let before = a.get(&key).copied().unwrap_or_default();
+            let after = b.remove(&key).unwrap_or_default();
+            let metrics = BTreeMap::from([
+                ("commits", delta(before.commits, after.commits)),
+                ("additions", delta(before.additions, after.additions)),
+                ("deletions", delta(before.deletions, after.deletions)),
+                ("churn", delta(before.churn, after.churn)),
+            ]);
+            (key, metrics)
+        })
+        .collect()
+}
+
+fn delta(a: i64, b: i64) -> Delta {
+    let delta_pct = (a != 0).then(|| ((b - a) as f64 * 10000.0 / a as f64).round() / 100.0);
+    Delta { a, b, delta_pct }
+}
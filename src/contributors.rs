@@ -0,0 +1,186 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::Utc;
+use git2::Commit;
+use serde::Serialize;
+
+use crate::diff::FileDelta;
+
+pub const DEFAULT_DORMANCY_MONTHS: u32 = 6;
+
+// Average Gregorian month, used to turn the dormancy window into seconds
+const SECONDS_PER_MONTH: i64 = 2_629_746;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CommitRef {
+    pub commit: String,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Contributor {
+    pub commits: i32,
+    pub additions: i64,
+    pub deletions: i64,
+    pub first_commit: CommitRef,
+    pub last_commit: CommitRef,
+    // No commit within the last dormancy_months, counted back from now
+    pub dormant: bool,
+}
+
+// Per-author activity, for offboarding and inactive-access audits
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct ContributorReport {
+    #[serde(flatten)]
+    pub authors: BTreeMap<String, Contributor>,
+}
+
+impl ContributorReport {
+    pub fn observe(&mut self, commit: &Commit, author: &str, deltas: &[FileDelta]) {
+        let current = CommitRef {
+            commit: commit.id().to_string(),
+            timestamp: commit.author().when().seconds(),
+        };
+        let additions: i64 = deltas.iter().map(|d| i64::from(d.additions)).sum();
+        let deletions: i64 = deltas.iter().map(|d| i64::from(d.deletions)).sum();
+
+        let contributor = self.authors.entry(author.to_string()).or_insert_with(|| Contributor {
+            commits: 0,
+            additions: 0,
+            deletions: 0,
+            first_commit: current.clone(),
+            last_commit: current.clone(),
+            dormant: false,
+        });
+        contributor.commits += 1;
+        contributor.additions += additions;
+        contributor.deletions += deletions;
+        if current.timestamp < contributor.first_commit.timestamp {
+            contributor.first_commit = current.clone();
+        }
+        if current.timestamp > contributor.last_commit.timestamp {
+            contributor.last_commit = current;
+        }
+    }
+
+    pub fn finish(&mut self, dormancy_months: u32) {
+        let cutoff = Utc::now().timestamp() - i64::from(dormancy_months) * SECONDS_PER_MONTH;
+        for contributor in self.authors.values_mut() {
+            contributor.dormant = contributor.last_commit.timestamp < cutoff;
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct NewContributorMonth {
+    pub active_authors: i32,
+    // Authors whose first commit in the scanned history falls in this month
+    pub new_authors: i32,
+    pub returning_authors: i32,
+    pub new_churn: i64,
+    pub returning_churn: i64,
+    // new_churn as a percentage of the month's churn
+    pub new_churn_pct: f64,
+}
+
+// First-time vs returning contributors per month. The walk runs newest first,
+// so activity is buffered and replayed in date order at the end.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct NewContributors {
+    #[serde(flatten)]
+    pub months: BTreeMap<String, NewContributorMonth>,
+    #[serde(skip)]
+    activity: Vec<(i64, String, String, i64)>,
+}
+
+impl NewContributors {
+    pub fn observe(&mut self, timestamp: i64, month_key: &str, author: &str, deltas: &[FileDelta]) {
+        let churn = deltas.iter().map(|d| i64::from(d.additions) + i64::from(d.deletions)).sum();
+        self.activity.push((timestamp, month_key.to_string(), author.to_string(), churn));
+    }
+
+    pub fn finish(&mut self) {
+        self.activity.sort();
+        let mut first_month: BTreeMap<String, String> = BTreeMap::new();
+        let mut seen: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        for (_, month, author, churn) in std::mem::take(&mut self.activity) {
+            let first = first_month.entry(author.clone()).or_insert_with(|| month.clone());
+            let is_new = *first == month;
+            let entry = self.months.entry(month.clone()).or_default();
+            if seen.entry(month).or_default().insert(author) {
+                entry.active_authors += 1;
+                if is_new {
+                    entry.new_authors += 1;
+                } else {
+                    entry.returning_authors += 1;
+                }
+            }
+            if is_new {
+                entry.new_churn += churn;
+            } else {
+                entry.returning_churn += churn;
+            }
+        }
+        for month in self.months.values_mut() {
+            let total = month.new_churn + month.returning_churn;
+            if total > 0 {
+                month.new_churn_pct = (month.new_churn as f64 * 10000.0 / total as f64).round() / 100.0;
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct GrowthMonth {
+    // Distinct authors seen up to and including this month
+    pub contributors: usize,
+    pub new_contributors: usize,
+    // Per extension, authors who changed such a file for the first time this
+    // month, and the running total of distinct authors per extension
+    pub new_by_extension: BTreeMap<String, usize>,
+    pub contributors_by_extension: BTreeMap<String, usize>,
+}
+
+// Running total of distinct contributors, for community-growth curves.
+// Buffered like NewContributors and replayed in date order.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct ContributorGrowth {
+    #[serde(flatten)]
+    pub months: BTreeMap<String, GrowthMonth>,
+    #[serde(skip)]
+    activity: Vec<(i64, String, String, BTreeSet<String>)>,
+}
+
+impl ContributorGrowth {
+    pub fn observe(&mut self, timestamp: i64, month_key: &str, author: &str, deltas: &[FileDelta]) {
+        let extensions = deltas.iter().map(|d| d.ext.clone()).collect();
+        self.activity.push((timestamp, month_key.to_string(), author.to_string(), extensions));
+    }
+
+    pub fn finish(&mut self) {
+        self.activity.sort();
+        let mut seen: BTreeSet<String> = BTreeSet::new();
+        let mut seen_by_extension: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        for (_, month, author, extensions) in std::mem::take(&mut self.activity) {
+            let entry = self.months.entry(month).or_default();
+            if seen.insert(author.clone()) {
+                entry.new_contributors += 1;
+            }
+            for ext in extensions {
+                let authors = seen_by_extension.entry(ext.clone()).or_default();
+                if authors.insert(author.clone()) {
+                    *entry.new_by_extension.entry(ext.clone()).or_default() += 1;
+                }
+                entry.contributors_by_extension.insert(ext, authors.len());
+            }
+            entry.contributors = seen.len();
+        }
+        // Fill in extensions not changed in a month so every month is a
+        // complete snapshot, as in cumulative_lines
+        let mut running: BTreeMap<String, usize> = BTreeMap::new();
+        for month in self.months.values_mut() {
+            running.extend(std::mem::take(&mut month.contributors_by_extension));
+            month.contributors_by_extension = running.clone();
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyDict, PyFloat, PyList, PyLong, PyString, PyTuple};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Number, Value};
+
+// Conversions between Python objects and serde_json values, so that every
+// serde-derived struct in this crate can cross the Python boundary the same way.
+
+pub fn json_to_py(py: Python<'_>, value: &Value) -> PyObject {
+    match value {
+        Value::Null => py.None(),
+        Value::Bool(b) => b.into_py(py),
+        Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                i.into_py(py)
+            } else if let Some(u) = n.as_u64() {
+                u.into_py(py)
+            } else {
+                n.as_f64().unwrap_or_default().into_py(py)
+            }
+        }
+        Value::String(s) => s.into_py(py),
+        Value::Array(items) => {
+            let list = PyList::empty(py);
+            for item in items {
+                // Appending to a freshly created list cannot fail
+                let _ = list.append(json_to_py(py, item));
+            }
+            list.into_py(py)
+        }
+        Value::Object(map) => {
+            let dict = PyDict::new(py);
+            for (key, item) in map {
+                let _ = dict.set_item(key, json_to_py(py, item));
+            }
+            dict.into_py(py)
+        }
+    }
+}
+
+pub fn py_to_json(obj: &PyAny) -> PyResult<Value> {
+    if obj.is_none() {
+        return Ok(Value::Null);
+    }
+    // bool must be checked before int, since Python bools are ints
+    if let Ok(b) = obj.downcast::<PyBool>() {
+        return Ok(Value::Bool(b.is_true()));
+    }
+    if obj.downcast::<PyLong>().is_ok() {
+        return Ok(Value::Number(Number::from(obj.extract::<i64>()?)));
+    }
+    if let Ok(f) = obj.downcast::<PyFloat>() {
+        return Number::from_f64(f.value())
+            .map(Value::Number)
+            .ok_or_else(|| PyValueError::new_err("NaN and infinite floats are not supported"));
+    }
+    if let Ok(s) = obj.downcast::<PyString>() {
+        return Ok(Value::String(s.to_str()?.to_string()));
+    }
+    if let Ok(dict) = obj.downcast::<PyDict>() {
+        let mut map = Map::new();
+        for (key, item) in dict {
+            let key: String = key
+                .extract()
+                .map_err(|_| PyValueError::new_err("dictionary keys must be strings"))?;
+            map.insert(key, py_to_json(item)?);
+        }
+        return Ok(Value::Object(map));
+    }
+    if let Ok(list) = obj.downcast::<PyList>() {
+        return list.iter().map(py_to_json).collect::<PyResult<_>>().map(Value::Array);
+    }
+    if let Ok(tuple) = obj.downcast::<PyTuple>() {
+        return tuple.iter().map(py_to_json).collect::<PyResult<_>>().map(Value::Array);
+    }
+    Err(PyValueError::new_err(format!(
+        "unsupported value of type {}",
+        obj.get_type().name()?
+    )))
+}
+
+pub fn to_py<T: Serialize>(py: Python<'_>, value: &T) -> PyResult<PyObject> {
+    let value = serde_json::to_value(value).map_err(|e| PyValueError::new_err(e.to_string()))?;
+    Ok(json_to_py(py, &value))
+}
+
+pub fn from_py<T: DeserializeOwned>(obj: &PyAny) -> PyResult<T> {
+    serde_json::from_value(py_to_json(obj)?).map_err(|e| PyValueError::new_err(e.to_string()))
+}
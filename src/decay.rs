@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::diff::FileDelta;
+use crate::markers::directory_of;
+
+pub const DEFAULT_HALF_LIFE_DAYS: f64 = 90.0;
+
+// Scores below this are left out of the report
+const MIN_SCORE: f64 = 0.01;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DecayedScore {
+    pub name: String,
+    pub score: f64,
+}
+
+// Churn (additions plus deletions) with each commit's share halved for every
+// half_life_days it is older than the newest commit scanned, per file,
+// directory and author, highest first
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct DecayedChurn {
+    pub half_life_days: f64,
+    // Timestamp scores are relative to: the newest commit scanned
+    pub reference: i64,
+    pub files: Vec<DecayedScore>,
+    pub directories: Vec<DecayedScore>,
+    pub authors: Vec<DecayedScore>,
+    #[serde(skip)]
+    scores: [HashMap<String, f64>; 3],
+    #[serde(skip)]
+    started: bool,
+}
+
+impl DecayedChurn {
+    pub fn new(half_life_days: f64) -> Self {
+        DecayedChurn {
+            half_life_days,
+            ..Default::default()
+        }
+    }
+
+    pub fn observe(&mut self, timestamp: i64, author: &str, deltas: &[FileDelta]) {
+        let half_life = self.half_life_days * 86_400.0;
+        // The walk runs newest first, so this only rescales when commits
+        // come out of date order
+        if !self.started {
+            self.started = true;
+            self.reference = timestamp;
+        } else if timestamp > self.reference {
+            let factor = 0.5f64.powf((timestamp - self.reference) as f64 / half_life);
+            for scores in &mut self.scores {
+                scores.values_mut().for_each(|score| *score *= factor);
+            }
+            self.reference = timestamp;
+        }
+        let weight = 0.5f64.powf((self.reference - timestamp) as f64 / half_life);
+
+        let mut total = 0.0;
+        for delta in deltas {
+            let churn = f64::from(delta.additions + delta.deletions) * weight;
+            if churn == 0.0 {
+                continue;
+            }
+            total += churn;
+            *self.scores[0].entry(delta.path.clone()).or_default() += churn;
+            *self.scores[1].entry(directory_of(&delta.path)).or_default() += churn;
+        }
+        if total > 0.0 {
+            *self.scores[2].entry(author.to_string()).or_default() += total;
+        }
+    }
+
+    pub fn finish(&mut self) {
+        let [files, directories, authors] = std::mem::take(&mut self.scores);
+        self.files = ranked(files);
+        self.directories = ranked(directories);
+        self.authors = ranked(authors);
+    }
+}
+
+fn ranked(scores: HashMap<String, f64>) -> Vec<DecayedScore> {
+    let mut ranked: Vec<DecayedScore> = scores
+        .into_iter()
+        .map(|(name, score)| DecayedScore {
+            name,
+            score: (score * 100.0).round() / 100.0,
+        })
+        .filter(|entry| entry.score >= MIN_SCORE)
+        .collect();
+    ranked.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.name.cmp(&b.name)));
+    ranked
+}
@@ -0,0 +1,164 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use git2::{Diff, Patch};
+use regex::Regex;
+use serde::Serialize;
+
+use crate::AnalyzerError;
+
+pub const DEFAULT_MANIFESTS: &[&str] = &[
+    "Cargo.toml", "package.json", "requirements.txt", "go.mod", "pyproject.toml", "Gemfile",
+];
+
+// Keys of a Cargo.toml [package] table that look like dependency lines
+const CARGO_PACKAGE_KEYS: &[&str] = &[
+    "name", "version", "edition", "authors", "description", "license", "repository",
+    "homepage", "readme", "keywords", "categories", "publish", "build", "rust-version",
+    "crate-type", "path", "default", "members", "exclude", "resolver",
+];
+
+// Dependency-file activity for one month
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct DependencyMonth {
+    pub commits: i32,
+    pub files_changed: i32,
+    pub by_manifest: BTreeMap<String, i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub added: Option<BTreeSet<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub removed: Option<BTreeSet<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated: Option<BTreeSet<String>>,
+}
+
+// Manifest changes found in one commit
+#[derive(Default)]
+pub struct ManifestChanges {
+    // File name of each manifest changed
+    files: Vec<String>,
+    added: BTreeSet<String>,
+    removed: BTreeSet<String>,
+}
+
+pub struct DependencyTracker {
+    manifests: Vec<String>,
+    parse_names: bool,
+    patterns: ManifestPatterns,
+}
+
+impl DependencyTracker {
+    pub fn new(manifests: &[String], parse_names: bool) -> Self {
+        DependencyTracker {
+            manifests: manifests.to_vec(),
+            parse_names,
+            patterns: ManifestPatterns::new(),
+        }
+    }
+
+    pub fn changes(&self, diff: &Diff) -> Result<ManifestChanges, AnalyzerError> {
+        let mut changes = ManifestChanges::default();
+
+        for (idx, delta) in diff.deltas().enumerate() {
+            let Some(path) = delta.new_file().path().or(delta.old_file().path()) else {
+                continue;
+            };
+            let Some(file_name) = Path::new(path).file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            if !self.manifests.iter().any(|m| m == file_name) {
+                continue;
+            }
+
+            changes.files.push(file_name.to_string());
+
+            if !self.parse_names {
+                continue;
+            }
+            if let Some(patch) = Patch::from_diff(diff, idx)? {
+                for hunk_idx in 0..patch.num_hunks() {
+                    for line_idx in 0..patch.num_lines_in_hunk(hunk_idx)? {
+                        let line = patch.line_in_hunk(hunk_idx, line_idx)?;
+                        let content = String::from_utf8_lossy(line.content());
+                        let Some(name) = self.patterns.dependency_name(file_name, &content) else {
+                            continue;
+                        };
+                        match line.origin() {
+                            '+' => changes.added.insert(name),
+                            '-' => changes.removed.insert(name),
+                            _ => false,
+                        };
+                    }
+                }
+            }
+        }
+
+        Ok(changes)
+    }
+
+    pub fn observe(&self, month: &mut DependencyMonth, changes: ManifestChanges) {
+        if !changes.files.is_empty() {
+            month.commits += 1;
+        }
+        for file_name in changes.files {
+            month.files_changed += 1;
+            *month.by_manifest.entry(file_name).or_default() += 1;
+        }
+        if self.parse_names {
+            // A name both removed and added within one commit is a version change
+            let (added, removed) = (changes.added, changes.removed);
+            let updated: BTreeSet<String> = added.intersection(&removed).cloned().collect();
+            month.added.get_or_insert_with(BTreeSet::new).extend(added.difference(&updated).cloned());
+            month.removed.get_or_insert_with(BTreeSet::new).extend(removed.difference(&updated).cloned());
+            month.updated.get_or_insert_with(BTreeSet::new).extend(updated);
+        }
+    }
+}
+
+// Line-level heuristics for the built-in manifest formats. Diff hunks rarely
+// show which table or block a line belongs to, so these match on the shape of
+// the line alone.
+struct ManifestPatterns {
+    toml_entry: Regex,
+    json_entry: Regex,
+    requirement: Regex,
+    go_require: Regex,
+    quoted_requirement: Regex,
+    gem: Regex,
+}
+
+impl ManifestPatterns {
+    fn new() -> Self {
+        ManifestPatterns {
+            toml_entry: Regex::new(r#"^\s*([A-Za-z0-9_-]+)\s*=\s*["{]"#).expect("valid regex"),
+            json_entry: Regex::new(r#"^\s*"(@?[A-Za-z0-9_.\-/]+)"\s*:\s*"(\^|~|>|<|=|\*|\d|latest|workspace:|npm:|git|file:)"#)
+                .expect("valid regex"),
+            requirement: Regex::new(r"^\s*([A-Za-z0-9][A-Za-z0-9_.\-]*)\s*(\[[^\]]*\])?\s*([=<>!~]=?|;|$)")
+                .expect("valid regex"),
+            go_require: Regex::new(r"^\s*(?:require\s+)?([A-Za-z0-9_.\-]+\.[A-Za-z]+/[A-Za-z0-9_.\-/]+)\s+v\d")
+                .expect("valid regex"),
+            quoted_requirement: Regex::new(r#"^\s*"([A-Za-z0-9][A-Za-z0-9_.\-]*)\s*(\[[^\]]*\])?\s*([=<>!~]=?[^"]*)?","#)
+                .expect("valid regex"),
+            gem: Regex::new(r#"^\s*gem\s+["']([A-Za-z0-9_.\-]+)["']"#).expect("valid regex"),
+        }
+    }
+
+    fn dependency_name(&self, manifest: &str, line: &str) -> Option<String> {
+        let captures = match manifest {
+            "Cargo.toml" => self
+                .toml_entry
+                .captures(line)
+                .filter(|c| !CARGO_PACKAGE_KEYS.contains(&&c[1])),
+            "package.json" => self
+                .json_entry
+                .captures(line)
+                .filter(|c| !matches!(&c[1], "version" | "node" | "npm")),
+            "requirements.txt" => self.requirement.captures(line),
+            "go.mod" => self.go_require.captures(line),
+            "pyproject.toml" => self.quoted_requirement.captures(line),
+            "Gemfile" => self.gem.captures(line),
+            _ => None,
+        }?;
+        Some(captures[1].to_string())
+    }
+}
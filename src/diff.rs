@@ -0,0 +1,519 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+use git2::{Commit, Delta, Diff, DiffFindOptions, DiffOptions, ErrorCode, Patch, Repository, Tree};
+use path_slash::PathExt;
+use serde::Serialize;
+use tracing::{debug, trace};
+
+use crate::classify;
+use crate::comments;
+use crate::formatting::{self, FormattingChange};
+use crate::markers;
+use crate::options::{AnalysisOptions, FileDetection, MergeDiff};
+use crate::paths::{extension_of, PathFilter};
+use crate::promisor;
+use crate::words;
+use crate::AnalyzerError;
+
+// One changed file of a commit whose extension is tracked
+#[derive(Debug, Clone)]
+pub struct FileDelta {
+    pub path: String,
+    pub ext: String,
+    pub status: Delta,
+    // Previous path of a renamed file, only set when detect_renames is on
+    pub old_path: Option<String>,
+    pub is_binary: bool,
+    pub additions: i32,
+    pub deletions: i32,
+    // Whether libgit2 produced any hunks; binary and mode-only changes have none
+    pub has_hunks: bool,
+    // Changed line content, only collected when a line-level mode needs it
+    pub hunks: Vec<Hunk>,
+    // Optional counters reported next to the base stats, keyed by output name
+    pub metrics: BTreeMap<&'static str, i32>,
+    // A blob left out of a partial clone; only the status is known
+    pub content_missing: bool,
+}
+
+// File-level view of a delta as listed per commit by `include_files`
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangedFile {
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_path: Option<String>,
+    pub status: &'static str,
+    pub additions: i32,
+    pub deletions: i32,
+}
+
+impl From<&FileDelta> for ChangedFile {
+    fn from(delta: &FileDelta) -> Self {
+        ChangedFile {
+            path: delta.path.clone(),
+            old_path: delta.old_path.clone(),
+            status: status_name(delta.status),
+            additions: delta.additions,
+            deletions: delta.deletions,
+        }
+    }
+}
+
+pub fn status_name(status: Delta) -> &'static str {
+    match status {
+        Delta::Added => "added",
+        Delta::Deleted => "deleted",
+        Delta::Modified => "modified",
+        Delta::Renamed => "renamed",
+        Delta::Copied => "copied",
+        Delta::Typechange => "typechange",
+        _ => "other",
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Hunk {
+    pub removed: Vec<String>,
+    pub added: Vec<String>,
+}
+
+// Builds the libgit2 diff options for a scan. Pathspecs are applied here so
+// libgit2 drops uninteresting deltas before any patch is generated.
+pub fn diff_options(options: &AnalysisOptions) -> DiffOptions {
+    let mut diff_opts = diff_knobs(options);
+    for pathspec in &options.pathspecs {
+        diff_opts.pathspec(pathspec);
+    }
+    diff_opts
+}
+
+fn diff_knobs(options: &AnalysisOptions) -> DiffOptions {
+    let mut diff_opts = DiffOptions::new();
+    diff_opts
+        .ignore_whitespace(options.ignore_whitespace)
+        .ignore_whitespace_change(options.ignore_whitespace_change)
+        .ignore_blank_lines(options.ignore_blank_lines)
+        .include_typechange(options.include_typechange);
+    if let Some(context_lines) = options.context_lines {
+        diff_opts.context_lines(context_lines);
+    }
+    diff_opts
+}
+
+// Diff of a commit against its first parent, which history rewrites by
+// replace refs or grafts may have changed
+pub fn parent_diff<'r>(
+    repo: &'r Repository,
+    parent: Option<&Commit>,
+    commit: &Commit,
+    options: &AnalysisOptions,
+) -> Result<Diff<'r>, AnalyzerError> {
+    // Handle both first commit and subsequent commits
+    let diff = if let Some(parent) = parent {
+        // Normal case - diff against parent
+        tree_diff(repo, Some(&parent.tree()?), &commit.tree()?, options)?
+    } else {
+        // First commit - diff against empty tree
+        tree_diff(repo, None, &commit.tree()?, options)?
+    };
+    if options.fetch_missing_objects {
+        if let Some(remote) = promisor::promisor_remote(repo) {
+            promisor::fetch_missing(repo, &remote, &diff)?;
+        }
+    }
+    Ok(diff)
+}
+
+fn tree_diff<'r>(
+    repo: &'r Repository,
+    old: Option<&Tree>,
+    new: &Tree,
+    options: &AnalysisOptions,
+) -> Result<Diff<'r>, AnalyzerError> {
+    tree_diff_with(repo, old, new, diff_options(options), options)
+}
+
+fn tree_diff_with<'r>(
+    repo: &'r Repository,
+    old: Option<&Tree>,
+    new: &Tree,
+    mut diff_opts: DiffOptions,
+    options: &AnalysisOptions,
+) -> Result<Diff<'r>, AnalyzerError> {
+    let mut diff = repo.diff_tree_to_tree(old, Some(new), Some(&mut diff_opts))?;
+    if options.detect_renames {
+        // A rename is then one delta under the new path instead of a
+        // deletion plus an addition
+        diff.find_similar(Some(DiffFindOptions::new().renames(true)))?;
+    }
+    Ok(diff)
+}
+
+// Picks the files a delta list covers and the bucket each is counted under
+type Selector = fn(&Path, &str, &AnalysisOptions) -> Option<String>;
+
+// Tracked file changes of a commit, `diff` being the diff against the first of
+// `parents`. Merges are compared against their first parent unless
+// `merge_diff="all_parents"`, in which case only lines differing from every
+// parent are kept, as `git show --cc` does.
+pub fn commit_deltas(
+    repo: &Repository,
+    commit: &Commit,
+    parents: &[Commit],
+    diff: &Diff,
+    filter: &PathFilter,
+    options: &AnalysisOptions,
+) -> Result<Vec<FileDelta>, AnalyzerError> {
+    selected_deltas(repo, commit, parents, diff, filter, options, classify::bucket_for)
+}
+
+// Changes to documentation files commit_deltas does not track, diffed the
+// same way, for the docs section
+pub fn docs_deltas(
+    repo: &Repository,
+    commit: &Commit,
+    parents: &[Commit],
+    diff: &Diff,
+    filter: &PathFilter,
+    options: &AnalysisOptions,
+) -> Result<Vec<FileDelta>, AnalyzerError> {
+    selected_deltas(repo, commit, parents, diff, filter, options, classify::untracked_docs_bucket)
+}
+
+fn selected_deltas(
+    repo: &Repository,
+    commit: &Commit,
+    parents: &[Commit],
+    diff: &Diff,
+    filter: &PathFilter,
+    options: &AnalysisOptions,
+    select: Selector,
+) -> Result<Vec<FileDelta>, AnalyzerError> {
+    if !diffs_all_parents(parents, options) {
+        let mut deltas = collect_deltas(diff, filter, options, select, options.needs_line_content())?;
+        drop_binary(&mut deltas, options);
+        for delta in &mut deltas {
+            annotate(delta, options);
+        }
+        return Ok(deltas);
+    }
+
+    let mut deltas = merge_work(repo, commit, parents, diff, filter, options, select)?;
+    drop_binary(&mut deltas, options);
+    for delta in &mut deltas {
+        annotate(delta, options);
+        if !options.needs_line_content() {
+            delta.hunks.clear();
+        }
+    }
+    Ok(deltas)
+}
+
+fn diffs_all_parents(parents: &[Commit], options: &AnalysisOptions) -> bool {
+    parents.len() >= 2 && options.merge_diff == MergeDiff::AllParents
+}
+
+// The files of a merge's first-parent diff that also changed against every
+// other parent, keeping in their hunks only the lines that did
+fn merge_work(
+    repo: &Repository,
+    commit: &Commit,
+    parents: &[Commit],
+    diff: &Diff,
+    filter: &PathFilter,
+    options: &AnalysisOptions,
+    select: Selector,
+) -> Result<Vec<FileDelta>, AnalyzerError> {
+    let mut deltas = collect_deltas(diff, filter, options, select, true)?;
+    for parent in &parents[1..] {
+        let other = tree_diff(repo, Some(&parent.tree()?), &commit.tree()?, options)?;
+        let others: HashMap<String, FileDelta> = collect_deltas(&other, filter, options, select, true)?
+            .into_iter()
+            .map(|d| (d.path.clone(), d))
+            .collect();
+        // A file matching any parent was taken from it and holds no merge work
+        deltas.retain_mut(|delta| match others.get(&delta.path) {
+            Some(other) => {
+                intersect_lines(delta, other);
+                true
+            }
+            None => false,
+        });
+    }
+    Ok(deltas)
+}
+
+fn drop_binary(deltas: &mut Vec<FileDelta>, options: &AnalysisOptions) {
+    if options.file_detection != FileDetection::Content {
+        return;
+    }
+    deltas.retain(|delta| {
+        if delta.is_binary {
+            trace!(path = delta.path, "binary content not tracked");
+        }
+        !delta.is_binary
+    });
+}
+
+// The diff the sections reading it directly, rather than the deltas, see. For
+// a merge under merge_diff="all_parents" that is the first-parent diff
+// narrowed to the files changed against every parent, and within those only
+// the lines differing from every parent count, as for the deltas.
+pub struct ObservedDiff<'r> {
+    pub diff: Diff<'r>,
+    // None outside such merges
+    merge_lines: Option<HashMap<String, MergeLines>>,
+}
+
+// Contents of the lines a merge added and removed in one file
+type MergeLines = (HashSet<String>, HashSet<String>);
+
+impl ObservedDiff<'_> {
+    // Whether a '+' or '-' line of `path` is the commit's own change
+    pub fn keeps_line(&self, path: &str, origin: char, content: &[u8]) -> bool {
+        let Some(merge_lines) = &self.merge_lines else {
+            return true;
+        };
+        let Some((added, removed)) = merge_lines.get(path) else {
+            return false;
+        };
+        let content = String::from_utf8_lossy(content);
+        let content = content.trim_end_matches(['\n', '\r']);
+        match origin {
+            '+' => added.contains(content),
+            '-' => removed.contains(content),
+            _ => false,
+        }
+    }
+}
+
+pub fn observed_diff<'r>(
+    repo: &'r Repository,
+    commit: &Commit,
+    parents: &[Commit],
+    diff: Diff<'r>,
+    filter: &PathFilter,
+    options: &AnalysisOptions,
+) -> Result<ObservedDiff<'r>, AnalyzerError> {
+    if !diffs_all_parents(parents, options) || !options.reads_commit_diff() {
+        return Ok(ObservedDiff { diff, merge_lines: None });
+    }
+    // Those sections see every file in the diff, ignore file or not
+    let deltas = merge_work(repo, commit, parents, &diff, &filter.without_ignore(), options, any_file)?;
+    let tree = commit.tree()?;
+    let narrowed = if deltas.is_empty() {
+        repo.diff_tree_to_tree(Some(&tree), Some(&tree), None)?
+    } else {
+        let mut diff_opts = diff_knobs(options);
+        diff_opts.disable_pathspec_match(true);
+        for delta in &deltas {
+            diff_opts.pathspec(&delta.path);
+            if let Some(old_path) = &delta.old_path {
+                diff_opts.pathspec(old_path);
+            }
+        }
+        tree_diff_with(repo, Some(&parents[0].tree()?), &tree, diff_opts, options)?
+    };
+    let merge_lines = deltas
+        .into_iter()
+        .map(|delta| {
+            let added = delta.hunks.iter().flat_map(|h| h.added.iter().cloned()).collect();
+            let removed = delta.hunks.iter().flat_map(|h| h.removed.iter().cloned()).collect();
+            (delta.path, (added, removed))
+        })
+        .collect();
+    Ok(ObservedDiff { diff: narrowed, merge_lines: Some(merge_lines) })
+}
+
+fn any_file(path: &Path, _: &str, _: &AnalysisOptions) -> Option<String> {
+    Some(extension_of(path))
+}
+
+pub fn file_deltas(
+    diff: &Diff,
+    filter: &PathFilter,
+    options: &AnalysisOptions,
+) -> Result<Vec<FileDelta>, AnalyzerError> {
+    let mut deltas = collect_deltas(diff, filter, options, classify::bucket_for, options.needs_line_content())?;
+    drop_binary(&mut deltas, options);
+    for delta in &mut deltas {
+        annotate(delta, options);
+    }
+    Ok(deltas)
+}
+
+fn collect_deltas(
+    diff: &Diff,
+    filter: &PathFilter,
+    options: &AnalysisOptions,
+    select: Selector,
+    with_content: bool,
+) -> Result<Vec<FileDelta>, AnalyzerError> {
+    let mut deltas = Vec::new();
+
+    for (idx, delta) in diff.deltas().enumerate() {
+        let Some(path) = delta.new_file().path() else {
+            continue;
+        };
+        let slash_path = path.to_slash_lossy().into_owned();
+        let Some(ext) = select(path, &slash_path, options) else {
+            trace!(path = slash_path, "file type not tracked");
+            continue;
+        };
+        if filter.is_excluded(path) {
+            debug!(path = slash_path, "file excluded by ignore file");
+            continue;
+        }
+
+        let mut file_delta = FileDelta {
+            path: slash_path,
+            ext,
+            status: delta.status(),
+            old_path: (delta.status() == Delta::Renamed)
+                .then(|| delta.old_file().path().map(|p| p.to_slash_lossy().into_owned()))
+                .flatten(),
+            is_binary: delta.flags().is_binary(),
+            additions: 0,
+            deletions: 0,
+            has_hunks: false,
+            hunks: Vec::new(),
+            metrics: BTreeMap::new(),
+            content_missing: false,
+        };
+
+        match Patch::from_diff(diff, idx) {
+            Ok(Some(patch)) => {
+                let (_, additions, deletions) = patch.line_stats()?;
+                file_delta.additions = additions as i32;
+                file_delta.deletions = deletions as i32;
+                file_delta.has_hunks = patch.num_hunks() > 0;
+                file_delta.is_binary = patch.delta().flags().is_binary();
+                if with_content {
+                    file_delta.hunks = collect_hunks(&patch)?;
+                }
+            }
+            Ok(None) => {}
+            // Elsewhere a missing blob means a damaged repository
+            Err(e) if filter.partial_clone && e.code() == ErrorCode::NotFound => {
+                trace!(path = file_delta.path, "blob not in partial clone");
+                file_delta.content_missing = true;
+            }
+            Err(e) => return Err(e.into()),
+        }
+
+        deltas.push(file_delta);
+    }
+
+    Ok(deltas)
+}
+
+fn collect_hunks(patch: &Patch) -> Result<Vec<Hunk>, AnalyzerError> {
+    let mut hunks = Vec::with_capacity(patch.num_hunks());
+    for hunk_idx in 0..patch.num_hunks() {
+        let mut hunk = Hunk::default();
+        for line_idx in 0..patch.num_lines_in_hunk(hunk_idx)? {
+            let line = patch.line_in_hunk(hunk_idx, line_idx)?;
+            let content = String::from_utf8_lossy(line.content());
+            let content = content.trim_end_matches(['\n', '\r']).to_string();
+            match line.origin() {
+                '+' => hunk.added.push(content),
+                '-' => hunk.removed.push(content),
+                _ => {}
+            }
+        }
+        hunks.push(hunk);
+    }
+    Ok(hunks)
+}
+
+// Keeps only the lines of `delta` that `other` (the same file diffed against
+// another parent) also added or removed, matching lines by content
+fn intersect_lines(delta: &mut FileDelta, other: &FileDelta) {
+    let mut added: HashMap<&str, i32> = HashMap::new();
+    let mut removed: HashMap<&str, i32> = HashMap::new();
+    for hunk in &other.hunks {
+        for line in &hunk.added {
+            *added.entry(line).or_default() += 1;
+        }
+        for line in &hunk.removed {
+            *removed.entry(line).or_default() += 1;
+        }
+    }
+
+    fn take(counts: &mut HashMap<&str, i32>, line: &str) -> bool {
+        match counts.get_mut(line) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                true
+            }
+            _ => false,
+        }
+    }
+    for hunk in &mut delta.hunks {
+        hunk.added.retain(|line| take(&mut added, line));
+        hunk.removed.retain(|line| take(&mut removed, line));
+    }
+    delta.hunks.retain(|hunk| !hunk.added.is_empty() || !hunk.removed.is_empty());
+
+    delta.additions = delta.hunks.iter().map(|h| h.added.len() as i32).sum();
+    delta.deletions = delta.hunks.iter().map(|h| h.removed.len() as i32).sum();
+    delta.has_hunks = !delta.hunks.is_empty();
+}
+
+// Fills in the optional per-file metrics enabled in the options
+fn annotate(delta: &mut FileDelta, options: &AnalysisOptions) {
+    if options.word_diff {
+        let (added, removed) = words::word_changes(&delta.hunks);
+        delta.metrics.insert("words_added", added);
+        delta.metrics.insert("words_removed", removed);
+    }
+
+    if options.classify_lines {
+        let (added, removed) = comments::count_kinds(&delta.hunks, &delta.ext);
+        delta.metrics.insert("code_added", added[0]);
+        delta.metrics.insert("comment_added", added[1]);
+        delta.metrics.insert("blank_added", added[2]);
+        delta.metrics.insert("code_removed", removed[0]);
+        delta.metrics.insert("comment_removed", removed[1]);
+        delta.metrics.insert("blank_removed", removed[2]);
+    }
+
+    if let Some(matcher) = &options.compiled.test_matcher {
+        let is_test = classify::is_test_path(matcher, &delta.path);
+        let (test, production) = if is_test { (1, 0) } else { (0, 1) };
+        delta.metrics.insert("test_additions", test * delta.additions);
+        delta.metrics.insert("test_deletions", test * delta.deletions);
+        delta.metrics.insert("production_additions", production * delta.additions);
+        delta.metrics.insert("production_deletions", production * delta.deletions);
+    }
+
+    if let Some(regex) = &options.compiled.marker_regex {
+        let total = markers::totals(&markers::count_markers(&delta.hunks, regex));
+        delta.metrics.insert("markers_added", total.added);
+        delta.metrics.insert("markers_removed", total.removed);
+    }
+
+    if options.detect_formatting {
+        let change = if delta.has_hunks {
+            formatting::classify(&delta.hunks)
+        } else if options.ignores_whitespace() && delta.status == Delta::Modified && !delta.is_binary {
+            // The file changed but the whitespace-insensitive diff found nothing
+            Some(FormattingChange::WhitespaceOnly)
+        } else {
+            None
+        };
+        match change {
+            Some(FormattingChange::WhitespaceOnly) => {
+                delta.metrics.insert("whitespace_only_files", 1);
+            }
+            Some(FormattingChange::FormattingOnly) => {
+                delta.metrics.insert("formatting_only_files", 1);
+            }
+            None => {}
+        }
+        if change.is_some() {
+            delta.metrics.insert("formatting_only_lines", delta.additions + delta.deletions);
+        }
+    }
+}
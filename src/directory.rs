@@ -0,0 +1,120 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+
+use ignore::WalkBuilder;
+use path_slash::PathExt;
+use tracing::{info, warn};
+
+use crate::fleet::{self, FleetEntry};
+use crate::options::AnalysisOptions;
+use crate::{AnalyzerError, MonthlyStats, RepoAnalysis, ScanMetadata};
+
+pub const DEFAULT_MAX_DEPTH: usize = 3;
+
+pub struct DirectoryScan {
+    // Repository path relative to the root ("." for the root itself)
+    pub repos: Vec<(String, RepoAnalysis)>,
+    // Repositories that could not be analyzed, e.g. ones without commits
+    pub errors: BTreeMap<String, String>,
+    pub merged: MonthlyStats,
+    pub metadata: ScanMetadata,
+}
+
+// A working tree with a .git directory or file, or a bare repository
+pub fn is_repository(path: &Path) -> bool {
+    path.join(".git").exists()
+        || (path.join("HEAD").is_file() && path.join("objects").is_dir() && path.join("refs").is_dir())
+}
+
+// Repositories at most `max_depth` levels below `root`, in path order. The walk
+// honors .gitignore and .ignore files, skips hidden directories and does not
+// descend into a repository once found, so submodules and vendored checkouts
+// inside it are not scanned twice.
+pub fn find_repositories(root: &Path, max_depth: usize) -> Vec<PathBuf> {
+    let mut repos: Vec<PathBuf> = WalkBuilder::new(root)
+        .max_depth(Some(max_depth))
+        .filter_entry(|entry| {
+            entry.depth() == 0 || !entry.path().parent().is_some_and(is_repository)
+        })
+        .build()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_some_and(|t| t.is_dir()) && is_repository(entry.path()))
+        .map(|entry| entry.into_path())
+        .collect();
+    repos.sort();
+    repos
+}
+
+pub fn scan_directory(
+    root: &str,
+    options: &AnalysisOptions,
+    max_depth: usize,
+) -> Result<DirectoryScan, AnalyzerError> {
+    let root_path = Path::new(root);
+    if !root_path.is_dir() {
+        return Err(AnalyzerError::InvalidOption(format!("{} is not a directory", root)));
+    }
+
+    let mut scan = DirectoryScan {
+        repos: Vec::new(),
+        errors: BTreeMap::new(),
+        merged: MonthlyStats::new(),
+        metadata: ScanMetadata {
+            repo_path: root.to_string(),
+            ..Default::default()
+        },
+    };
+    let repositories = find_repositories(root_path, max_depth);
+    info!(root, repositories = repositories.len(), "found repositories");
+    let entries: Vec<FleetEntry> = repositories
+        .iter()
+        .map(|path| FleetEntry {
+            label: match path.strip_prefix(root_path) {
+                Ok(relative) if !relative.as_os_str().is_empty() => relative.to_slash_lossy().into_owned(),
+                _ => ".".to_string(),
+            },
+            repo: path.to_string_lossy().into_owned(),
+        })
+        .collect();
+
+    // Repositories are analyzed concurrently and merged as they complete
+    let (sender, receiver) = mpsc::channel();
+    let workers = fleet::worker_count(None, options);
+    thread::scope(|s| {
+        let scanning = s.spawn(|| fleet::scan_fleet(&entries, options, workers, sender));
+        for event in receiver {
+            let name = entries[event.index].label.clone();
+            match event.result {
+                Ok(analysis) => {
+                    scan.metadata.absorb(&analysis.metadata);
+                    merge_months(&mut scan.merged, &analysis.months);
+                    scan.repos.push((name, analysis));
+                }
+                Err(e) => {
+                    warn!(repo = name, error = %e, "repository scan failed");
+                    scan.errors.insert(name, e.to_string());
+                }
+            }
+        }
+        scanning
+            .join()
+            .map_err(|_| AnalyzerError::InvalidOption("directory worker pool panicked".to_string()))?
+    })?;
+    scan.repos.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(scan)
+}
+
+// Adds one repository's months to a combined result, counting the repository
+// once under every month and extension it contributed to
+pub fn merge_months(merged: &mut MonthlyStats, months: &MonthlyStats) {
+    for (month, extensions) in months {
+        let merged_month = merged.entry(month.clone()).or_default();
+        for (ext, stats) in extensions {
+            let merged_stats = merged_month.entry(ext.clone()).or_default();
+            merged_stats.add(stats);
+            merged_stats.repos += 1;
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use serde::Serialize;
+
+// Names this close (in edits) are treated as probable typos of each other
+const MAX_NAME_DISTANCE: usize = 2;
+// Shorter names are too likely to collide by chance for the distance check
+const MIN_FUZZY_NAME_LEN: usize = 5;
+const GENERIC_LOCAL_PARTS: &[&str] = &[
+    "admin", "administrator", "root", "info", "dev", "developer", "git", "user", "test", "build",
+    "ci", "bot", "support", "contact", "mail", "webmaster", "postmaster", "nobody", "donotreply",
+    "do-not-reply",
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AuthorCluster {
+    // The identity with the most commits, suggested as the canonical one
+    pub canonical: String,
+    pub identities: Vec<String>,
+    pub commits: i32,
+    pub reasons: Vec<String>,
+}
+
+// Author identities that probably belong to the same person, with a
+// suggested .mailmap mapping each alias onto its cluster's canonical identity
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct DuplicateAuthors {
+    pub clusters: Vec<AuthorCluster>,
+    pub mailmap: String,
+    #[serde(skip)]
+    seen: BTreeMap<(String, String), i32>,
+}
+
+impl DuplicateAuthors {
+    pub fn observe(&mut self, name: &str, email: &str) {
+        *self.seen.entry((name.to_string(), email.to_string())).or_default() += 1;
+    }
+
+    pub fn finish(&mut self) {
+        let identities: Vec<(&(String, String), &i32)> = self.seen.iter().collect();
+        let lowered: Vec<(String, String)> = identities
+            .iter()
+            .map(|((name, email), _)| (name.to_lowercase(), email.to_lowercase()))
+            .collect();
+        let mut parents: Vec<usize> = (0..identities.len()).collect();
+        let mut reasons: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+
+        // Identities sharing a key are candidates, instead of comparing every
+        // pair
+        let mut exact: HashMap<(&str, &str), Vec<usize>> = HashMap::new();
+        let mut variants: HashMap<String, Vec<usize>> = HashMap::new();
+        for (i, (name, email)) in lowered.iter().enumerate() {
+            if !email.is_empty() {
+                exact.entry(("same_email", email)).or_default().push(i);
+            }
+            if !name.is_empty() {
+                exact.entry(("same_name", name)).or_default().push(i);
+            }
+            let local = email.split('@').next().unwrap_or("");
+            if !local.is_empty() && !is_generic_local_part(local) {
+                exact.entry(("same_email_local_part", local)).or_default().push(i);
+            }
+            if name.chars().count() >= MIN_FUZZY_NAME_LEN {
+                for variant in deletion_variants(name, MAX_NAME_DISTANCE) {
+                    variants.entry(variant).or_default().push(i);
+                }
+            }
+        }
+
+        let mut link = |i: usize, j: usize, reason: &str| {
+            let (root_a, root_b) = (find(&mut parents, i), find(&mut parents, j));
+            if root_a != root_b {
+                parents[root_b] = root_a;
+            }
+            for k in [i, j] {
+                reasons.entry(k).or_default().push(reason.to_string());
+            }
+        };
+        for ((reason, _), members) in &exact {
+            for &j in members.iter().skip(1) {
+                link(members[0], j, reason);
+            }
+        }
+        // Names within MAX_NAME_DISTANCE edits share a variant with at most
+        // that many characters deleted
+        let mut compared = HashSet::new();
+        for members in variants.values() {
+            for (idx, &i) in members.iter().enumerate() {
+                for &j in &members[idx + 1..] {
+                    let (name_a, name_b) = (&lowered[i].0, &lowered[j].0);
+                    if name_a == name_b || !compared.insert((i, j)) {
+                        continue;
+                    }
+                    if edit_distance(name_a, name_b) <= MAX_NAME_DISTANCE {
+                        link(i, j, "similar_name");
+                    }
+                }
+            }
+        }
+
+        let mut groups: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for i in 0..identities.len() {
+            let root = find(&mut parents, i);
+            groups.entry(root).or_default().push(i);
+        }
+        for (root, members) in &groups {
+            if members.len() < 2 {
+                continue;
+            }
+            let format = |i: usize| format!("{} <{}>", identities[i].0 .0, identities[i].0 .1);
+            let canonical = *members
+                .iter()
+                .max_by_key(|&&i| (identities[i].1, std::cmp::Reverse(i)))
+                .unwrap_or(root);
+            let mut cluster_reasons: Vec<String> =
+                members.iter().flat_map(|i| reasons.get(i).cloned().unwrap_or_default()).collect();
+            cluster_reasons.sort();
+            cluster_reasons.dedup();
+
+            for &i in members {
+                if i != canonical {
+                    self.mailmap.push_str(&format!("{} {}\n", format(canonical), format(i)));
+                }
+            }
+            self.clusters.push(AuthorCluster {
+                canonical: format(canonical),
+                identities: members.iter().map(|&i| format(i)).collect(),
+                commits: members.iter().map(|&i| identities[i].1).sum(),
+                reasons: cluster_reasons,
+            });
+        }
+        self.clusters.sort_by(|a, b| b.commits.cmp(&a.commits).then_with(|| a.canonical.cmp(&b.canonical)));
+    }
+}
+
+fn find(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+// Mailbox names shared by unrelated people and bots, e.g. admin@ on two
+// domains; never a reason to merge
+fn is_generic_local_part(local: &str) -> bool {
+    GENERIC_LOCAL_PARTS.contains(&local) || local.contains("noreply") || local.contains("no-reply")
+}
+
+// `value` with up to `max` characters deleted, itself included
+fn deletion_variants(value: &str, max: usize) -> HashSet<String> {
+    let mut variants = HashSet::from([value.to_string()]);
+    let mut frontier = vec![value.to_string()];
+    for _ in 0..max {
+        let mut next = Vec::new();
+        for variant in &frontier {
+            let chars: Vec<char> = variant.chars().collect();
+            for skip in 0..chars.len() {
+                let shorter: String =
+                    chars.iter().enumerate().filter(|&(i, _)| i != skip).map(|(_, c)| c).collect();
+                if variants.insert(shorter.clone()) {
+                    next.push(shorter);
+                }
+            }
+        }
+        frontier = next;
+    }
+    variants
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
@@ -0,0 +1,140 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::series;
+use crate::{AnalyzerError, MonthlyStats};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CocomoMode {
+    #[default]
+    Organic,
+    SemiDetached,
+    Embedded,
+}
+
+impl CocomoMode {
+    #[cfg(feature = "python")]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CocomoMode::Organic => "organic",
+            CocomoMode::SemiDetached => "semi_detached",
+            CocomoMode::Embedded => "embedded",
+        }
+    }
+
+    // Basic COCOMO (Boehm, 1981)
+    pub fn coefficients(&self) -> CocomoCoefficients {
+        let (a, b, c, d) = match self {
+            CocomoMode::Organic => (2.4, 1.05, 2.5, 0.38),
+            CocomoMode::SemiDetached => (3.0, 1.12, 2.5, 0.35),
+            CocomoMode::Embedded => (3.6, 1.20, 2.5, 0.32),
+        };
+        CocomoCoefficients { a, b, c, d }
+    }
+}
+
+// effort = a * KLOC^b person-months, schedule = c * effort^d months
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CocomoCoefficients {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+}
+
+impl CocomoCoefficients {
+    pub fn validate(&self) -> Result<(), AnalyzerError> {
+        if [self.a, self.b, self.c, self.d].iter().any(|v| !v.is_finite() || *v <= 0.0) {
+            return Err(AnalyzerError::InvalidOption(
+                "cocomo_coefficients must be positive numbers".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn effort(&self, kloc: f64) -> f64 {
+        self.a * kloc.powf(self.b)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EffortMonth {
+    pub kloc: f64,
+    pub effort_person_months: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EffortEstimate {
+    pub coefficients: CocomoCoefficients,
+    // Net lines at the end of the scan, in thousands
+    pub kloc: f64,
+    pub effort_person_months: f64,
+    pub schedule_months: f64,
+    pub average_staff: f64,
+    // Effort for every line added or deleted, as if churn were new code
+    pub churn_kloc: f64,
+    pub churn_effort_person_months: f64,
+    // Only present when cost_per_person_month is set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cost: Option<f64>,
+    // Effort for the cumulative size at the end of each month
+    pub months: BTreeMap<String, EffortMonth>,
+    #[serde(skip)]
+    cost_per_person_month: Option<f64>,
+}
+
+impl EffortEstimate {
+    pub fn new(coefficients: CocomoCoefficients, cost_per_person_month: Option<f64>) -> Self {
+        EffortEstimate {
+            coefficients,
+            kloc: 0.0,
+            effort_person_months: 0.0,
+            schedule_months: 0.0,
+            average_staff: 0.0,
+            churn_kloc: 0.0,
+            churn_effort_person_months: 0.0,
+            cost: None,
+            months: BTreeMap::new(),
+            cost_per_person_month,
+        }
+    }
+
+    pub fn finish(&mut self, monthly_stats: &MonthlyStats) {
+        let coefficients = self.coefficients;
+        let mut kloc = 0.0;
+        for (month, totals) in series::cumulative_lines(monthly_stats) {
+            // Extensions whose running total went negative contribute nothing
+            kloc = totals.values().map(|lines| (*lines).max(0)).sum::<i64>() as f64 / 1000.0;
+            self.months.insert(
+                month,
+                EffortMonth {
+                    kloc: round(kloc),
+                    effort_person_months: round(coefficients.effort(kloc)),
+                },
+            );
+        }
+
+        let churn: i64 = monthly_stats
+            .values()
+            .flat_map(|exts| exts.values())
+            .map(|s| i64::from(s.additions) + i64::from(s.deletions))
+            .sum();
+        let effort = coefficients.effort(kloc);
+        let schedule = if effort > 0.0 { coefficients.c * effort.powf(coefficients.d) } else { 0.0 };
+
+        self.kloc = round(kloc);
+        self.effort_person_months = round(effort);
+        self.schedule_months = round(schedule);
+        self.average_staff = if schedule > 0.0 { round(effort / schedule) } else { 0.0 };
+        self.churn_kloc = round(churn as f64 / 1000.0);
+        self.churn_effort_person_months = round(coefficients.effort(churn as f64 / 1000.0));
+        self.cost = self.cost_per_person_month.map(|rate| round(effort * rate));
+    }
+}
+
+fn round(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
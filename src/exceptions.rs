@@ -0,0 +1,83 @@
+// create_exception! in pyo3 0.19 checks an `addr_of` cfg that newer rustc does not know
+#![allow(unexpected_cfgs)]
+
+use pyo3::create_exception;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::{AnalyzerError, ScanMetadata};
+
+// RepoScanError derives from ValueError, which every failure used to raise,
+// so existing `except ValueError` handlers keep working. Instances carry
+// `repo_path` and `commit` attributes, None when not known.
+create_exception!(repo_scan_rs, RepoScanError, PyValueError, "Base class of repo_scan_rs errors.");
+create_exception!(repo_scan_rs, GitOpenError, RepoScanError, "The repository could not be opened.");
+create_exception!(repo_scan_rs, PatternError, RepoScanError, "An author or path pattern is invalid.");
+create_exception!(repo_scan_rs, Cancelled, RepoScanError, "The analysis was cancelled before it finished.");
+create_exception!(
+    repo_scan_rs,
+    PartialResultError,
+    RepoScanError,
+    "The analysis stopped early; the result so far is in the `partial` attribute."
+);
+
+const CONTEXT_ATTRIBUTES: &[&str] = &["repo_path", "commit"];
+
+pub fn register(py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    let base = py.get_type::<RepoScanError>();
+    for name in CONTEXT_ATTRIBUTES {
+        base.setattr(*name, py.None())?;
+    }
+    py.get_type::<PartialResultError>().setattr("partial", py.None())?;
+    m.add("RepoScanError", base)?;
+    m.add("GitOpenError", py.get_type::<GitOpenError>())?;
+    m.add("PatternError", py.get_type::<PatternError>())?;
+    m.add("Cancelled", py.get_type::<Cancelled>())?;
+    m.add("PartialResultError", py.get_type::<PartialResultError>())?;
+    Ok(())
+}
+
+impl From<AnalyzerError> for PyErr {
+    fn from(error: AnalyzerError) -> PyErr {
+        let message = error.to_string();
+        let mut repo_path = None;
+        let mut commit = None;
+        let mut cause = &error;
+        if let AnalyzerError::Commit { repo_path: path, commit: oid, source } = &error {
+            repo_path = Some(path.clone());
+            commit = Some(oid.clone());
+            cause = source;
+        }
+
+        let err = match cause {
+            AnalyzerError::Open { repo_path: path, .. } => {
+                repo_path = Some(path.clone());
+                GitOpenError::new_err(message)
+            }
+            AnalyzerError::RegexError(_) => PatternError::new_err(message),
+            AnalyzerError::Cancelled => Cancelled::new_err(message),
+            _ => RepoScanError::new_err(message),
+        };
+        Python::with_gil(|py| {
+            let value = err.value(py);
+            // Attribute assignment on a fresh exception instance cannot fail
+            let _ = value.setattr("repo_path", repo_path);
+            let _ = value.setattr("commit", commit);
+        });
+        err
+    }
+}
+
+// A scan kept by partial_results is raised as PartialResultError, so callers
+// cannot mistake it for a complete result; `partial` holds the usual output
+pub fn check_partial(py: Python<'_>, output: PyObject, metadata: &ScanMetadata) -> PyResult<PyObject> {
+    let Some(stopped) = &metadata.stopped_early else {
+        return Ok(output);
+    };
+    let err = PartialResultError::new_err(format!("Scan stopped early: {}", stopped.error));
+    let value = err.value(py);
+    value.setattr("partial", output)?;
+    value.setattr("repo_path", &metadata.repo_path)?;
+    value.setattr("commit", stopped.commit.clone())?;
+    Err(err)
+}
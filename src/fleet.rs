@@ -0,0 +1,120 @@
+use std::collections::HashSet;
+use std::sync::mpsc::Sender;
+use std::time::Instant;
+
+use rayon::prelude::*;
+use serde::Deserialize;
+
+use crate::options::AnalysisOptions;
+use crate::progress::ProgressGroup;
+use crate::remote;
+use crate::{analyze_repo_internal, AnalyzerError, RepoAnalysis};
+
+pub const DEFAULT_WORKERS: usize = 4;
+
+// An explicit worker count, else the options' max_parallel_repos
+pub fn worker_count(workers: Option<usize>, options: &AnalysisOptions) -> usize {
+    workers.or(options.max_parallel_repos).unwrap_or(DEFAULT_WORKERS)
+}
+
+// A manifest line: a bare path or URL, or {"repo": ..., "label": ...}
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum ManifestEntry {
+    Repo(String),
+    Labeled(LabeledEntry),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LabeledEntry {
+    repo: String,
+    #[serde(default)]
+    label: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FleetEntry {
+    pub label: String,
+    pub repo: String,
+}
+
+impl FleetEntry {
+    // A failed scan's error as it may be reported; libgit2 quotes the URL it
+    // was given, credentials included
+    pub fn error_message(&self, error: &AnalyzerError) -> String {
+        error.to_string().replace(&self.repo, &remote::redacted_url(&self.repo))
+    }
+}
+
+pub struct FleetEvent {
+    // Position of the repository in the manifest
+    pub index: usize,
+    pub elapsed_seconds: f64,
+    pub result: Result<RepoAnalysis, AnalyzerError>,
+}
+
+// Labels default to the path or URL, without any credentials in it, and must
+// be unique, since the combined report is keyed by them
+pub fn parse_manifest(entries: Vec<ManifestEntry>) -> Result<Vec<FleetEntry>, AnalyzerError> {
+    let mut labels = HashSet::new();
+    entries
+        .into_iter()
+        .map(|entry| {
+            let (repo, label) = match entry {
+                ManifestEntry::Repo(repo) => (repo.clone(), remote::redacted_url(&repo)),
+                ManifestEntry::Labeled(LabeledEntry { repo, label }) => {
+                    let label = label.unwrap_or_else(|| remote::redacted_url(&repo));
+                    (repo, label)
+                }
+            };
+            if !labels.insert(label.clone()) {
+                return Err(AnalyzerError::InvalidOption(format!("duplicate manifest label {:?}", label)));
+            }
+            Ok(FleetEntry { label, repo })
+        })
+        .collect()
+}
+
+// Analyzes every entry on a pool of `workers` threads, sending one event per
+// repository as it completes. Remote URLs are cloned to a temporary directory
+// for the duration of their scan; failures are reported per repository. With
+// show_progress each repository gets its own bar, labelled with its entry.
+pub fn scan_fleet(
+    entries: &[FleetEntry],
+    options: &AnalysisOptions,
+    workers: usize,
+    events: Sender<FleetEvent>,
+) -> Result<(), AnalyzerError> {
+    if workers == 0 {
+        return Err(AnalyzerError::InvalidOption("workers must be at least 1".to_string()));
+    }
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(workers)
+        .build()
+        .map_err(|e| AnalyzerError::InvalidOption(e.to_string()))?;
+    let progress = ProgressGroup::new(options)?;
+
+    pool.install(|| {
+        entries.par_iter().enumerate().for_each_with(events, |events, (index, entry)| {
+            let started = Instant::now();
+            let mut entry_options = options.clone();
+            entry_options.clone_remote = remote::is_remote_url(&entry.repo);
+            entry_options.compiled.progress_group = progress.as_ref().map(|group| group.with_label(&entry.label));
+            let result = remote::with_local_repo(&entry.repo, &entry_options, |path| {
+                analyze_repo_internal(path, &entry_options)
+            })
+            .map(|mut analysis| {
+                analysis.metadata.repo_path = remote::redacted_url(&entry.repo);
+                analysis
+            });
+            // The receiver only goes away if the caller stopped listening
+            let _ = events.send(FleetEvent {
+                index,
+                elapsed_seconds: started.elapsed().as_secs_f64(),
+                result,
+            });
+        });
+    });
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use crate::diff::Hunk;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormattingChange {
+    // Only indentation, trailing whitespace or blank lines changed
+    WhitespaceOnly,
+    // Same tokens, but re-wrapped across lines (typical of auto-formatters)
+    FormattingOnly,
+}
+
+pub fn classify(hunks: &[Hunk]) -> Option<FormattingChange> {
+    if hunks.is_empty() {
+        return None;
+    }
+
+    if hunks.iter().all(|h| significant_lines(&h.removed) == significant_lines(&h.added)) {
+        return Some(FormattingChange::WhitespaceOnly);
+    }
+
+    if hunks.iter().all(|h| squashed(&h.removed) == squashed(&h.added)) {
+        return Some(FormattingChange::FormattingOnly);
+    }
+
+    None
+}
+
+// Lines with all whitespace removed, skipping lines that were blank
+fn significant_lines(lines: &[String]) -> Vec<String> {
+    lines
+        .iter()
+        .map(|line| strip_whitespace(line))
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+// All non-whitespace characters of a block, ignoring where the lines broke
+fn squashed(lines: &[String]) -> String {
+    lines.iter().map(|line| strip_whitespace(line)).collect()
+}
+
+fn strip_whitespace(line: &str) -> String {
+    line.chars().filter(|c| !c.is_whitespace()).collect()
+}
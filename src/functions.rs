@@ -0,0 +1,296 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::Arc;
+
+use git2::{Delta, Oid, Patch, Repository};
+use path_slash::PathExt;
+use serde::Serialize;
+use tree_sitter::{Language, Node, Parser};
+
+use crate::diff::{FileDelta, ObservedDiff};
+use crate::AnalyzerError;
+
+// Functions listed in the report, most modified first
+const TOP_FUNCTIONS: usize = 100;
+
+// Node kinds that define functions and the ones that enclose them (classes,
+// impl blocks, modules) in one grammar. The enclosing names qualify the
+// function name.
+struct Grammar {
+    language: Language,
+    functions: &'static [&'static str],
+    containers: &'static [&'static str],
+    separator: &'static str,
+}
+
+fn grammar(ext: &str) -> Option<Grammar> {
+    let (language, functions, containers, separator): (Language, &[&str], &[&str], &str) = match ext {
+        "rs" => (
+            tree_sitter_rust::LANGUAGE.into(),
+            &["function_item"],
+            &["impl_item", "trait_item", "mod_item"],
+            "::",
+        ),
+        "py" | "pyi" => (
+            tree_sitter_python::LANGUAGE.into(),
+            &["function_definition"],
+            &["class_definition"],
+            ".",
+        ),
+        "js" | "jsx" | "mjs" | "cjs" => (
+            tree_sitter_javascript::LANGUAGE.into(),
+            &["function_declaration", "generator_function_declaration", "method_definition"],
+            &["class_declaration"],
+            ".",
+        ),
+        "ts" => (
+            tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            &["function_declaration", "generator_function_declaration", "method_definition"],
+            &["class_declaration", "abstract_class_declaration", "interface_declaration"],
+            ".",
+        ),
+        "tsx" => (
+            tree_sitter_typescript::LANGUAGE_TSX.into(),
+            &["function_declaration", "generator_function_declaration", "method_definition"],
+            &["class_declaration", "abstract_class_declaration", "interface_declaration"],
+            ".",
+        ),
+        "go" => (
+            tree_sitter_go::LANGUAGE.into(),
+            &["function_declaration", "method_declaration"],
+            &[],
+            ".",
+        ),
+        "java" => (
+            tree_sitter_java::LANGUAGE.into(),
+            &["method_declaration", "constructor_declaration"],
+            &["class_declaration", "interface_declaration", "enum_declaration", "record_declaration"],
+            ".",
+        ),
+        _ => return None,
+    };
+    Some(Grammar {
+        language,
+        functions,
+        containers,
+        separator,
+    })
+}
+
+// A function's qualified name and its 1-based line range
+#[derive(Debug)]
+struct FunctionSpan {
+    name: String,
+    start: u32,
+    end: u32,
+}
+
+fn node_name(node: Node, source: &[u8]) -> Option<String> {
+    let name = node
+        .child_by_field_name("name")
+        // impl blocks are named after their type
+        .or_else(|| node.child_by_field_name("type"))?;
+    name.utf8_text(source).ok().map(str::to_string)
+}
+
+fn collect_spans(
+    node: Node,
+    source: &[u8],
+    grammar: &Grammar,
+    scope: &mut Vec<String>,
+    spans: &mut Vec<FunctionSpan>,
+) {
+    let kind = node.kind();
+    let name = if grammar.functions.contains(&kind) || grammar.containers.contains(&kind) {
+        node_name(node, source)
+    } else {
+        None
+    };
+    if let Some(name) = &name {
+        scope.push(name.clone());
+        if grammar.functions.contains(&kind) {
+            spans.push(FunctionSpan {
+                name: scope.join(grammar.separator),
+                start: node.start_position().row as u32 + 1,
+                end: node.end_position().row as u32 + 1,
+            });
+        }
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect_spans(child, source, grammar, scope, spans);
+    }
+    if name.is_some() {
+        scope.pop();
+    }
+}
+
+// Innermost function containing each line
+fn functions_at(spans: &[FunctionSpan], lines: &[u32]) -> HashMap<String, i32> {
+    let mut changed: HashMap<String, i32> = HashMap::new();
+    for &line in lines {
+        let innermost = spans
+            .iter()
+            .filter(|span| span.start <= line && line <= span.end)
+            .min_by_key(|span| span.end - span.start);
+        if let Some(span) = innermost {
+            *changed.entry(span.name.clone()).or_default() += 1;
+        }
+    }
+    changed
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FunctionHotspot {
+    pub path: String,
+    pub function: String,
+    // Commits that changed lines inside the function
+    pub modifications: i32,
+    pub lines_changed: i32,
+    pub authors: usize,
+}
+
+// Lines changed per (path, function)
+pub type FunctionChanges = Vec<((String, String), i32)>;
+
+#[derive(Debug, Default)]
+struct FunctionTotals {
+    modifications: i32,
+    lines_changed: i32,
+    authors: BTreeSet<String>,
+}
+
+// Changed lines attributed to the innermost function around them: removed
+// lines in the old version of the file, added lines in the new one. Only
+// modified (and renamed) files in a supported language are parsed.
+#[derive(Debug, Default, Serialize)]
+pub struct FunctionChurn {
+    pub functions: Vec<FunctionHotspot>,
+    #[serde(skip)]
+    totals: HashMap<(String, String), FunctionTotals>,
+    // The last blob parsed for each path: walking newest first, the old side
+    // of one change is usually the new side of the next one seen
+    #[serde(skip)]
+    parsed: HashMap<String, (Oid, Arc<Vec<FunctionSpan>>)>,
+}
+
+impl FunctionChurn {
+    // The functions one commit changed; observe counts them
+    pub fn changes(
+        &mut self,
+        repo: &Repository,
+        observed: &ObservedDiff,
+        deltas: &[FileDelta],
+    ) -> Result<FunctionChanges, AnalyzerError> {
+        let diff = &observed.diff;
+        let mut changes = Vec::new();
+        // Blobs left out of a partial clone cannot be parsed
+        let tracked: HashSet<&str> = deltas
+            .iter()
+            .filter(|d| !d.content_missing)
+            .map(|d| d.path.as_str())
+            .collect();
+        for (idx, delta) in diff.deltas().enumerate() {
+            if !matches!(delta.status(), Delta::Modified | Delta::Renamed) {
+                continue;
+            }
+            let Some(path) = delta.new_file().path() else {
+                continue;
+            };
+            let path_str = path.to_slash_lossy().into_owned();
+            if !tracked.contains(path_str.as_str()) {
+                continue;
+            }
+            let Some(grammar) = path.extension().and_then(|e| e.to_str()).and_then(grammar) else {
+                continue;
+            };
+            let Some(patch) = Patch::from_diff(diff, idx)? else {
+                continue;
+            };
+            let mut removed = Vec::new();
+            let mut added = Vec::new();
+            for hunk in 0..patch.num_hunks() {
+                for line in 0..patch.num_lines_in_hunk(hunk)? {
+                    let line = patch.line_in_hunk(hunk, line)?;
+                    if !observed.keeps_line(&path_str, line.origin(), line.content()) {
+                        continue;
+                    }
+                    match line.origin() {
+                        '-' => removed.extend(line.old_lineno()),
+                        '+' => added.extend(line.new_lineno()),
+                        _ => {}
+                    }
+                }
+            }
+            if removed.is_empty() && added.is_empty() {
+                continue;
+            }
+
+            let new_spans = self.spans(repo, &path_str, delta.new_file().id(), &grammar)?;
+            let old_spans = self.spans(repo, &path_str, delta.old_file().id(), &grammar)?;
+            let mut changed = functions_at(&old_spans, &removed);
+            for (name, lines) in functions_at(&new_spans, &added) {
+                *changed.entry(name).or_default() += lines;
+            }
+            changes.extend(changed.into_iter().map(|(name, lines)| ((path_str.clone(), name), lines)));
+        }
+        Ok(changes)
+    }
+
+    pub fn observe(&mut self, author: &str, changes: FunctionChanges) {
+        for (function, lines) in changes {
+            let totals = self.totals.entry(function).or_default();
+            totals.modifications += 1;
+            totals.lines_changed += lines;
+            totals.authors.insert(author.to_string());
+        }
+    }
+
+    fn spans(
+        &mut self,
+        repo: &Repository,
+        path: &str,
+        blob_id: Oid,
+        grammar: &Grammar,
+    ) -> Result<Arc<Vec<FunctionSpan>>, AnalyzerError> {
+        if let Some((parsed_id, spans)) = self.parsed.get(path) {
+            if *parsed_id == blob_id {
+                return Ok(spans.clone());
+            }
+        }
+        let blob = repo.find_blob(blob_id)?;
+        let mut spans = Vec::new();
+        let mut parser = Parser::new();
+        // A grammar/library version mismatch leaves the file unparsed
+        if parser.set_language(&grammar.language).is_ok() {
+            if let Some(tree) = parser.parse(blob.content(), None) {
+                collect_spans(tree.root_node(), blob.content(), grammar, &mut Vec::new(), &mut spans);
+            }
+        }
+        let spans = Arc::new(spans);
+        self.parsed.insert(path.to_string(), (blob_id, spans.clone()));
+        Ok(spans)
+    }
+
+    pub fn finish(&mut self) {
+        let mut functions: Vec<FunctionHotspot> = std::mem::take(&mut self.totals)
+            .into_iter()
+            .map(|((path, function), totals)| FunctionHotspot {
+                path,
+                function,
+                modifications: totals.modifications,
+                lines_changed: totals.lines_changed,
+                authors: totals.authors.len(),
+            })
+            .collect();
+        functions.sort_by(|a, b| {
+            b.modifications
+                .cmp(&a.modifications)
+                .then_with(|| b.lines_changed.cmp(&a.lines_changed))
+                .then_with(|| a.path.cmp(&b.path))
+                .then_with(|| a.function.cmp(&b.function))
+        });
+        functions.truncate(TOP_FUNCTIONS);
+        self.functions = functions;
+        self.parsed = HashMap::new();
+    }
+}
@@ -0,0 +1,114 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+// Built-in gitmoji -> category mapping; `gitmoji_categories` adds to or
+// overrides it. Emoji and their :shortcode: spellings are both recognized.
+pub const DEFAULT_GITMOJI: &[(&str, &str, &str)] = &[
+    ("✨", ":sparkles:", "feature"),
+    ("🐛", ":bug:", "fix"),
+    ("🚑", ":ambulance:", "hotfix"),
+    ("🩹", ":adhesive_bandage:", "fix"),
+    ("📝", ":memo:", "docs"),
+    ("🎨", ":art:", "style"),
+    ("♻", ":recycle:", "refactor"),
+    ("⚡", ":zap:", "performance"),
+    ("🔥", ":fire:", "removal"),
+    ("✅", ":white_check_mark:", "test"),
+    ("🔒", ":lock:", "security"),
+    ("🚀", ":rocket:", "deploy"),
+    ("💄", ":lipstick:", "ui"),
+    ("🎉", ":tada:", "init"),
+    ("🔧", ":wrench:", "config"),
+    ("⬆", ":arrow_up:", "dependencies"),
+    ("⬇", ":arrow_down:", "dependencies"),
+    ("➕", ":heavy_plus_sign:", "dependencies"),
+    ("➖", ":heavy_minus_sign:", "dependencies"),
+    ("👷", ":construction_worker:", "ci"),
+    ("💚", ":green_heart:", "ci"),
+    ("🚧", ":construction:", "wip"),
+    ("⏪", ":rewind:", "revert"),
+    ("🔀", ":twisted_rightwards_arrows:", "merge"),
+    ("🏷", ":label:", "types"),
+    ("🌐", ":globe_with_meridians:", "i18n"),
+    ("💥", ":boom:", "breaking"),
+    ("🗑", ":wastebasket:", "deprecation"),
+];
+
+// Category for emoji prefixes missing from the mapping
+pub const UNMAPPED_CATEGORY: &str = "other";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Gitmoji {
+    pub emoji: String,
+    pub category: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct GitmojiClassifier {
+    categories: BTreeMap<String, String>,
+}
+
+impl GitmojiClassifier {
+    pub fn new(extra: &BTreeMap<String, String>) -> Self {
+        let mut categories = BTreeMap::new();
+        for (emoji, shortcode, category) in DEFAULT_GITMOJI {
+            categories.insert(emoji.to_string(), category.to_string());
+            categories.insert(shortcode.to_string(), category.to_string());
+        }
+        for (key, category) in extra {
+            categories.insert(strip_variation(key), category.clone());
+        }
+        GitmojiClassifier { categories }
+    }
+
+    // The emoji or :shortcode: a subject starts with, and its category
+    pub fn classify(&self, subject: &str) -> Option<Gitmoji> {
+        let prefix = prefix_of(subject.trim_start())?;
+        let category = self
+            .categories
+            .get(&prefix)
+            .cloned()
+            .unwrap_or_else(|| UNMAPPED_CATEGORY.to_string());
+        Some(Gitmoji { emoji: prefix, category })
+    }
+}
+
+fn prefix_of(subject: &str) -> Option<String> {
+    if let Some(rest) = subject.strip_prefix(':') {
+        let end = rest.find(':')?;
+        let name = &rest[..end];
+        let valid = !name.is_empty()
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '+');
+        return valid.then(|| format!(":{}:", name));
+    }
+
+    let emoji: String = subject
+        .chars()
+        .take_while(|&c| !c.is_ascii() && !c.is_alphanumeric() && !c.is_whitespace())
+        .collect();
+    let emoji = strip_variation(&emoji);
+    (!emoji.is_empty()).then_some(emoji)
+}
+
+// Drops variation selectors so "♻️" and "♻" compare equal
+fn strip_variation(value: &str) -> String {
+    value.chars().filter(|&c| c != '\u{fe0f}').collect()
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct GitmojiMonth {
+    pub commits: i32,
+    pub with_gitmoji: i32,
+    pub categories: BTreeMap<String, i32>,
+}
+
+impl GitmojiMonth {
+    pub fn observe(&mut self, gitmoji: Option<&Gitmoji>) {
+        self.commits += 1;
+        if let Some(gitmoji) = gitmoji {
+            self.with_gitmoji += 1;
+            *self.categories.entry(gitmoji.category.clone()).or_default() += 1;
+        }
+    }
+}
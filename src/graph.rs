@@ -0,0 +1,212 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+use chrono::{TimeZone, Utc};
+use git2::Oid;
+
+use crate::authors;
+use crate::diff::{commit_deltas, parent_diff};
+use crate::history::Replacements;
+use crate::messages;
+use crate::options::AnalysisOptions;
+use crate::paths::PathFilter;
+use crate::{open_repository, AnalyzerError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    Dot,
+    GraphMl,
+}
+
+impl GraphFormat {
+    pub fn parse(value: &str) -> Result<Self, AnalyzerError> {
+        match value {
+            "dot" => Ok(GraphFormat::Dot),
+            "graphml" => Ok(GraphFormat::GraphMl),
+            other => Err(AnalyzerError::InvalidOption(format!(
+                "graph format must be \"dot\" or \"graphml\", got {:?}",
+                other
+            ))),
+        }
+    }
+}
+
+struct Node {
+    oid: Oid,
+    parents: Vec<Oid>,
+    author: String,
+    timestamp: i64,
+    subject: String,
+}
+
+// Commit graph reachable from HEAD. Commits rejected by the author, date or
+// merge filters are dropped, and with `simplify` so are commits on linear
+// stretches (one parent, one child); edges then lead to the nearest kept
+// ancestors so the shape of the history is preserved.
+pub fn export(
+    repo_path: &str,
+    options: &AnalysisOptions,
+    format: GraphFormat,
+    simplify: bool,
+) -> Result<String, AnalyzerError> {
+    let repo = open_repository(repo_path, options)?;
+    let path_filter = PathFilter::for_repo(&repo, options.use_ignore_file)?;
+    let head = repo.head()?.peel_to_commit()?.id();
+
+    let replacements = Replacements::load(&repo, !options.ignore_replacements)?;
+    let revwalk = replacements.walk_sorted(&repo, git2::Sort::TOPOLOGICAL)?;
+
+    let mut nodes = Vec::new();
+    let mut children: HashMap<Oid, usize> = HashMap::new();
+    let mut kept = HashSet::new();
+    for oid in revwalk {
+        let oid = oid?;
+        let commit = replacements.find_commit(&repo, oid)?;
+        let parents = replacements.parent_ids(oid, &commit);
+        for parent in &parents {
+            *children.entry(*parent).or_default() += 1;
+        }
+        let matches = options.matches_author(&commit.author())
+            && options.in_date_range(commit.author().when().seconds())
+            && !options.skips_commit(parents.len());
+        if matches {
+            kept.insert(oid);
+        }
+        nodes.push(Node {
+            oid,
+            parents,
+            author: authors::identity(&commit.author(), options.normalize_authors),
+            timestamp: commit.author().when().seconds(),
+            subject: messages::split(commit.message().unwrap_or("")).0.to_string(),
+        });
+    }
+
+    let parents_of: HashMap<Oid, &[Oid]> = nodes.iter().map(|n| (n.oid, n.parents.as_slice())).collect();
+    if simplify {
+        kept.retain(|oid| {
+            let parent_count = parents_of.get(oid).map_or(0, |parents| parents.len());
+            *oid == head || parent_count != 1 || children.get(oid).copied().unwrap_or(0) != 1
+        });
+    }
+    let mut nearest: HashMap<Oid, Vec<Oid>> = HashMap::new();
+    let mut edges = Vec::new();
+    let mut kept_nodes = Vec::new();
+    for node in nodes.iter().filter(|n| kept.contains(&n.oid)) {
+        let commit = replacements.find_commit(&repo, node.oid)?;
+        let parents = replacements.parents(&repo, node.oid, &commit);
+        let diff = parent_diff(&repo, parents.first(), &commit, options)?;
+        let churn: i64 = commit_deltas(&repo, &commit, &parents, &diff, &path_filter, options)?
+            .iter()
+            .map(|d| i64::from(d.additions) + i64::from(d.deletions))
+            .sum();
+        kept_nodes.push((node, churn));
+        for parent in &node.parents {
+            for ancestor in nearest_kept(*parent, &kept, &parents_of, &mut nearest) {
+                edges.push((node.oid, ancestor));
+            }
+        }
+    }
+    edges.sort();
+    edges.dedup();
+
+    let mut out = String::new();
+    match format {
+        GraphFormat::Dot => {
+            out.push_str("digraph commits {\n  rankdir=BT;\n  node [shape=box];\n");
+            for (node, churn) in &kept_nodes {
+                let _ = writeln!(
+                    out,
+                    "  \"{}\" [label=\"{}\\n{}\", author=\"{}\", date=\"{}\", churn={}];",
+                    node.oid,
+                    &node.oid.to_string()[..7],
+                    escape_dot(&node.subject),
+                    escape_dot(&node.author),
+                    iso_date(node.timestamp),
+                    churn
+                );
+            }
+            for (child, parent) in &edges {
+                let _ = writeln!(out, "  \"{}\" -> \"{}\";", child, parent);
+            }
+            out.push_str("}\n");
+        }
+        GraphFormat::GraphMl => {
+            out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+            out.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+            for (key, kind) in [("author", "string"), ("date", "string"), ("subject", "string"), ("churn", "long")] {
+                let _ = writeln!(
+                    out,
+                    "  <key id=\"{0}\" for=\"node\" attr.name=\"{0}\" attr.type=\"{1}\"/>",
+                    key, kind
+                );
+            }
+            out.push_str("  <graph id=\"commits\" edgedefault=\"directed\">\n");
+            for (node, churn) in &kept_nodes {
+                let _ = writeln!(out, "    <node id=\"{}\">", node.oid);
+                let _ = writeln!(out, "      <data key=\"author\">{}</data>", escape_xml(&node.author));
+                let _ = writeln!(out, "      <data key=\"date\">{}</data>", iso_date(node.timestamp));
+                let _ = writeln!(out, "      <data key=\"subject\">{}</data>", escape_xml(&node.subject));
+                let _ = writeln!(out, "      <data key=\"churn\">{}</data>", churn);
+                out.push_str("    </node>\n");
+            }
+            for (child, parent) in &edges {
+                let _ = writeln!(out, "    <edge source=\"{}\" target=\"{}\"/>", child, parent);
+            }
+            out.push_str("  </graph>\n</graphml>\n");
+        }
+    }
+    Ok(out)
+}
+
+// Kept commits reached from `oid` by following parents through dropped ones
+fn nearest_kept(
+    oid: Oid,
+    kept: &HashSet<Oid>,
+    parents_of: &HashMap<Oid, &[Oid]>,
+    memo: &mut HashMap<Oid, Vec<Oid>>,
+) -> Vec<Oid> {
+    if kept.contains(&oid) {
+        return vec![oid];
+    }
+    if let Some(found) = memo.get(&oid) {
+        return found.clone();
+    }
+    // Iterative walk, since long dropped stretches would overflow the stack
+    let mut found = Vec::new();
+    let mut stack = vec![oid];
+    let mut visited = HashSet::new();
+    while let Some(current) = stack.pop() {
+        if !visited.insert(current) {
+            continue;
+        }
+        if current != oid && kept.contains(&current) {
+            found.push(current);
+            continue;
+        }
+        if let Some(cached) = memo.get(&current) {
+            found.extend(cached.iter().copied());
+            continue;
+        }
+        stack.extend(parents_of.get(&current).copied().unwrap_or(&[]).iter().copied());
+    }
+    found.sort();
+    found.dedup();
+    memo.insert(oid, found.clone());
+    found
+}
+
+fn iso_date(timestamp: i64) -> String {
+    Utc.timestamp_opt(timestamp, 0).single().unwrap_or_default().to_rfc3339()
+}
+
+fn escape_dot(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,298 @@
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use git2::{Commit, ErrorCode, Oid, Repository, Revwalk, Sort};
+
+use crate::AnalyzerError;
+
+const REPLACE_REFS: &str = "refs/replace/";
+
+// History rewrites git applies while walking: refs/replace/<oid> swaps a
+// commit for another one, and info/grafts gives a commit different parents.
+// libgit2 does not follow replace refs at all. It applies grafts to every
+// commit it reads, so they are in effect whatever ignore_replacements says,
+// but its revwalk and merge-base still follow the stored parents; the walks
+// here follow the grafted ones. Shallow clone boundaries are handled by
+// libgit2.
+#[derive(Debug, Default)]
+pub struct Replacements {
+    commits: HashMap<Oid, Oid>,
+    grafts: HashMap<Oid, Vec<Oid>>,
+}
+
+impl Replacements {
+    // Without replace refs when `replace_refs` is false, as with
+    // GIT_NO_REPLACE_OBJECTS; grafts are always read
+    pub fn load(repo: &Repository, replace_refs: bool) -> Result<Self, AnalyzerError> {
+        let mut replacements = Replacements::default();
+        if replace_refs {
+            for reference in repo.references_glob(&format!("{}*", REPLACE_REFS))? {
+                let reference = reference?;
+                let (Some(name), Some(target)) = (reference.name(), reference.target()) else {
+                    continue;
+                };
+                if let Ok(original) = Oid::from_str(&name[REPLACE_REFS.len()..]) {
+                    replacements.commits.insert(original, target);
+                }
+            }
+        }
+        let grafts = std::fs::read_to_string(repo.path().join("info").join("grafts")).unwrap_or_default();
+        for line in grafts.lines().filter(|line| !line.starts_with('#')) {
+            let mut oids = line.split_whitespace().filter_map(|oid| Oid::from_str(oid).ok());
+            if let Some(commit) = oids.next() {
+                replacements.grafts.insert(commit, oids.collect());
+            }
+        }
+        Ok(replacements)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commits.is_empty() && self.grafts.is_empty()
+    }
+
+    // Whether the commit's content or parents differ from its object
+    pub fn affects(&self, oid: Oid) -> bool {
+        self.commits.contains_key(&oid) || self.grafts.contains_key(&oid)
+    }
+
+    // The commit as git shows it: the replacement object if there is one
+    pub fn find_commit<'r>(&self, repo: &'r Repository, oid: Oid) -> Result<Commit<'r>, git2::Error> {
+        repo.find_commit(self.commits.get(&oid).copied().unwrap_or(oid))
+    }
+
+    pub fn parent_ids(&self, oid: Oid, commit: &Commit) -> Vec<Oid> {
+        match self.grafts.get(&oid) {
+            Some(parents) => parents.clone(),
+            None => commit.parent_ids().collect(),
+        }
+    }
+
+    // The parents a commit is diffed against, first parent first, up to the
+    // first one that cannot be read: a commit at a shallow boundary is diffed
+    // as a root commit.
+    pub fn parents<'r>(&self, repo: &'r Repository, oid: Oid, commit: &Commit) -> Vec<Commit<'r>> {
+        self.parent_ids(oid, commit)
+            .into_iter()
+            .map_while(|parent| self.find_commit(repo, parent).ok())
+            .collect()
+    }
+
+    // Commits reachable from HEAD, newest first. Without replacements this is
+    // libgit2's own walk.
+    pub fn walk<'a>(&'a self, repo: &'a Repository) -> Result<Walk<'a>, AnalyzerError> {
+        if self.is_empty() {
+            let mut revwalk = repo.revwalk()?;
+            revwalk.push_head()?;
+            return Ok(Walk::Revwalk(revwalk));
+        }
+        let head = repo.head()?.peel_to_commit()?.id();
+        let mut walk = ReplacedWalk::new(repo, self, HashSet::new());
+        walk.push(head)?;
+        Ok(Walk::Replaced(walk))
+    }
+
+    // Commits reachable from HEAD in `sort` order. Over rewritten history the
+    // order is always topological, newest first among the commits ready, and
+    // Sort::REVERSE the only other flag honored.
+    pub fn walk_sorted<'a>(&'a self, repo: &'a Repository, sort: Sort) -> Result<Walk<'a>, AnalyzerError> {
+        if self.is_empty() {
+            let mut revwalk = repo.revwalk()?;
+            revwalk.push_head()?;
+            revwalk.set_sorting(sort)?;
+            return Ok(Walk::Revwalk(revwalk));
+        }
+        let mut oids = self.topological(repo)?;
+        if sort.contains(Sort::REVERSE) {
+            oids.reverse();
+        }
+        Ok(Walk::Listed(oids.into_iter()))
+    }
+
+    // The first-parent chain from the root to HEAD
+    pub fn first_parent_walk<'a>(&'a self, repo: &'a Repository) -> Result<Walk<'a>, AnalyzerError> {
+        if self.is_empty() {
+            let mut revwalk = repo.revwalk()?;
+            revwalk.push_head()?;
+            revwalk.simplify_first_parent()?;
+            revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)?;
+            return Ok(Walk::Revwalk(revwalk));
+        }
+        let mut chain = Vec::new();
+        let mut next = Some(repo.head()?.peel_to_commit()?.id());
+        while let Some(oid) = next {
+            let commit = self.find_commit(repo, oid)?;
+            // A first parent that cannot be read is a shallow boundary
+            let first = self.parent_ids(oid, &commit).first().copied();
+            next = first.filter(|parent| self.find_commit(repo, *parent).is_ok());
+            chain.push(oid);
+        }
+        chain.reverse();
+        Ok(Walk::Listed(chain.into_iter()))
+    }
+
+    // Every commit before its parents, a commit's children all before it
+    fn topological(&self, repo: &Repository) -> Result<Vec<Oid>, AnalyzerError> {
+        let mut parents: HashMap<Oid, (i64, Vec<Oid>)> = HashMap::new();
+        for oid in self.walk(repo)? {
+            let oid = oid?;
+            let commit = self.find_commit(repo, oid)?;
+            parents.insert(oid, (commit.time().seconds(), self.parent_ids(oid, &commit)));
+        }
+        let mut children: HashMap<Oid, usize> = HashMap::new();
+        for (_, commit_parents) in parents.values() {
+            for parent in commit_parents.iter().filter(|parent| parents.contains_key(parent)) {
+                *children.entry(*parent).or_default() += 1;
+            }
+        }
+        let mut ready: BinaryHeap<(i64, Oid)> = parents
+            .iter()
+            .filter(|(oid, _)| !children.contains_key(oid))
+            .map(|(oid, (time, _))| (*time, *oid))
+            .collect();
+        let mut order = Vec::with_capacity(parents.len());
+        while let Some((_, oid)) = ready.pop() {
+            order.push(oid);
+            for parent in &parents[&oid].1 {
+                let Some(count) = children.get_mut(parent) else {
+                    continue;
+                };
+                *count -= 1;
+                if *count == 0 {
+                    ready.push((parents[parent].0, *parent));
+                }
+            }
+        }
+        Ok(order)
+    }
+
+    // The newest commit reachable from both, found over the rewritten parents
+    pub fn merge_base(&self, repo: &Repository, one: Oid, two: Oid) -> Result<Option<Oid>, AnalyzerError> {
+        if self.is_empty() {
+            return match repo.merge_base(one, two) {
+                Ok(base) => Ok(Some(base)),
+                Err(e) if e.code() == ErrorCode::NotFound => Ok(None),
+                Err(e) => Err(e.into()),
+            };
+        }
+        // Which of the two each commit is reachable from, as bits 1 and 2
+        let mut reached: HashMap<Oid, u8> = HashMap::new();
+        let mut queue = BinaryHeap::new();
+        for (oid, side) in [(one, 1), (two, 2)] {
+            *reached.entry(oid).or_default() |= side;
+            queue.push((self.find_commit(repo, oid)?.time().seconds(), oid));
+        }
+        while let Some((_, oid)) = queue.pop() {
+            let sides = reached[&oid];
+            if sides == 3 {
+                return Ok(Some(oid));
+            }
+            let commit = self.find_commit(repo, oid)?;
+            for parent in self.parent_ids(oid, &commit) {
+                // A missing parent ends that line of history
+                let Ok(parent_commit) = self.find_commit(repo, parent) else {
+                    continue;
+                };
+                let parent_sides = reached.entry(parent).or_default();
+                if *parent_sides | sides != *parent_sides {
+                    *parent_sides |= sides;
+                    queue.push((parent_commit.time().seconds(), parent));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    // Commits reachable from `tips` but not from `hide`, newest first: what a
+    // merge brings in when `tips` are its other parents and `hide` its first
+    pub fn walk_between<'a>(
+        &'a self,
+        repo: &'a Repository,
+        tips: &[Oid],
+        hide: Oid,
+    ) -> Result<Walk<'a>, AnalyzerError> {
+        if self.is_empty() {
+            let mut revwalk = repo.revwalk()?;
+            for tip in tips {
+                revwalk.push(*tip)?;
+            }
+            revwalk.hide(hide)?;
+            return Ok(Walk::Revwalk(revwalk));
+        }
+        // Everything behind `hide` counts as seen before the walk starts
+        let mut hidden = ReplacedWalk::new(repo, self, HashSet::new());
+        hidden.push(hide)?;
+        let seen = hidden.collect::<Result<HashSet<Oid>, _>>()?;
+        let mut walk = ReplacedWalk::new(repo, self, seen);
+        for tip in tips {
+            walk.push(*tip)?;
+        }
+        Ok(Walk::Replaced(walk))
+    }
+}
+
+pub enum Walk<'a> {
+    Revwalk(Revwalk<'a>),
+    Replaced(ReplacedWalk<'a>),
+    // Worked out in full up front, for the orders needing the whole graph
+    Listed(std::vec::IntoIter<Oid>),
+}
+
+impl Iterator for Walk<'_> {
+    type Item = Result<Oid, git2::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Walk::Revwalk(revwalk) => revwalk.next(),
+            Walk::Replaced(walk) => walk.next(),
+            Walk::Listed(oids) => oids.next().map(Ok),
+        }
+    }
+}
+
+// Date-ordered walk over the rewritten parent links
+pub struct ReplacedWalk<'a> {
+    repo: &'a Repository,
+    replacements: &'a Replacements,
+    // (commit time, commit), newest on top
+    queue: BinaryHeap<(i64, Oid)>,
+    seen: HashSet<Oid>,
+}
+
+impl<'a> ReplacedWalk<'a> {
+    fn new(repo: &'a Repository, replacements: &'a Replacements, seen: HashSet<Oid>) -> Self {
+        ReplacedWalk {
+            repo,
+            replacements,
+            queue: BinaryHeap::new(),
+            seen,
+        }
+    }
+
+    fn push(&mut self, oid: Oid) -> Result<(), git2::Error> {
+        if self.seen.insert(oid) {
+            let commit = self.replacements.find_commit(self.repo, oid)?;
+            self.queue.push((commit.time().seconds(), oid));
+        }
+        Ok(())
+    }
+}
+
+impl Iterator for ReplacedWalk<'_> {
+    type Item = Result<Oid, git2::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (_, oid) = self.queue.pop()?;
+        let parents = match self.replacements.find_commit(self.repo, oid) {
+            Ok(commit) => self.replacements.parent_ids(oid, &commit),
+            Err(e) => return Some(Err(e)),
+        };
+        for parent in parents {
+            // A missing parent ends that line of history, as at a shallow boundary
+            if let Err(e) = self.push(parent) {
+                if e.code() != git2::ErrorCode::NotFound {
+                    return Some(Err(e));
+                }
+            }
+        }
+        Some(Ok(oid))
+    }
+}
@@ -0,0 +1,91 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Datelike, FixedOffset, Timelike, Weekday};
+use git2::Commit;
+use serde::Serialize;
+
+use crate::diff::FileDelta;
+
+pub const DEFAULT_WORKING_HOURS: (u8, u8) = (9, 17);
+
+// Share of commits and churn landing on weekends, or on weekdays outside
+// working hours, in the author's own timezone
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct HoursStats {
+    pub commits: i32,
+    pub weekend_pct: f64,
+    pub out_of_hours_pct: f64,
+    pub weekend_churn_pct: f64,
+    pub out_of_hours_churn_pct: f64,
+    #[serde(skip)]
+    counts: [(i32, i64); 2],
+    #[serde(skip)]
+    churn: i64,
+}
+
+impl HoursStats {
+    fn observe(&mut self, slot: Option<usize>, churn: i64) {
+        self.commits += 1;
+        self.churn += churn;
+        if let Some(slot) = slot {
+            self.counts[slot].0 += 1;
+            self.counts[slot].1 += churn;
+        }
+    }
+
+    fn finish(&mut self) {
+        let [weekend, out_of_hours] = self.counts;
+        self.weekend_pct = percent(i64::from(weekend.0), i64::from(self.commits));
+        self.out_of_hours_pct = percent(i64::from(out_of_hours.0), i64::from(self.commits));
+        self.weekend_churn_pct = percent(weekend.1, self.churn);
+        self.out_of_hours_churn_pct = percent(out_of_hours.1, self.churn);
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HoursReport {
+    // Working hours as [start, end) in local hours
+    pub working_hours: (u8, u8),
+    pub months: BTreeMap<String, HoursStats>,
+    pub authors: BTreeMap<String, HoursStats>,
+}
+
+impl HoursReport {
+    pub fn new(working_hours: (u8, u8)) -> Self {
+        HoursReport {
+            working_hours,
+            months: BTreeMap::new(),
+            authors: BTreeMap::new(),
+        }
+    }
+
+    pub fn observe(&mut self, month_key: &str, commit: &Commit, author: &str, deltas: &[FileDelta]) {
+        let when = commit.author().when();
+        let local = FixedOffset::east_opt(when.offset_minutes() * 60)
+            .and_then(|offset| DateTime::from_timestamp(when.seconds(), 0).map(|t| t.with_timezone(&offset)));
+        let slot = local.and_then(|local| {
+            let (start, end) = self.working_hours;
+            if matches!(local.weekday(), Weekday::Sat | Weekday::Sun) {
+                Some(0)
+            } else if local.hour() < u32::from(start) || local.hour() >= u32::from(end) {
+                Some(1)
+            } else {
+                None
+            }
+        });
+        let churn: i64 = deltas.iter().map(|d| i64::from(d.additions) + i64::from(d.deletions)).sum();
+        self.months.entry(month_key.to_string()).or_default().observe(slot, churn);
+        self.authors.entry(author.to_string()).or_default().observe(slot, churn);
+    }
+
+    pub fn finish(&mut self) {
+        self.months.values_mut().chain(self.authors.values_mut()).for_each(HoursStats::finish);
+    }
+}
+
+fn percent(part: i64, total: i64) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    (part as f64 * 10000.0 / total as f64).round() / 100.0
+}
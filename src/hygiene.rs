@@ -0,0 +1,190 @@
+use std::collections::BTreeMap;
+
+use git2::Commit;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::messages::{self, MAX_SUBJECT_LENGTH};
+use crate::AnalyzerError;
+
+// Prefixes of standards and hashes written like issue keys (UTF-8, SHA-256,
+// ISO-8601), never counted as issue references
+const NOT_ISSUE_KEYS: &[&str] = &[
+    "AES", "ARM", "CP", "CVE", "CWE", "ECMA", "IEC", "IEEE", "ISO", "MD", "PEP", "RFC", "RSA", "SHA", "UCS", "UTF",
+];
+
+// Imperative verbs the suffix heuristic would reject because they end in
+// "ed", "ing" or "s"; sorted for binary search
+const IMPERATIVE_VERBS: &[&str] = &[
+    "access", "address", "alias", "bias", "bleed", "breed", "bring", "bypass", "debias", "discuss", "embed", "exceed",
+    "feed", "focus", "harness", "need", "ping", "proceed", "process", "reseed", "restring", "seed", "shed", "shred",
+    "sing", "speed", "spring", "string", "succeed", "swing", "unembed", "unfocus", "wing",
+];
+
+// Relative weight of each check in the score; equal by default
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HygieneWeights {
+    pub subject_length: f64,
+    pub body: f64,
+    pub imperative: f64,
+    pub issue_reference: f64,
+}
+
+impl Default for HygieneWeights {
+    fn default() -> Self {
+        HygieneWeights {
+            subject_length: 1.0,
+            body: 1.0,
+            imperative: 1.0,
+            issue_reference: 1.0,
+        }
+    }
+}
+
+impl HygieneWeights {
+    pub fn validate(&self) -> Result<(), AnalyzerError> {
+        let weights = [self.subject_length, self.body, self.imperative, self.issue_reference];
+        if weights.iter().any(|w| !w.is_finite() || *w < 0.0) || weights.iter().sum::<f64>() <= 0.0 {
+            return Err(AnalyzerError::InvalidOption(
+                "hygiene_weights must be non-negative numbers, not all zero".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn total(&self) -> f64 {
+        self.subject_length + self.body + self.imperative + self.issue_reference
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CommitHygiene {
+    pub commit: String,
+    pub month: String,
+    // Weighted share of the checks passed, 0 to 100
+    pub score: f64,
+    // A non-empty subject of at most 72 characters
+    pub subject_length: bool,
+    pub body: bool,
+    pub imperative: bool,
+    pub issue_reference: bool,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct HygieneMonth {
+    pub commits: i32,
+    pub mean_score: f64,
+    pub subject_length_pct: f64,
+    pub body_pct: f64,
+    pub imperative_pct: f64,
+    pub issue_reference_pct: f64,
+    #[serde(skip)]
+    score_sum: f64,
+    #[serde(skip)]
+    passed: [i32; 4],
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HygieneReport {
+    pub weights: HygieneWeights,
+    pub months: BTreeMap<String, HygieneMonth>,
+    // In the order commits were scanned, newest first
+    pub commits: Vec<CommitHygiene>,
+    #[serde(skip)]
+    conventional_prefix: Regex,
+    #[serde(skip)]
+    issue_reference: Regex,
+}
+
+impl HygieneReport {
+    pub fn new(weights: HygieneWeights) -> Self {
+        HygieneReport {
+            weights,
+            months: BTreeMap::new(),
+            commits: Vec::new(),
+            // "feat(scope)!: " and the like, skipped before the mood check
+            conventional_prefix: Regex::new(r"^[A-Za-z]+(\([^)]*\))?!?:\s*").expect("valid regex"),
+            // #123, GH-123, JIRA-style PROJ-123 and issue URLs; the key is
+            // captured so standards like UTF-8 can be told apart
+            issue_reference: Regex::new(
+                r"(^|[^\w&])#\d+\b|\b(?P<key>[A-Z]{2}[A-Z0-9]*)-\d+\b|/(issues|pull|merge_requests)/\d+",
+            )
+            .expect("valid regex"),
+        }
+    }
+
+    pub fn observe(&mut self, month_key: &str, commit: &Commit) {
+        let message = commit.message().unwrap_or("");
+        let (subject, body) = messages::split(message);
+        let length = subject.chars().count();
+        let checks = [
+            length > 0 && length <= MAX_SUBJECT_LENGTH,
+            !body.is_empty(),
+            self.is_imperative(subject),
+            self.has_issue_reference(message),
+        ];
+        let weights = [
+            self.weights.subject_length,
+            self.weights.body,
+            self.weights.imperative,
+            self.weights.issue_reference,
+        ];
+        let passed: f64 = checks.iter().zip(weights).filter(|(check, _)| **check).map(|(_, w)| w).sum();
+        let score = (passed * 10000.0 / self.weights.total()).round() / 100.0;
+
+        let month = self.months.entry(month_key.to_string()).or_default();
+        month.commits += 1;
+        month.score_sum += score;
+        for (count, check) in month.passed.iter_mut().zip(checks) {
+            *count += i32::from(check);
+        }
+        self.commits.push(CommitHygiene {
+            commit: commit.id().to_string(),
+            month: month_key.to_string(),
+            score,
+            subject_length: checks[0],
+            body: checks[1],
+            imperative: checks[2],
+            issue_reference: checks[3],
+        });
+    }
+
+    fn has_issue_reference(&self, message: &str) -> bool {
+        self.issue_reference.captures_iter(message).any(|captures| {
+            captures.name("key").is_none_or(|key| !NOT_ISSUE_KEYS.contains(&key.as_str()))
+        })
+    }
+
+    // Known verbs pass as they are; otherwise the first word must not look
+    // past tense ("Added"), a gerund ("Adding") or third person ("Adds")
+    fn is_imperative(&self, subject: &str) -> bool {
+        let subject = self.conventional_prefix.replace(subject, "");
+        let Some(word) = subject.split_whitespace().next() else {
+            return false;
+        };
+        let word = word.to_lowercase();
+        if !word.chars().all(char::is_alphabetic) {
+            return false;
+        }
+        if IMPERATIVE_VERBS.binary_search(&word.as_str()).is_ok() {
+            return true;
+        }
+        !(word.ends_with("ed") || word.ends_with("ing") || (word.ends_with('s') && !word.ends_with("ss")))
+    }
+
+    pub fn finish(&mut self) {
+        for month in self.months.values_mut() {
+            if month.commits == 0 {
+                continue;
+            }
+            let commits = f64::from(month.commits);
+            let pct = |count: i32| (f64::from(count) * 10000.0 / commits).round() / 100.0;
+            month.mean_score = (month.score_sum * 100.0 / commits).round() / 100.0;
+            month.subject_length_pct = pct(month.passed[0]);
+            month.body_pct = pct(month.passed[1]);
+            month.imperative_pct = pct(month.passed[2]);
+            month.issue_reference_pct = pct(month.passed[3]);
+        }
+    }
+}
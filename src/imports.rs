@@ -0,0 +1,151 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use regex::Regex;
+use serde::Serialize;
+
+use crate::diff::FileDelta;
+use crate::paths::extension_of;
+
+// Modules listed in the report, most imported first
+const TOP_MODULES: usize = 50;
+
+// Import statement patterns by extension; the first capture group is the
+// imported module. Only single-line statements are recognized, plus the
+// quoted lines of Go import blocks.
+#[derive(Debug)]
+struct ImportPatterns {
+    rust: Regex,
+    python: Regex,
+    javascript: Regex,
+    go: Regex,
+    java: Regex,
+}
+
+impl ImportPatterns {
+    fn new() -> Self {
+        ImportPatterns {
+            // The path up to a {...} group or the last segment
+            rust: Regex::new(r"^\s*(?:pub(?:\([^)]*\))?\s+)?use\s+((?:[A-Za-z_][A-Za-z0-9_]*::)*)").expect("valid regex"),
+            python: Regex::new(r"^\s*(?:from\s+(\.*[A-Za-z_][\w.]*|\.+)\s+import\b|import\s+([A-Za-z_][\w.]*))")
+                .expect("valid regex"),
+            javascript: Regex::new(r#"(?:^\s*import\b[^'"]*|\brequire\s*\(\s*|^\s*export\b[^'"]*\bfrom\s*)['"]([^'"]+)['"]"#)
+                .expect("valid regex"),
+            go: Regex::new(r#"^\s*(?:import\s+)?(?:[A-Za-z_.][A-Za-z0-9_]*\s+)?"([^"]+)"\s*$"#).expect("valid regex"),
+            java: Regex::new(r"^\s*import\s+(?:static\s+)?([A-Za-z_][\w.]*)\.(?:\*|[A-Za-z_]\w*)\s*;").expect("valid regex"),
+        }
+    }
+
+    fn module(&self, ext: &str, line: &str) -> Option<String> {
+        let module = match ext {
+            ".rs" => {
+                let path = self.rust.captures(line)?.get(1)?.as_str();
+                path.trim_end_matches("::").to_string()
+            }
+            ".py" => {
+                let captures = self.python.captures(line)?;
+                captures.get(1).or_else(|| captures.get(2))?.as_str().to_string()
+            }
+            ".js" | ".jsx" | ".ts" | ".tsx" => self.javascript.captures(line)?[1].to_string(),
+            ".go" => self.go.captures(line)?[1].to_string(),
+            ".java" => self.java.captures(line)?[1].to_string(),
+            _ => return None,
+        };
+        (!module.is_empty()).then_some(module)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+pub struct ImportCounts {
+    pub added: i32,
+    pub removed: i32,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct ImportMonth {
+    pub added: i32,
+    pub removed: i32,
+    pub extensions: BTreeMap<String, ImportCounts>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModuleImports {
+    pub module: String,
+    pub extension: String,
+    pub added: i32,
+    pub removed: i32,
+}
+
+// Import statements (Rust `use`, Python `import`/`from`, JavaScript and
+// TypeScript `import`/`require`, Go and Java `import`) added and removed per
+// month, and the modules imported most often across the scan
+#[derive(Debug, Serialize)]
+pub struct ImportReport {
+    pub months: BTreeMap<String, ImportMonth>,
+    pub modules: Vec<ModuleImports>,
+    #[serde(skip)]
+    patterns: ImportPatterns,
+    #[serde(skip)]
+    module_counts: HashMap<(String, String), ImportCounts>,
+}
+
+impl Default for ImportReport {
+    fn default() -> Self {
+        ImportReport {
+            months: BTreeMap::new(),
+            modules: Vec::new(),
+            patterns: ImportPatterns::new(),
+            module_counts: HashMap::new(),
+        }
+    }
+}
+
+impl ImportReport {
+    pub fn observe(&mut self, month_key: &str, deltas: &[FileDelta]) {
+        let month = self.months.entry(month_key.to_string()).or_default();
+        for delta in deltas {
+            // The language goes by the file's own extension, as the bucket may be "infra"
+            let language = extension_of(Path::new(&delta.path));
+            for hunk in &delta.hunks {
+                let added = hunk.added.iter().map(|line| (line, true));
+                let removed = hunk.removed.iter().map(|line| (line, false));
+                for (line, added) in added.chain(removed) {
+                    let Some(module) = self.patterns.module(&language, line) else {
+                        continue;
+                    };
+                    let counts = month.extensions.entry(delta.ext.clone()).or_default();
+                    let totals = self.module_counts.entry((module, delta.ext.clone())).or_default();
+                    if added {
+                        month.added += 1;
+                        counts.added += 1;
+                        totals.added += 1;
+                    } else {
+                        month.removed += 1;
+                        counts.removed += 1;
+                        totals.removed += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn finish(&mut self) {
+        let mut modules: Vec<ModuleImports> = std::mem::take(&mut self.module_counts)
+            .into_iter()
+            .map(|((module, extension), counts)| ModuleImports {
+                module,
+                extension,
+                added: counts.added,
+                removed: counts.removed,
+            })
+            .collect();
+        modules.sort_by(|a, b| {
+            b.added
+                .cmp(&a.added)
+                .then_with(|| a.module.cmp(&b.module))
+                .then_with(|| a.extension.cmp(&b.extension))
+        });
+        modules.truncate(TOP_MODULES);
+        self.modules = modules;
+    }
+}
@@ -0,0 +1,83 @@
+use std::collections::BTreeMap;
+
+use git2::Commit;
+use serde::Serialize;
+
+use crate::anomalies::quantile;
+
+// A week between writing a change and it landing
+pub const DEFAULT_LATENCY_THRESHOLD_HOURS: f64 = 168.0;
+
+// Committer time minus author time, in seconds. Rebases, cherry-picks and
+// patches applied after review all widen the gap; clock skew can make it
+// negative.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct LatencyMonth {
+    pub commits: i32,
+    pub mean_seconds: f64,
+    pub median_seconds: f64,
+    pub p90_seconds: f64,
+    pub max_seconds: i64,
+    #[serde(skip)]
+    latencies: Vec<i64>,
+}
+
+impl LatencyMonth {
+    fn finish(&mut self) {
+        let mut sorted: Vec<f64> = self.latencies.iter().map(|&l| l as f64).collect();
+        if sorted.is_empty() {
+            return;
+        }
+        sorted.sort_by(f64::total_cmp);
+        self.commits = sorted.len() as i32;
+        self.mean_seconds = (sorted.iter().sum::<f64>() / sorted.len() as f64 * 100.0).round() / 100.0;
+        self.median_seconds = quantile(&sorted, 0.5);
+        self.p90_seconds = quantile(&sorted, 0.9);
+        self.max_seconds = self.latencies.iter().copied().max().unwrap_or_default();
+        self.latencies = Vec::new();
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SlowCommit {
+    pub commit: String,
+    pub month: String,
+    pub author: String,
+    pub latency_seconds: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LatencyReport {
+    pub threshold_hours: f64,
+    pub months: BTreeMap<String, LatencyMonth>,
+    // Commits whose latency exceeds threshold_hours, slowest first
+    pub flagged: Vec<SlowCommit>,
+}
+
+impl LatencyReport {
+    pub fn new(threshold_hours: f64) -> Self {
+        LatencyReport {
+            threshold_hours,
+            months: BTreeMap::new(),
+            flagged: Vec::new(),
+        }
+    }
+
+    pub fn observe(&mut self, month_key: &str, commit: &Commit, author: &str) {
+        let latency = commit.committer().when().seconds() - commit.author().when().seconds();
+        self.months.entry(month_key.to_string()).or_default().latencies.push(latency);
+        if latency as f64 > self.threshold_hours * 3600.0 {
+            self.flagged.push(SlowCommit {
+                commit: commit.id().to_string(),
+                month: month_key.to_string(),
+                author: author.to_string(),
+                latency_seconds: latency,
+            });
+        }
+    }
+
+    pub fn finish(&mut self) {
+        self.months.values_mut().for_each(LatencyMonth::finish);
+        self.flagged.sort_by(|a, b| b.latency_seconds.cmp(&a.latency_seconds).then_with(|| a.commit.cmp(&b.commit)));
+    }
+}
@@ -1,17 +1,107 @@
+// pyo3 0.19's #[pymethods] expands to impl blocks that newer rustc flags as non-local
+#![allow(non_local_definitions)]
+// Without a front end (python, cli or capi) nothing calls into the analyses
+#![cfg_attr(not(any(feature = "python", feature = "cli", feature = "capi")), allow(dead_code))]
+
 use std::collections::{HashMap, HashSet, BTreeMap};
 use std::path::Path;
 use std::sync::Arc;
+use std::time::Instant;
 
 use chrono::{DateTime, TimeZone, Utc, Datelike};
 use git2::{Repository, Commit, Oid};
 use parking_lot::Mutex;
-use path_slash::PathExt;
-use pyo3::exceptions::PyValueError;
-use pyo3::prelude::*;
-use rayon::prelude::*;
-use regex::Regex;
+use serde::Serialize;
 use thiserror::Error;
-use indicatif::{ProgressBar, ProgressStyle};
+use tracing::{debug, info, trace, warn};
+
+mod anomalies;
+#[cfg(any(feature = "cli", feature = "capi"))]
+mod api;
+mod authors;
+mod binaries;
+mod branching;
+mod buckets;
+#[cfg(feature = "capi")]
+pub mod capi;
+mod churn;
+#[cfg(feature = "python")]
+mod changes;
+mod ci;
+#[cfg(feature = "cli")]
+pub mod cli;
+mod classify;
+mod collaboration;
+mod comments;
+mod compare;
+mod contributors;
+#[cfg(feature = "python")]
+mod convert;
+mod decay;
+mod dependencies;
+mod diff;
+mod directory;
+mod duplicates;
+mod effort;
+#[cfg(feature = "python")]
+mod exceptions;
+mod fleet;
+mod formatting;
+#[cfg(feature = "functions")]
+mod functions;
+mod gitmoji;
+mod graph;
+mod history;
+mod hours;
+mod hygiene;
+mod imports;
+mod latency;
+mod license;
+#[cfg(feature = "python")]
+mod logging;
+mod markers;
+mod matrix;
+#[cfg(feature = "python")]
+mod merge;
+mod merges;
+mod messages;
+mod nesting;
+mod notes;
+mod options;
+mod ownership;
+mod paths;
+mod pivot;
+mod progress;
+mod promisor;
+mod public_api;
+#[cfg(feature = "python")]
+mod python;
+mod remote;
+mod renames;
+mod report;
+mod reviews;
+mod rolling;
+mod secrets;
+#[cfg(feature = "server")]
+mod server;
+mod series;
+mod sizes;
+mod snapshot;
+mod survival;
+mod stash;
+mod storage;
+mod timezones;
+mod touched;
+mod words;
+mod worktree;
+
+use buckets::Bucket;
+use diff::{commit_deltas, parent_diff, ChangedFile, FileDelta};
+use history::Replacements;
+use options::{AnalysisOptions, BulkChanges, OnError, Pivot};
+use paths::PathFilter;
+use progress::Progress;
+use report::Sections;
 
 #[derive(Error, Debug)]
 pub enum AnalyzerError {
@@ -19,6 +109,41 @@ pub enum AnalyzerError {
     GitError(#[from] git2::Error),
     #[error("Invalid regex pattern: {0}")]
     RegexError(#[from] regex::Error),
+    #[error("Invalid option: {0}")]
+    InvalidOption(String),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Cannot open repository {repo_path}: {source}")]
+    Open { repo_path: String, source: git2::Error },
+    // Raised while processing one commit, with the failing commit attached
+    #[error("Commit {commit}: {source}")]
+    Commit {
+        repo_path: String,
+        commit: String,
+        source: Box<AnalyzerError>,
+    },
+    #[error("Analysis cancelled")]
+    Cancelled,
+    // Reading an input or writing the output failed, with what was being done
+    #[error("{context}: {source}")]
+    Io { context: String, source: std::io::Error },
+}
+
+// Linked worktrees and `.git` files pointing elsewhere are followed by libgit2.
+// With `git_dir`, the repository is opened from there and repo_path is only
+// its work tree, as with GIT_DIR and GIT_WORK_TREE.
+fn open_repository(repo_path: &str, options: &AnalysisOptions) -> Result<Repository, AnalyzerError> {
+    let opened = match &options.git_dir {
+        Some(git_dir) => Repository::open(git_dir).and_then(|repo| {
+            repo.set_workdir(Path::new(repo_path), false)?;
+            Ok(repo)
+        }),
+        None => Repository::open(repo_path),
+    };
+    opened.map_err(|source| AnalyzerError::Open {
+        repo_path: repo_path.to_string(),
+        source,
+    })
 }
 
 #[derive(Debug, Default, Clone)]
@@ -29,9 +154,218 @@ struct FileStats {
     deletions: i32,
     modifications: i32,
     repos: i32,
+    // Optional counters, only present when the mode producing them is enabled
+    metrics: BTreeMap<&'static str, i32>,
+}
+
+impl FileStats {
+    fn add(&mut self, other: &FileStats) {
+        self.lines += other.lines;
+        self.files += other.files;
+        self.additions += other.additions;
+        self.deletions += other.deletions;
+        self.modifications += other.modifications;
+        self.repos += other.repos;
+        for (name, value) in &other.metrics {
+            *self.metrics.entry(name).or_default() += value;
+        }
+    }
+
+    fn to_map(&self, include_repos: bool) -> HashMap<String, i32> {
+        let mut map = HashMap::from([
+            ("lines".to_string(), self.lines),
+            ("files".to_string(), self.files),
+            ("additions".to_string(), self.additions),
+            ("deletions".to_string(), self.deletions),
+            ("modifications".to_string(), self.modifications),
+        ]);
+        if include_repos {
+            map.insert("repos".to_string(), self.repos);
+        }
+        for (name, value) in &self.metrics {
+            map.insert(name.to_string(), *value);
+        }
+        map
+    }
 }
 
 type MonthlyStats = HashMap<String, HashMap<String, FileStats>>;
+type PyMonthlyStats = HashMap<String, HashMap<String, HashMap<String, i32>>>;
+
+#[derive(Debug, Default, Serialize)]
+struct ScanMetadata {
+    repo_path: String,
+    commits_scanned: usize,
+    commits_matched: usize,
+    // Matched commits with two parents, and octopus merges with three or more
+    merge_commits: usize,
+    octopus_merges: usize,
+    // Matched commits that changed no tracked file
+    commits_empty: usize,
+    // Matched commits left out by min_lines_changed or max_lines_changed
+    commits_out_of_size_bounds: usize,
+    // Matched commits over max_files_changed, left out or counted under the
+    // "<bulk>" bucket
+    commits_bulk: usize,
+    // Matched commits left out because their objects could not be read
+    commits_failed: usize,
+    // Walked commits whose content or parents came from refs/replace or
+    // info/grafts
+    commits_replaced: usize,
+    // Changed files counted by status only because a partial clone left out
+    // their blobs; see fetch_missing_objects
+    files_without_content: usize,
+    // Only with on_error="collect"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    errors: Option<Vec<CommitError>>,
+    // Set when partial_results kept the result of a scan that failed part way
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stopped_early: Option<StoppedEarly>,
+    // Set when time_budget_seconds ran out before the walk reached the first
+    // commit
+    #[serde(skip_serializing_if = "Option::is_none")]
+    time_budget_exhausted: Option<BudgetCoverage>,
+    // What the month keys of analyze_git_repo results are; merge_results
+    // refuses to combine results keyed differently
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    keys: Option<MonthKeys>,
+    // (commit time, commit) of the oldest commit walked so far
+    #[serde(skip)]
+    oldest_walked: Option<(i64, Oid)>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct MonthKeys {
+    bucket: Bucket,
+    // Only for the fiscal buckets
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fiscal_year_start_month: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bucket_format: Option<String>,
+}
+
+impl MonthKeys {
+    fn new(options: &AnalysisOptions) -> Self {
+        MonthKeys {
+            bucket: options.bucket,
+            fiscal_year_start_month: matches!(options.bucket, Bucket::FiscalQuarter | Bucket::FiscalYear)
+                .then_some(options.fiscal_year_start_month),
+            bucket_format: options.bucket_format.clone(),
+        }
+    }
+}
+
+// How much of the history a walk stopped by time_budget_seconds covered: it
+// goes newest first, so every commit from HEAD back to the oldest one walked
+#[derive(Debug, Clone, Serialize)]
+struct BudgetCoverage {
+    budget_seconds: f64,
+    oldest_commit: Option<String>,
+    oldest_timestamp: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct StoppedEarly {
+    // The commit being processed, if the failure was tied to one
+    commit: Option<String>,
+    error: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct CommitError {
+    commit: String,
+    error: String,
+}
+
+impl ScanMetadata {
+    // Adds another scan's counters, for results spanning several repositories
+    fn absorb(&mut self, other: &ScanMetadata) {
+        self.commits_scanned += other.commits_scanned;
+        self.commits_matched += other.commits_matched;
+        self.merge_commits += other.merge_commits;
+        self.octopus_merges += other.octopus_merges;
+        self.commits_empty += other.commits_empty;
+        self.commits_out_of_size_bounds += other.commits_out_of_size_bounds;
+        self.commits_bulk += other.commits_bulk;
+        self.commits_failed += other.commits_failed;
+        self.commits_replaced += other.commits_replaced;
+        self.files_without_content += other.files_without_content;
+        if let Some(errors) = &other.errors {
+            self.errors.get_or_insert_with(Vec::new).extend(errors.iter().cloned());
+        }
+        if self.keys.is_none() {
+            self.keys = other.keys.clone();
+        }
+    }
+
+    // Applies `on_error` to a commit that failed to process. Only git errors
+    // are recovered from; anything else still aborts the scan.
+    fn recover(&mut self, oid: Oid, error: AnalyzerError, on_error: OnError) -> Result<(), AnalyzerError> {
+        if on_error == OnError::Raise || !matches!(error, AnalyzerError::GitError(_)) {
+            return Err(AnalyzerError::Commit {
+                repo_path: self.repo_path.clone(),
+                commit: oid.to_string(),
+                source: Box::new(error),
+            });
+        }
+        warn!(commit = %oid, error = %error, "skipped unreadable commit");
+        self.commits_failed += 1;
+        if on_error == OnError::Collect {
+            self.errors.get_or_insert_with(Vec::new).push(CommitError {
+                commit: oid.to_string(),
+                error: error.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    // Ends a scan that failed part way: an error unless `partial_results`
+    // keeps what was processed so far
+    fn stop_early(&mut self, error: AnalyzerError, options: &AnalysisOptions) -> Result<(), AnalyzerError> {
+        if !options.partial_results {
+            return Err(error);
+        }
+        warn!(repo = self.repo_path, error = %error, "scan stopped early, keeping the partial result");
+        let commit = match &error {
+            AnalyzerError::Commit { commit, .. } => Some(commit.clone()),
+            _ => None,
+        };
+        self.stopped_early = Some(StoppedEarly {
+            commit,
+            error: error.to_string(),
+        });
+        Ok(())
+    }
+
+    fn record_walked(&mut self, commit: &Commit) {
+        let time = commit.time().seconds();
+        if self.oldest_walked.is_none_or(|(oldest, _)| time < oldest) {
+            self.oldest_walked = Some((time, commit.id()));
+        }
+    }
+
+    fn exhaust_budget(&mut self, budget_seconds: f64) {
+        warn!(
+            repo = self.repo_path,
+            commits = self.commits_scanned,
+            "time budget exhausted, returning the history covered so far"
+        );
+        self.time_budget_exhausted = Some(BudgetCoverage {
+            budget_seconds,
+            oldest_commit: self.oldest_walked.map(|(_, oid)| oid.to_string()),
+            oldest_timestamp: self.oldest_walked.map(|(time, _)| time),
+        });
+    }
+
+    fn record_match(&mut self, parent_count: usize) {
+        self.commits_matched += 1;
+        match parent_count {
+            0 | 1 => {}
+            2 => self.merge_commits += 1,
+            _ => self.octopus_merges += 1,
+        }
+    }
+}
 
 #[derive(Debug)]
 struct CommitData {
@@ -39,263 +373,294 @@ struct CommitData {
     message: String,
     author: String,
     stats: HashMap<String, FileStats>,
+    touches_docs: Option<bool>,
+    parents: Vec<String>,
+    tree: String,
+    notes: Option<BTreeMap<String, String>>,
+    // None unless classify_gitmoji is set; Some(None) for commits without one
+    gitmoji: Option<Option<gitmoji::Gitmoji>>,
+    // None unless detect_squash_merges is set; Some(None) for other commits
+    squash_merge: Option<Option<merges::SquashMerge>>,
+    files: Option<Vec<ChangedFile>>,
 }
 
-const TEXT_EXTENSIONS: &[&str] = &[
-    ".txt", ".md", ".rs", ".py", ".js", ".ts", ".jsx", ".tsx",
-    ".html", ".css", ".scss", ".json", ".yaml", ".yml", ".toml",
-    ".c", ".cpp", ".h", ".hpp", ".java", ".go", ".rb", ".php"
-];
-
-#[pyfunction]
-fn analyze_git_commits(
-    repo_path: String,
-    patterns: Vec<String>,
-    show_progress: Option<bool>,
-    py: Python<'_>,
-) -> PyResult<BTreeMap<String, HashMap<String, PyObject>>> {
-    let compiled_patterns = patterns
-        .into_iter()
-        .map(|p| Regex::new(&p))
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| PyValueError::new_err(e.to_string()))?;
-
-    py.allow_threads(|| {
-        let commits = analyze_commits_internal(&repo_path, &compiled_patterns, show_progress.unwrap_or(false))
-            .map_err(|e| PyValueError::new_err(e.to_string()))?;
-        
-        // Convert to Python-friendly format
-        let mut result = BTreeMap::new();
-        
-        for (commit_id, commit_data) in commits {
-            let mut commit_dict = HashMap::new();
-            
-            // Convert timestamp
-            commit_dict.insert("timestamp".to_string(), 
-                Python::with_gil(|py| commit_data.timestamp.into_py(py)));
-            
-            // Add message and author
-            commit_dict.insert("message".to_string(),
-                Python::with_gil(|py| commit_data.message.into_py(py)));
-            commit_dict.insert("author".to_string(),
-                Python::with_gil(|py| commit_data.author.into_py(py)));
-            
-            // Convert file stats
-            let stats_dict: HashMap<String, HashMap<String, i32>> = commit_data.stats
-                .into_iter()
-                .map(|(ext, stats)| {
-                    (ext, HashMap::from([
-                        ("lines".to_string(), stats.lines),
-                        ("files".to_string(), stats.files),
-                        ("additions".to_string(), stats.additions),
-                        ("deletions".to_string(), stats.deletions),
-                        ("modifications".to_string(), stats.modifications),
-                    ]))
-                })
-                .collect();
-            
-            commit_dict.insert("stats".to_string(),
-                Python::with_gil(|py| stats_dict.into_py(py)));
-            
-            result.insert(commit_id, commit_dict);
-        }
-        
-        Ok(result)
-    })
+#[cfg(feature = "python")]
+impl CommitData {
+    // Rough heap footprint, used to bound how many records are buffered
+    fn approx_bytes(&self) -> usize {
+        let stats: usize = self.stats.keys().map(|ext| ext.len() + 160).sum();
+        let notes: usize = self.notes.iter().flatten().map(|(r, n)| r.len() + n.len()).sum();
+        let files: usize = self.files.iter().flatten().map(|f| f.path.len() + 64).sum();
+        let oids = (self.parents.len() + 1) * 64;
+        std::mem::size_of::<Self>() + self.message.len() + self.author.len() + oids + stats + notes + files
+    }
 }
 
-#[pyfunction]
-fn analyze_git_repo(
-    repo_path: String,
-    patterns: Vec<String>,
-    show_progress: Option<bool>,
-    py: Python<'_>,
-) -> PyResult<HashMap<String, HashMap<String, HashMap<String, i32>>>> {
-    let compiled_patterns = patterns
-        .into_iter()
-        .map(|p| Regex::new(&p))
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| PyValueError::new_err(e.to_string()))?;
-
-    py.allow_threads(|| {
-        analyze_repo_internal(&repo_path, &compiled_patterns, show_progress.unwrap_or(false))
-            .map_err(|e| PyValueError::new_err(e.to_string()))
-    })
+struct RepoAnalysis {
+    months: MonthlyStats,
+    metadata: ScanMetadata,
+    sections: Sections,
 }
+
 fn analyze_repo_internal(
     repo_path: &str,
-    patterns: &[Regex],
-    show_progress: bool,
-) -> Result<HashMap<String, HashMap<String, HashMap<String, i32>>>, AnalyzerError> {
-    let repo = Repository::open(repo_path)?;
+    options: &AnalysisOptions,
+) -> Result<RepoAnalysis, AnalyzerError> {
+    let started = Instant::now();
+    let repo = open_repository(repo_path, options)?;
+    debug!(repo = repo_path, "opened repository");
+    let path_filter = PathFilter::for_repo(&repo, options.use_ignore_file)?;
     let unique_files = Arc::new(Mutex::new(HashSet::new()));
     let monthly_stats = Arc::new(Mutex::new(MonthlyStats::new()));
+    let mut sections = Sections::new(options);
+    let mut metadata = ScanMetadata {
+        repo_path: repo_path.to_string(),
+        keys: Some(MonthKeys::new(options)),
+        ..Default::default()
+    };
     
-    let mut revwalk = repo.revwalk()?;
-    revwalk.push_head()?;
-    
-    let commits: Vec<Oid> = revwalk.collect::<Result<Vec<_>, _>>()?;
+    // Commits are processed as the walk yields them; the total stays unknown
+    let replacements = Replacements::load(&repo, !options.ignore_replacements)?;
+    let revwalk = replacements.walk(&repo)?;
     
-    let progress_bar = if show_progress {
-        let pb = ProgressBar::new(commits.len() as u64);
-        pb.set_style(ProgressStyle::default_bar()
-            .template("[{elapsed_precise}] {bar:40.cyan/blue} {pos}/{len} commits")
-            .expect("Invalid progress bar template"));
-        Some(pb)
-    } else {
-        None
-    };
+    let mut progress = Progress::new(options)?;
 
-    commits.iter().try_for_each(|&oid| -> Result<(), AnalyzerError> {
-        if let Some(pb) = &progress_bar {
-            pb.inc(1);
+    let mut out_of_time = false;
+    let mut revwalk = revwalk.take_while(|_| {
+        out_of_time = !options.within_budget(started);
+        !out_of_time
+    });
+    let walked = revwalk.try_for_each(|oid| -> Result<(), AnalyzerError> {
+        options.check_cancelled()?;
+        let oid = oid?;
+        progress.inc();
+        metadata.commits_scanned += 1;
+        let commit = match replacements.find_commit(&repo, oid) {
+            Ok(commit) => commit,
+            Err(e) => return metadata.recover(oid, e.into(), options.on_error),
+        };
+        metadata.record_walked(&commit);
+        if replacements.affects(oid) {
+            metadata.commits_replaced += 1;
         }
-        let commit = repo.find_commit(oid)?;
+        let parent_count = replacements.parent_ids(oid, &commit).len();
         
-        // Check if commit author matches any pattern
-        let author = format!("{} <{}>", 
-            commit.author().name().unwrap_or(""),
-            commit.author().email().unwrap_or(""));
-        
-        if !patterns.is_empty() && !patterns.iter().any(|p| p.is_match(&author)) {
+        if let Some(filter) = options.rejected_by(&commit, parent_count) {
+            debug!(commit = %oid, filter, "commit filtered out");
             return Ok(());
         }
+        if options.reads_notes() {
+            match notes::read_notes(&repo, oid, &options.notes_refs) {
+                Ok(notes) if options.matches_notes(&notes) => {}
+                Ok(_) => {
+                    debug!(commit = %oid, filter = "notes", "commit filtered out");
+                    return Ok(());
+                }
+                Err(e) => return metadata.recover(oid, e, options.on_error),
+            }
+        }
+        metadata.record_match(parent_count);
         
-        process_commit(&repo, &commit, &unique_files, &monthly_stats)?;
+        let processed = match process_commit(
+            &repo,
+            &replacements,
+            oid,
+            &commit,
+            options,
+            &path_filter,
+            &unique_files,
+            &monthly_stats,
+            &mut sections,
+        ) {
+            Ok(processed) => processed,
+            Err(e) => return metadata.recover(oid, e, options.on_error),
+        };
+        match processed {
+            Processed::Counted { tracked, missing, bulk } => {
+                metadata.files_without_content += missing;
+                if bulk {
+                    metadata.commits_bulk += 1;
+                }
+                if !tracked {
+                    trace!(commit = %oid, "commit changed no tracked files");
+                    metadata.commits_empty += 1;
+                }
+            }
+            Processed::OutOfSizeBounds => {
+                debug!(commit = %oid, filter = "lines_changed", "commit filtered out");
+                metadata.commits_out_of_size_bounds += 1;
+            }
+            Processed::Bulk => {
+                debug!(commit = %oid, filter = "files_changed", "commit filtered out");
+                metadata.commits_bulk += 1;
+            }
+        }
         
         Ok(())
-    })?;
+    });
+    progress.finish();
+    if let Err(e) = walked {
+        metadata.stop_early(e, options)?;
+    }
+    if let (true, Some(budget)) = (out_of_time, options.time_budget_seconds) {
+        metadata.exhaust_budget(budget);
+    }
+    
+    let months = std::mem::take(&mut *monthly_stats.lock());
+    sections.finish(&repo, &months, options, &path_filter)?;
+    info!(
+        repo = repo_path,
+        scanned = metadata.commits_scanned,
+        matched = metadata.commits_matched,
+        elapsed_ms = started.elapsed().as_millis() as u64,
+        "analysis finished"
+    );
     
-    // Convert internal representation to Python-friendly format
-    let result = convert_to_python_format(&monthly_stats.lock());
-    Ok(result)
+    Ok(RepoAnalysis { months, metadata, sections })
 }
     
+// What process_commit did with a matched commit
+enum Processed {
+    // Whether it changed any tracked file, how many of those had no content
+    // to diff in a partial clone, and whether it went to the "<bulk>" bucket
+    Counted { tracked: bool, missing: usize, bulk: bool },
+    // Left out by min_lines_changed or max_lines_changed
+    OutOfSizeBounds,
+    // Left out by max_files_changed
+    Bulk,
+}
+
+#[allow(clippy::too_many_arguments)]
 fn process_commit(
     repo: &Repository,
+    replacements: &Replacements,
+    oid: Oid,
     commit: &Commit,
+    options: &AnalysisOptions,
+    path_filter: &PathFilter,
     unique_files: &Arc<Mutex<HashSet<String>>>,
     monthly_stats: &Arc<Mutex<MonthlyStats>>,
-) -> Result<(), AnalyzerError> {
-    let date: DateTime<Utc> = Utc.timestamp_opt(commit.author().when().seconds(), 0)
-        .single()
-        .unwrap_or_default();
-    let month_key = format!("{}-{:02}", date.year(), date.month());
+    sections: &mut Sections,
+) -> Result<Processed, AnalyzerError> {
+    let month_key = options.bucket_key(commit.author().when().seconds());
+    let author = authors::identity(&commit.author(), options.normalize_authors);
     
-    // Handle both first commit and subsequent commits
-    let diff = if let Ok(parent) = commit.parent(0) {
-        // Normal case - diff against parent
-        repo.diff_tree_to_tree(
-            Some(&parent.tree()?),
-            Some(&commit.tree()?),
-            None,
-        )?
+    let parent_ids = replacements.parent_ids(oid, commit);
+    let parents = replacements.parents(repo, oid, commit);
+    let diff = parent_diff(repo, parents.first(), commit, options)?;
+    let mut deltas = commit_deltas(repo, commit, &parents, &diff, path_filter, options)?;
+    if !deltas.is_empty() && !options.within_size_bounds(lines_changed(&deltas)) {
+        return Ok(Processed::OutOfSizeBounds);
+    }
+    let bulk = options.is_bulk(deltas.len());
+    if bulk {
+        match options.bulk_changes {
+            BulkChanges::Exclude => return Ok(Processed::Bulk),
+            BulkChanges::Separate => rebucket_bulk(&mut deltas),
+        }
+    }
+    let docs_deltas = if options.docs_churn {
+        diff::docs_deltas(repo, commit, &parents, &diff, path_filter, options)?
     } else {
-        // First commit - diff against empty tree
-        repo.diff_tree_to_tree(
-            None,
-            Some(&commit.tree()?),
-            None,
-        )?
+        Vec::new()
     };
+    let observed = diff::observed_diff(repo, commit, &parents, diff, path_filter, options)?;
+    sections.observe(repo, replacements, &month_key, commit, &parent_ids, &author, &observed, &deltas)?;
+    sections.observe_docs(&month_key, &deltas, &docs_deltas);
     
     let mut new_files = Vec::new();  // For file additions
-    let mut file_changes: HashMap<String, (i32, i32)> = HashMap::new();  // Track per-file changes
-    
-    diff.foreach(
-        &mut |delta, _| {
-            if let Some(path) = delta.new_file().path() {
-                let path_str = path.to_slash_lossy().into_owned();
-                let ext = Path::new(&path_str)
-                    .extension()
-                    .and_then(|e| e.to_str())
-                    .map(|e| format!(".{}", e.to_lowercase()))
-                    .unwrap_or_default();
-                
-                if TEXT_EXTENSIONS.contains(&ext.as_str()) {
-                    let mut unique = unique_files.lock();
-                    if !unique.contains(&path_str) {
-                        new_files.push(ext);  // Store just the extension
-                        unique.insert(path_str);
-                    }
-                }
+    {
+        let mut unique = unique_files.lock();
+        for delta in &deltas {
+            if unique.insert(delta.path.clone()) {
+                new_files.push(delta.ext.clone());  // Store just the extension
             }
-            true
-        },
-        None,
-        None,
-        Some(&mut |delta, _hunk, lines| {
-            if let Some(path) = delta.new_file().path() {
-                let ext = Path::new(path)
-                    .extension()
-                    .and_then(|e| e.to_str())
-                    .map(|e| format!(".{}", e.to_lowercase()))
-                    .unwrap_or_default();
-                
-                if TEXT_EXTENSIONS.contains(&ext.as_str()) {
-                    let mut additions = 0;
-                    let mut deletions = 0;
-                    
-                    // Count actual line changes
-                    match lines.origin() {
-                        '+' => additions += 1,
-                        '-' => deletions += 1,
-                        _ => {}
-                    }
-                    
-                    // Accumulate changes per file extension
-                    let entry = file_changes.entry(ext).or_insert((0, 0));
-                    entry.0 += additions;
-                    entry.1 += deletions;
-                }
-            }
-            true
-        }),
-    )?;
-
+        }
+    }
+    
     // Process both types of changes
-    let mut stats = monthly_stats.lock();
+    let mut commit_stats = stats_by_extension(&deltas);
     for ext in new_files {
-        let file_stats = stats.entry(month_key.clone())
-            .or_default()
-            .entry(ext)
-            .or_default();
-        file_stats.files += 1;
+        commit_stats.entry(ext).or_default().files += 1;
+    }
+    if let Some(group_months) = &mut sections.group_months {
+        let groups = if !options.groups.is_empty() {
+            options.group_labels(&commit.author())
+        } else if options.pivot == Pivot::Pattern {
+            options.matching_labels(&commit.author())
+        } else {
+            vec![author]
+        };
+        for group in groups {
+            let group_stats = group_months.entry(group).or_default().entry(month_key.clone()).or_default();
+            for (ext, changes) in &commit_stats {
+                group_stats.entry(ext.clone()).or_default().add(changes);
+            }
+        }
     }
     
-    for (ext, (additions, deletions)) in file_changes {
-        let file_stats = stats.entry(month_key.clone())
-            .or_default()
-            .entry(ext)
-            .or_default();
-        file_stats.additions += additions;
-        file_stats.deletions += deletions;
-        file_stats.lines += additions - deletions;
-        file_stats.modifications += 1;  // Count one modification per file, not per hunk
+    let mut stats = monthly_stats.lock();
+    let month_stats = stats.entry(month_key).or_default();
+    for (ext, changes) in commit_stats {
+        month_stats.entry(ext).or_default().add(&changes);
     }
     
-    Ok(())
+    let missing = deltas.iter().filter(|d| d.content_missing).count();
+    Ok(Processed::Counted { tracked: !deltas.is_empty(), missing, bulk })
+}
+
+fn rebucket_bulk(deltas: &mut [FileDelta]) {
+    for delta in deltas {
+        delta.ext = classify::BULK_BUCKET.to_string();
+    }
+}
+
+// Added plus deleted lines, the size min_lines_changed and max_lines_changed
+// bound
+fn lines_changed(deltas: &[FileDelta]) -> u64 {
+    deltas.iter().map(|d| (d.additions + d.deletions) as u64).sum()
+}
+
+fn month_key(timestamp: i64) -> String {
+    let date: DateTime<Utc> = Utc.timestamp_opt(timestamp, 0)
+        .single()
+        .unwrap_or_default();
+    format!("{}-{:02}", date.year(), date.month())
+}
+
+// Sums line changes per extension, counting one modification per extension
+// rather than per hunk
+fn stats_by_extension(deltas: &[FileDelta]) -> HashMap<String, FileStats> {
+    let mut stats: HashMap<String, FileStats> = HashMap::new();
+    let mut modified = HashSet::new();
+    for delta in deltas {
+        if !delta.has_hunks && delta.metrics.is_empty() {
+            continue;
+        }
+        let file_stats = stats.entry(delta.ext.clone()).or_default();
+        file_stats.additions += delta.additions;
+        file_stats.deletions += delta.deletions;
+        file_stats.lines += delta.additions - delta.deletions;
+        for (name, value) in &delta.metrics {
+            *file_stats.metrics.entry(name).or_default() += value;
+        }
+        if delta.has_hunks && modified.insert(delta.ext.as_str()) {
+            file_stats.modifications += 1;
+        }
+    }
+    stats
 }
     
 fn convert_to_python_format(
     monthly_stats: &MonthlyStats,
-) -> HashMap<String, HashMap<String, HashMap<String, i32>>> {
+) -> PyMonthlyStats {
         let mut result = HashMap::new();
         
         for (month, exts) in monthly_stats {
             let mut month_data = HashMap::new();
             
             for (ext, stats) in exts {
-                let stat_map = HashMap::from([
-                    ("lines".to_string(), stats.lines),
-                    ("files".to_string(), stats.files),
-                    ("additions".to_string(), stats.additions),
-                    ("deletions".to_string(), stats.deletions),
-                    ("modifications".to_string(), stats.modifications),
-                    ("repos".to_string(), stats.repos),
-                ]);
-                
-                month_data.insert(ext.clone(), stat_map);
+                month_data.insert(ext.clone(), stats.to_map(true));
             }
             
             result.insert(month.clone(), month_data);
@@ -306,132 +671,171 @@ fn convert_to_python_format(
 
 fn analyze_commits_internal(
     repo_path: &str,
-    patterns: &[Regex],
-    show_progress: bool,
-) -> Result<BTreeMap<String, CommitData>, AnalyzerError> {
-    let repo = Repository::open(repo_path)?;
+    options: &AnalysisOptions,
+) -> Result<(BTreeMap<String, CommitData>, ScanMetadata), AnalyzerError> {
     let mut results = BTreeMap::new();
+    let metadata = analyze_commits_with(repo_path, options, |oid, data| {
+        results.insert(oid, data);
+        Ok(())
+    })?;
+    Ok((results, metadata))
+}
+
+// Hands each commit record to `on_commit` as soon as it is built, so callers
+// decide how many are held at once
+fn analyze_commits_with(
+    repo_path: &str,
+    options: &AnalysisOptions,
+    mut on_commit: impl FnMut(String, CommitData) -> Result<(), AnalyzerError>,
+) -> Result<ScanMetadata, AnalyzerError> {
+    let started = Instant::now();
+    let repo = open_repository(repo_path, options)?;
+    debug!(repo = repo_path, "opened repository");
+    let path_filter = PathFilter::for_repo(&repo, options.use_ignore_file)?;
+    let mut metadata = ScanMetadata {
+        repo_path: repo_path.to_string(),
+        ..Default::default()
+    };
     
-    let mut revwalk = repo.revwalk()?;
-    revwalk.push_head()?;
-    
-    let commits: Vec<Oid> = revwalk.collect::<Result<Vec<_>, _>>()?;
+    // Commits are processed as the walk yields them; the total stays unknown
+    let replacements = Replacements::load(&repo, !options.ignore_replacements)?;
+    let revwalk = replacements.walk(&repo)?;
     
-    let progress_bar = if show_progress {
-        let pb = ProgressBar::new(commits.len() as u64);
-        pb.set_style(ProgressStyle::default_bar()
-            .template("[{elapsed_precise}] {bar:40.cyan/blue} {pos}/{len} commits")
-            .expect("Invalid progress bar template"));
-        Some(pb)
-    } else {
-        None
-    };
+    let mut progress = Progress::new(options)?;
 
-    for oid in commits {
-        if let Some(pb) = &progress_bar {
-            pb.inc(1);
-        }
-        let commit = repo.find_commit(oid)?;
-        
-        // Check if commit author matches any pattern
-        let author = format!("{} <{}>", 
-            commit.author().name().unwrap_or(""),
-            commit.author().email().unwrap_or(""));
+    let mut out_of_time = false;
+    let walk = || -> Result<(), AnalyzerError> {
+        for oid in revwalk {
+            options.check_cancelled()?;
+            if !options.within_budget(started) {
+                out_of_time = true;
+                break;
+            }
+            let oid = oid?;
+            progress.inc();
+            metadata.commits_scanned += 1;
+            let commit = match replacements.find_commit(&repo, oid) {
+                Ok(commit) => commit,
+                Err(e) => {
+                    metadata.recover(oid, e.into(), options.on_error)?;
+                    continue;
+                }
+            };
+            metadata.record_walked(&commit);
+            if replacements.affects(oid) {
+                metadata.commits_replaced += 1;
+            }
         
-        if !patterns.is_empty() && !patterns.iter().any(|p| p.is_match(&author)) {
-            continue;
-        }
+            let author = authors::identity(&commit.author(), options.normalize_authors);
+            let parent_ids = replacements.parent_ids(oid, &commit);
         
-        let diff = if let Ok(parent) = commit.parent(0) {
-            repo.diff_tree_to_tree(
-                Some(&parent.tree()?),
-                Some(&commit.tree()?),
-                None,
-            )?
-        } else {
-            repo.diff_tree_to_tree(
-                None,
-                Some(&commit.tree()?),
-                None,
-            )?
-        };
+            if let Some(filter) = options.rejected_by(&commit, parent_ids.len()) {
+                debug!(commit = %oid, filter, "commit filtered out");
+                continue;
+            }
+            let notes = if options.reads_notes() {
+                match notes::read_notes(&repo, oid, &options.notes_refs) {
+                    Ok(notes) => notes,
+                    Err(e) => {
+                        metadata.recover(oid, e, options.on_error)?;
+                        continue;
+                    }
+                }
+            } else {
+                BTreeMap::new()
+            };
+            if !options.matches_notes(&notes) {
+                debug!(commit = %oid, filter = "notes", "commit filtered out");
+                continue;
+            }
+            metadata.record_match(parent_ids.len());
         
-        let mut file_changes: HashMap<String, (i32, i32)> = HashMap::new();
-        let mut new_files: HashSet<String> = HashSet::new();
+            let parents = replacements.parents(&repo, oid, &commit);
+            let changes = parent_diff(&repo, parents.first(), &commit, options).and_then(|diff| {
+                let deltas = commit_deltas(&repo, &commit, &parents, &diff, &path_filter, options)?;
+                let docs_deltas = options
+                    .docs_churn
+                    .then(|| diff::docs_deltas(&repo, &commit, &parents, &diff, &path_filter, options))
+                    .transpose()?;
+                Ok((deltas, docs_deltas))
+            });
+            let (mut deltas, docs_deltas) = match changes {
+                Ok(changes) => changes,
+                Err(e) => {
+                    metadata.recover(oid, e, options.on_error)?;
+                    continue;
+                }
+            };
         
-        // Collect file changes
-        diff.foreach(
-            &mut |delta, _| {
-                if let Some(path) = delta.new_file().path() {
-                    let ext = Path::new(path)
-                        .extension()
-                        .and_then(|e| e.to_str())
-                        .map(|e| format!(".{}", e.to_lowercase()))
-                        .unwrap_or_default();
-                    
-                    if TEXT_EXTENSIONS.contains(&ext.as_str()) {
-                        new_files.insert(ext);
-                    }
+            metadata.files_without_content += deltas.iter().filter(|d| d.content_missing).count();
+            if deltas.is_empty() {
+                trace!(commit = %oid, "commit changed no tracked files");
+                metadata.commits_empty += 1;
+                if !options.include_empty {
+                    continue;
                 }
-                true
-            },
-            None,
-            None,
-            Some(&mut |delta, _hunk, lines| {
-                if let Some(path) = delta.new_file().path() {
-                    let ext = Path::new(path)
-                        .extension()
-                        .and_then(|e| e.to_str())
-                        .map(|e| format!(".{}", e.to_lowercase()))
-                        .unwrap_or_default();
-                    
-                    if TEXT_EXTENSIONS.contains(&ext.as_str()) {
-                        let entry = file_changes.entry(ext).or_insert((0, 0));
-                        match lines.origin() {
-                            '+' => entry.0 += 1,
-                            '-' => entry.1 += 1,
-                            _ => {}
-                        }
-                    }
+            } else if !options.within_size_bounds(lines_changed(&deltas)) {
+                debug!(commit = %oid, filter = "lines_changed", "commit filtered out");
+                metadata.commits_out_of_size_bounds += 1;
+                continue;
+            } else if options.is_bulk(deltas.len()) {
+                metadata.commits_bulk += 1;
+                if options.bulk_changes == BulkChanges::Exclude {
+                    debug!(commit = %oid, filter = "files_changed", "commit filtered out");
+                    continue;
                 }
-                true
-            }),
-        )?;
+                rebucket_bulk(&mut deltas);
+            }
+
+            let new_files: HashSet<String> = deltas.iter().map(|d| d.ext.clone()).collect();
         
-        // Aggregate stats per extension
-        let mut stats = HashMap::new();
+            // Aggregate stats per extension
+            let mut stats = stats_by_extension(&deltas);
         
-        for ext in new_files {
-            let file_stats: &mut FileStats = stats.entry(ext).or_default();
-            file_stats.files += 1;
-        }
+            for ext in new_files {
+                stats.entry(ext).or_default().files += 1;
+            }
         
-        for (ext, (additions, deletions)) in file_changes {
-            let file_stats = stats.entry(ext).or_default();
-            file_stats.additions += additions;
-            file_stats.deletions += deletions;
-            file_stats.lines += additions - deletions;
-            file_stats.modifications += 1;
+            on_commit(
+                oid.to_string(),
+                CommitData {
+                    timestamp: commit.author().when().seconds(),
+                    message: commit.message().unwrap_or("").to_string(),
+                    author,
+                    stats,
+                    touches_docs: docs_deltas.map(|docs_deltas| report::touches_docs(&deltas, &docs_deltas)),
+                    parents: parent_ids.iter().map(Oid::to_string).collect(),
+                    tree: commit.tree_id().to_string(),
+                    notes: options.include_notes.then_some(notes),
+                    gitmoji: options.compiled.gitmoji.as_ref().map(|classifier| {
+                        classifier.classify(messages::split(commit.message().unwrap_or("")).0)
+                    }),
+                    squash_merge: options
+                        .compiled
+                        .squash
+                        .as_ref()
+                        .map(|detector| detector.detect(&commit, parent_ids.len(), lines_changed(&deltas))),
+                    files: options.include_files.then(|| deltas.iter().map(ChangedFile::from).collect()),
+                },
+            )?;
         }
-        
-        // Store commit data
-        results.insert(
-            oid.to_string(),
-            CommitData {
-                timestamp: commit.author().when().seconds(),
-                message: commit.message().unwrap_or("").to_string(),
-                author,
-                stats,
-            }
-        );
+        Ok(())
+    };
+    let walked = walk();
+    progress.finish();
+    if let Err(e) = walked {
+        metadata.stop_early(e, options)?;
+    }
+    if let (true, Some(budget)) = (out_of_time, options.time_budget_seconds) {
+        metadata.exhaust_budget(budget);
     }
+    info!(
+        repo = repo_path,
+        scanned = metadata.commits_scanned,
+        matched = metadata.commits_matched,
+        elapsed_ms = started.elapsed().as_millis() as u64,
+        "analysis finished"
+    );
     
-    Ok(results)
-}
-
-#[pymodule]
-fn repo_scan_rs(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
-    m.add_function(wrap_pyfunction!(analyze_git_repo, m)?)?;
-    m.add_function(wrap_pyfunction!(analyze_git_commits, m)?)?;
-    Ok(())
+    Ok(metadata)
 }
@@ -0,0 +1,123 @@
+use git2::{Commit, Diff, ObjectType, Repository, TreeWalkMode, TreeWalkResult};
+use path_slash::PathExt;
+use regex::Regex;
+use serde::Serialize;
+
+use crate::AnalyzerError;
+
+// (SPDX id, phrases that must all appear), checked in order so that the more
+// specific texts win over the ones they embed
+const LICENSE_SIGNATURES: &[(&str, &[&str])] = &[
+    ("AGPL-3.0", &["GNU AFFERO GENERAL PUBLIC LICENSE", "Version 3"]),
+    ("LGPL-3.0", &["GNU LESSER GENERAL PUBLIC LICENSE", "Version 3"]),
+    ("LGPL-2.1", &["GNU LESSER GENERAL PUBLIC LICENSE", "Version 2.1"]),
+    ("GPL-3.0", &["GNU GENERAL PUBLIC LICENSE", "Version 3"]),
+    ("GPL-2.0", &["GNU GENERAL PUBLIC LICENSE", "Version 2"]),
+    ("Apache-2.0", &["Apache License", "Version 2.0"]),
+    ("MPL-2.0", &["Mozilla Public License", "2.0"]),
+    ("BSD-3-Clause", &["Redistribution and use", "Neither the name"]),
+    ("BSD-2-Clause", &["Redistribution and use", "THIS SOFTWARE IS PROVIDED"]),
+    ("ISC", &["Permission to use, copy, modify, and/or distribute this software"]),
+    ("MIT", &["Permission is hereby granted, free of charge"]),
+    ("Unlicense", &["This is free and unencumbered software released into the public domain"]),
+    ("CC0-1.0", &["CC0 1.0 Universal"]),
+    ("BSL-1.0", &["Boost Software License - Version 1.0"]),
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LicenseFile {
+    pub path: String,
+    // SPDX identifier, or null if the text matched no known license
+    pub license: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LicenseChange {
+    pub commit: String,
+    pub timestamp: i64,
+    pub author: String,
+    pub path: String,
+    pub status: String,
+}
+
+// License files present at HEAD and every commit in the scan that touched one
+#[derive(Debug, Clone, Serialize)]
+pub struct LicenseReport {
+    pub files: Vec<LicenseFile>,
+    pub changes: Vec<LicenseChange>,
+    #[serde(skip)]
+    file_name: Regex,
+}
+
+impl Default for LicenseReport {
+    fn default() -> Self {
+        LicenseReport {
+            files: Vec::new(),
+            changes: Vec::new(),
+            file_name: Regex::new(r"(?i)^(licen[cs]e|copying|unlicense)([-._].*)?$")
+                .expect("valid regex"),
+        }
+    }
+}
+
+impl LicenseReport {
+    fn is_license_file(&self, path: &str) -> bool {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        self.file_name.is_match(name)
+    }
+
+    pub fn observe(&mut self, commit: &Commit, author: &str, diff: &Diff) {
+        for delta in diff.deltas() {
+            let Some(path) = delta.new_file().path().or(delta.old_file().path()) else {
+                continue;
+            };
+            let path = path.to_slash_lossy().into_owned();
+            if !self.is_license_file(&path) {
+                continue;
+            }
+            self.changes.push(LicenseChange {
+                commit: commit.id().to_string(),
+                timestamp: commit.author().when().seconds(),
+                author: author.to_string(),
+                path,
+                status: format!("{:?}", delta.status()).to_lowercase(),
+            });
+        }
+    }
+
+    // Identifies the license files in the HEAD tree
+    pub fn finish(&mut self, repo: &Repository) -> Result<(), AnalyzerError> {
+        self.changes.sort_by_key(|c| c.timestamp);
+
+        let Ok(head) = repo.head() else {
+            return Ok(());
+        };
+        let tree = head.peel_to_tree()?;
+        let mut found = Vec::new();
+        tree.walk(TreeWalkMode::PreOrder, |dir, entry| {
+            if entry.kind() == Some(ObjectType::Blob) {
+                let path = format!("{}{}", dir, entry.name().unwrap_or(""));
+                if self.is_license_file(&path) {
+                    found.push((path, entry.id()));
+                }
+            }
+            TreeWalkResult::Ok
+        })?;
+
+        for (path, id) in found {
+            let blob = repo.find_blob(id)?;
+            let text = String::from_utf8_lossy(blob.content());
+            self.files.push(LicenseFile { path, license: identify(&text).map(str::to_string) });
+        }
+        Ok(())
+    }
+}
+
+pub fn identify(text: &str) -> Option<&'static str> {
+    // Normalize line wrapping so phrases split across lines still match
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    LICENSE_SIGNATURES
+        .iter()
+        .find(|(_, phrases)| phrases.iter().all(|p| text.contains(p)))
+        .map(|(id, _)| *id)
+}
@@ -0,0 +1,132 @@
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicI32, AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use pyo3::prelude::*;
+use pyo3::sync::GILOnceCell;
+use tracing::field::{Field, Visit};
+use tracing::subscriber::Interest;
+use tracing::{Event, Level, Metadata, Subscriber};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+
+pub const LOGGER_NAME: &str = "repo_scan_rs";
+
+// logging has no TRACE; it sits below logging.DEBUG
+const TRACE_LEVEL: i32 = 5;
+
+// How long the Python logger's effective level is trusted before re-reading it
+const LEVEL_REFRESH_MILLIS: u64 = 1000;
+
+static LOGGER: GILOnceCell<PyObject> = GILOnceCell::new();
+static LEVEL: AtomicI32 = AtomicI32::new(TRACE_LEVEL);
+static LEVEL_READ_AT: AtomicU64 = AtomicU64::new(0);
+
+// Forwards tracing events to `logging.getLogger("repo_scan_rs")`. Events below
+// the logger's effective level are dropped without taking the GIL.
+struct PythonLogging;
+
+// Installs the bridge as the global subscriber; a no-op if the host process
+// already set one
+pub fn install(py: Python<'_>) -> PyResult<()> {
+    let logging = py.import("logging")?;
+    let trace_name: String = logging.call_method1("getLevelName", (TRACE_LEVEL,))?.extract()?;
+    if trace_name.starts_with("Level ") {
+        // Unnamed so far; keep any name the application registered itself
+        logging.call_method1("addLevelName", (TRACE_LEVEL, "TRACE"))?;
+    }
+    let logger = logging.call_method1("getLogger", (LOGGER_NAME,))?;
+    let _ = LOGGER.set(py, logger.into());
+    sync_level(py);
+    let _ = tracing::subscriber::set_global_default(tracing_subscriber::registry().with(PythonLogging));
+    Ok(())
+}
+
+// Re-reads the logger's effective level. Called when a Python call starts so
+// a level set just before it applies to the whole scan.
+pub fn sync_level(py: Python<'_>) {
+    let Some(logger) = LOGGER.get(py) else {
+        return;
+    };
+    if let Ok(level) = logger.call_method0(py, "getEffectiveLevel").and_then(|l| l.extract::<i32>(py)) {
+        LEVEL.store(level, Ordering::Relaxed);
+    }
+    LEVEL_READ_AT.store(now_millis(), Ordering::Relaxed);
+}
+
+fn python_level(level: &Level) -> i32 {
+    match *level {
+        Level::ERROR => 40,
+        Level::WARN => 30,
+        Level::INFO => 20,
+        Level::DEBUG => 10,
+        Level::TRACE => TRACE_LEVEL,
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+// Levels changed during a long scan are picked up within a second
+fn effective_level() -> i32 {
+    let now = now_millis();
+    let read_at = LEVEL_READ_AT.load(Ordering::Relaxed);
+    if now.saturating_sub(read_at) >= LEVEL_REFRESH_MILLIS
+        && LEVEL_READ_AT
+            .compare_exchange(read_at, now, Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok()
+    {
+        Python::with_gil(sync_level);
+    }
+    LEVEL.load(Ordering::Relaxed)
+}
+
+impl<S: Subscriber> Layer<S> for PythonLogging {
+    // Python's level can change at runtime, so nothing is cached per callsite
+    fn register_callsite(&self, _metadata: &'static Metadata<'static>) -> Interest {
+        Interest::sometimes()
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>, _ctx: Context<'_, S>) -> bool {
+        python_level(metadata.level()) >= effective_level()
+    }
+
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut message = Message::default();
+        event.record(&mut message);
+        let level = python_level(event.metadata().level());
+        Python::with_gil(|py| {
+            if let Some(logger) = LOGGER.get(py) {
+                // A failing handler must not abort the scan
+                let _ = logger.call_method1(py, "log", (level, "%s", message.text));
+            }
+        });
+    }
+}
+
+// "message key=value ..." built from an event's fields
+#[derive(Default)]
+struct Message {
+    text: String,
+}
+
+impl Visit for Message {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.text.insert_str(0, value);
+        } else {
+            let _ = write!(self.text, " {}={}", field.name(), value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.text.insert_str(0, &format!("{:?}", value));
+        } else {
+            let _ = write!(self.text, " {}={:?}", field.name(), value);
+        }
+    }
+}
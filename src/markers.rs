@@ -0,0 +1,85 @@
+use std::collections::BTreeMap;
+
+use regex::Regex;
+use serde::Serialize;
+
+use crate::diff::{FileDelta, Hunk};
+
+pub const DEFAULT_MARKERS: &[&str] = &["TODO", "FIXME", "HACK"];
+
+pub fn marker_regex(markers: &[String]) -> Result<Regex, regex::Error> {
+    let alternatives: Vec<String> = markers.iter().map(|m| regex::escape(m)).collect();
+    Regex::new(&format!(r"\b({})\b", alternatives.join("|")))
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+pub struct MarkerCounts {
+    pub added: i32,
+    pub removed: i32,
+}
+
+impl MarkerCounts {
+    fn add(&mut self, other: MarkerCounts) {
+        self.added += other.added;
+        self.removed += other.removed;
+    }
+}
+
+// Marker occurrences in the added and removed lines of a file, per marker
+pub fn count_markers(hunks: &[Hunk], regex: &Regex) -> BTreeMap<String, MarkerCounts> {
+    let mut counts: BTreeMap<String, MarkerCounts> = BTreeMap::new();
+    for hunk in hunks {
+        for line in &hunk.added {
+            for found in regex.find_iter(line) {
+                counts.entry(found.as_str().to_string()).or_default().added += 1;
+            }
+        }
+        for line in &hunk.removed {
+            for found in regex.find_iter(line) {
+                counts.entry(found.as_str().to_string()).or_default().removed += 1;
+            }
+        }
+    }
+    counts
+}
+
+pub fn totals(counts: &BTreeMap<String, MarkerCounts>) -> MarkerCounts {
+    let mut total = MarkerCounts::default();
+    counts.values().for_each(|c| total.add(*c));
+    total
+}
+
+// Marker activity for one month, overall and broken down by marker and by the
+// directory containing the file
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct MarkersMonth {
+    pub added: i32,
+    pub removed: i32,
+    pub by_marker: BTreeMap<String, MarkerCounts>,
+    pub by_directory: BTreeMap<String, MarkerCounts>,
+}
+
+impl MarkersMonth {
+    pub fn observe(&mut self, deltas: &[FileDelta], regex: &Regex) {
+        for delta in deltas {
+            let counts = count_markers(&delta.hunks, regex);
+            if counts.is_empty() {
+                continue;
+            }
+            let total = totals(&counts);
+            self.added += total.added;
+            self.removed += total.removed;
+            self.by_directory.entry(directory_of(&delta.path)).or_default().add(total);
+            for (marker, count) in counts {
+                self.by_marker.entry(marker).or_default().add(count);
+            }
+        }
+    }
+}
+
+pub fn directory_of(path: &str) -> String {
+    match path.rsplit_once('/') {
+        Some((dir, _)) => dir.to_string(),
+        None => ".".to_string(),
+    }
+}
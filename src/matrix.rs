@@ -0,0 +1,133 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::Serialize;
+
+use crate::authors;
+use crate::diff::{commit_deltas, parent_diff};
+use crate::history::Replacements;
+use crate::options::AnalysisOptions;
+use crate::paths::PathFilter;
+use crate::{open_repository, AnalyzerError, ScanMetadata};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatrixMetric {
+    Commits,
+    Additions,
+    Deletions,
+    Churn,
+}
+
+impl MatrixMetric {
+    pub fn parse(value: &str) -> Result<Self, AnalyzerError> {
+        match value {
+            "commits" => Ok(MatrixMetric::Commits),
+            "additions" => Ok(MatrixMetric::Additions),
+            "deletions" => Ok(MatrixMetric::Deletions),
+            "churn" => Ok(MatrixMetric::Churn),
+            other => Err(AnalyzerError::InvalidOption(format!(
+                "matrix metric must be \"commits\", \"additions\", \"deletions\" or \"churn\", got {:?}",
+                other
+            ))),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MatrixMetric::Commits => "commits",
+            MatrixMetric::Additions => "additions",
+            MatrixMetric::Deletions => "deletions",
+            MatrixMetric::Churn => "churn",
+        }
+    }
+}
+
+// Authors as rows and extensions as columns, both sorted; values[i][j] is the
+// metric for authors[i] in extensions[j], zero where they never met
+#[derive(Debug, Clone, Serialize)]
+pub struct ContributionMatrix {
+    pub metric: &'static str,
+    pub authors: Vec<String>,
+    pub extensions: Vec<String>,
+    pub values: Vec<Vec<i64>>,
+}
+
+// The window is the options' since/until; every other filter applies as in
+// analyze_git_repo. A commit counts once per extension it touched.
+pub fn author_extension_matrix(
+    repo_path: &str,
+    options: &AnalysisOptions,
+    metric: MatrixMetric,
+) -> Result<(ContributionMatrix, ScanMetadata), AnalyzerError> {
+    let repo = open_repository(repo_path, options)?;
+    let path_filter = PathFilter::for_repo(&repo, options.use_ignore_file)?;
+    let mut metadata = ScanMetadata {
+        repo_path: repo_path.to_string(),
+        ..Default::default()
+    };
+    let mut cells: BTreeMap<(String, String), i64> = BTreeMap::new();
+    let mut extensions = BTreeSet::new();
+
+    let replacements = Replacements::load(&repo, !options.ignore_replacements)?;
+    for oid in replacements.walk(&repo)? {
+        let oid = oid?;
+        let commit = replacements.find_commit(&repo, oid)?;
+        metadata.commits_scanned += 1;
+        if replacements.affects(oid) {
+            metadata.commits_replaced += 1;
+        }
+        let parent_count = replacements.parent_ids(oid, &commit).len();
+        if !options.matches_author(&commit.author())
+            || !options.in_date_range(commit.author().when().seconds())
+            || options.skips_commit(parent_count)
+        {
+            continue;
+        }
+        metadata.record_match(parent_count);
+
+        let author = authors::identity(&commit.author(), options.normalize_authors);
+        let parents = replacements.parents(&repo, oid, &commit);
+        let diff = parent_diff(&repo, parents.first(), &commit, options)?;
+        let mut per_ext: BTreeMap<&str, i64> = BTreeMap::new();
+        let deltas = commit_deltas(&repo, &commit, &parents, &diff, &path_filter, options)?;
+        for delta in &deltas {
+            let value = match metric {
+                // Counted once per touched extension below
+                MatrixMetric::Commits => 0,
+                MatrixMetric::Additions => i64::from(delta.additions),
+                MatrixMetric::Deletions => i64::from(delta.deletions),
+                MatrixMetric::Churn => i64::from(delta.additions) + i64::from(delta.deletions),
+            };
+            *per_ext.entry(&delta.ext).or_default() += value;
+        }
+        for (ext, value) in per_ext {
+            let value = if metric == MatrixMetric::Commits { 1 } else { value };
+            extensions.insert(ext.to_string());
+            *cells.entry((author.clone(), ext.to_string())).or_default() += value;
+        }
+    }
+
+    let authors: Vec<String> = cells
+        .keys()
+        .map(|(author, _)| author.clone())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let extensions: Vec<String> = extensions.into_iter().collect();
+    let values = authors
+        .iter()
+        .map(|author| {
+            extensions
+                .iter()
+                .map(|ext| cells.get(&(author.clone(), ext.clone())).copied().unwrap_or(0))
+                .collect()
+        })
+        .collect();
+
+    let matrix = ContributionMatrix {
+        metric: metric.as_str(),
+        authors,
+        extensions,
+        values,
+    };
+    Ok((matrix, metadata))
+}
@@ -0,0 +1,173 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde_json::{Map, Value};
+
+use crate::AnalyzerError;
+
+// One extension of one month in the merged result
+#[derive(Default)]
+struct MergedStats {
+    counters: BTreeMap<String, i64>,
+    // Repositories known by path count once however many results (shards,
+    // incremental runs) they appear in; results already combining several
+    // repositories, like the "merged" months of scan_directory, add their own
+    // "repos" count
+    repo_paths: BTreeSet<String>,
+    repos: i64,
+}
+
+// The months and, for an envelope, the metadata of an analyze_git_repo result
+pub fn result_parts(result: &Value) -> (&Value, Option<&Map<String, Value>>) {
+    match (result.get("months"), result.get("metadata")) {
+        (Some(months), Some(Value::Object(metadata))) => (months, Some(metadata)),
+        _ => (result, None),
+    }
+}
+
+// Combines analyze_git_repo results, nested or envelope, into one. Counters
+// are summed month by month; "repos" is a distinct count. Envelope results
+// keep their summed metadata, with stopped_early and time_budget_exhausted
+// listed per input, and must share their bucket. The optional report sections
+// are left out as they cannot be combined from their output.
+pub fn merge_results(results: &[Value]) -> Result<Value, AnalyzerError> {
+    let mut merged: BTreeMap<String, BTreeMap<String, MergedStats>> = BTreeMap::new();
+    let mut metadata: Option<Map<String, Value>> = None;
+    let mut repo_paths: BTreeSet<String> = BTreeSet::new();
+
+    for (idx, result) in results.iter().enumerate() {
+        let invalid = || {
+            AnalyzerError::InvalidOption(format!("results[{}] is not an analyze_git_repo result", idx))
+        };
+        let (months, result_metadata) = result_parts(result);
+        let repo_path = result_metadata
+            .and_then(|m| m.get("repo_path"))
+            .and_then(Value::as_str)
+            .filter(|path| !path.is_empty());
+
+        for (month, extensions) in months.as_object().ok_or_else(invalid)? {
+            let merged_month = merged.entry(month.clone()).or_default();
+            for (ext, stats) in extensions.as_object().ok_or_else(invalid)? {
+                let merged_stats = merged_month.entry(ext.clone()).or_default();
+                for (name, value) in stats.as_object().ok_or_else(invalid)? {
+                    let value = value.as_i64().ok_or_else(invalid)?;
+                    if name == "repos" {
+                        merged_stats.repos += value;
+                    } else {
+                        *merged_stats.counters.entry(name.clone()).or_default() += value;
+                    }
+                }
+                merged_stats.repo_paths.extend(repo_path.map(str::to_string));
+            }
+        }
+
+        if let Some(result_metadata) = result_metadata {
+            if let Some(metadata) = &metadata {
+                for key in KEYING {
+                    let (ours, theirs) = (keying(metadata, key), keying(result_metadata, key));
+                    if ours != theirs {
+                        return Err(AnalyzerError::InvalidOption(format!(
+                            "results[{}] has {} {} where the results before it have {}",
+                            idx, key, theirs, ours
+                        )));
+                    }
+                }
+            }
+            absorb_metadata(metadata.get_or_insert_with(Map::new), result_metadata, repo_path);
+            repo_paths.extend(repo_path.map(str::to_string));
+            // A result merged before lists its repositories instead
+            if let Some(Value::Array(paths)) = result_metadata.get("repo_paths") {
+                repo_paths.extend(paths.iter().filter_map(Value::as_str).map(str::to_string));
+            }
+        }
+    }
+
+    let months: Map<String, Value> = merged
+        .into_iter()
+        .map(|(month, extensions)| {
+            let extensions: Map<String, Value> = extensions
+                .into_iter()
+                .map(|(ext, stats)| {
+                    let mut map: Map<String, Value> =
+                        stats.counters.into_iter().map(|(name, value)| (name, value.into())).collect();
+                    map.insert("repos".to_string(), (stats.repo_paths.len() as i64 + stats.repos).into());
+                    (ext, Value::Object(map))
+                })
+                .collect();
+            (month, Value::Object(extensions))
+        })
+        .collect();
+
+    let Some(mut metadata) = metadata else {
+        return Ok(Value::Object(months));
+    };
+    // One repository keeps its repo_path; several are listed instead
+    if repo_paths.len() == 1 {
+        metadata.remove("repo_paths");
+        metadata.insert("repo_path".to_string(), repo_paths.pop_first().unwrap_or_default().into());
+    } else {
+        metadata.remove("repo_path");
+        metadata.insert("repo_paths".to_string(), repo_paths.into_iter().collect());
+    }
+    let mut envelope = Map::new();
+    envelope.insert("months".to_string(), Value::Object(months));
+    envelope.insert("metadata".to_string(), Value::Object(metadata));
+    Ok(Value::Object(envelope))
+}
+
+// Metadata fields saying what the month keys are, which must agree for the
+// months to line up
+const KEYING: &[&str] = &["bucket", "fiscal_year_start_month", "bucket_format"];
+
+// Metadata fields about how one scan ended, listed per input in the merged
+// metadata with the input's repo_path
+const PER_INPUT: &[&str] = &["stopped_early", "time_budget_exhausted"];
+
+// Results from before the metadata recorded the bucket are monthly
+fn keying(metadata: &Map<String, Value>, key: &str) -> Value {
+    match metadata.get(key) {
+        None if key == "bucket" => Value::from("month"),
+        value => value.cloned().unwrap_or(Value::Null),
+    }
+}
+
+// Sums the counters of another result's metadata and appends its errors and
+// PER_INPUT fields; anything else keeps the first value seen
+fn absorb_metadata(
+    metadata: &mut Map<String, Value>,
+    other: &Map<String, Value>,
+    repo_path: Option<&str>,
+) {
+    for (key, value) in other {
+        if PER_INPUT.contains(&key.as_str()) {
+            let entries = metadata.entry(key.clone()).or_insert_with(|| Value::Array(Vec::new()));
+            let Value::Array(entries) = entries else {
+                continue;
+            };
+            match value {
+                // Already merged from several inputs
+                Value::Array(more) => entries.extend(more.iter().cloned()),
+                Value::Object(entry) => {
+                    let mut entry = entry.clone();
+                    if let Some(repo_path) = repo_path {
+                        entry.insert("repo_path".to_string(), repo_path.into());
+                    }
+                    entries.push(Value::Object(entry));
+                }
+                _ => {}
+            }
+            continue;
+        }
+        match (metadata.get_mut(key), value) {
+            (None, _) => {
+                metadata.insert(key.clone(), value.clone());
+            }
+            (Some(Value::Number(total)), Value::Number(n)) => {
+                if let (Some(a), Some(b)) = (total.as_u64(), n.as_u64()) {
+                    *total = (a + b).into();
+                }
+            }
+            (Some(Value::Array(items)), Value::Array(more)) => items.extend(more.iter().cloned()),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,236 @@
+use std::collections::BTreeMap;
+
+use git2::{Commit, Oid, Repository};
+use regex::Regex;
+use serde::Serialize;
+
+use crate::authors;
+use crate::history::Replacements;
+use crate::messages;
+use crate::reviews;
+use crate::AnalyzerError;
+
+// Subjects squash merges get from GitHub ("Title (#123)") and Bitbucket
+// ("Merged in branch (pull request #12)"); the first group is the pull
+// request number. `squash_patterns` replaces these.
+pub const DEFAULT_SQUASH_PATTERNS: &[&str] = &[r"\(#(\d+)\)$", r"\(pull request #(\d+)\)"];
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct SelfMergeMonth {
+    pub merges: i32,
+    pub self_merged: i32,
+    pub self_merged_pct: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SelfMerge {
+    pub commit: String,
+    pub month: String,
+    pub committer: String,
+    // Commits the merge brought in
+    pub merged_commits: usize,
+}
+
+pub enum MergeCheck {
+    NotMerge,
+    Merge,
+    // Merged by the author of every commit it brings in, without review
+    SelfMerge { committer: String, merged_commits: usize },
+}
+
+// Merges whose committer wrote every commit they bring in, with no
+// Reviewed-by:/Acked-by: trailer on the merge or the merged commits. Merges
+// left out by merge_diff "skip" are not seen.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct SelfMergeReport {
+    pub months: BTreeMap<String, SelfMergeMonth>,
+    pub flagged: Vec<SelfMerge>,
+    #[serde(skip)]
+    normalize: bool,
+}
+
+impl SelfMergeReport {
+    pub fn new(normalize: bool) -> Self {
+        SelfMergeReport {
+            normalize,
+            ..Default::default()
+        }
+    }
+
+    // Walks the history a merge brings in; observe then counts the outcome
+    pub fn check(
+        &self,
+        repo: &Repository,
+        replacements: &Replacements,
+        commit: &Commit,
+        parent_ids: &[Oid],
+    ) -> Result<MergeCheck, AnalyzerError> {
+        if parent_ids.len() < 2 {
+            return Ok(MergeCheck::NotMerge);
+        }
+        let committer = authors::identity(&commit.committer(), self.normalize);
+        let mut reviewed = reviews::has_review(commit.message().unwrap_or(""));
+        let mut only_committer = true;
+        // Commits reachable from the merged parents but not the first one
+        let mut merged_commits = 0;
+        for oid in replacements.walk_between(repo, &parent_ids[1..], parent_ids[0])? {
+            let merged = replacements.find_commit(repo, oid?)?;
+            merged_commits += 1;
+            only_committer &= authors::identity(&merged.author(), self.normalize) == committer;
+            reviewed |= reviews::has_review(merged.message().unwrap_or(""));
+            if reviewed || !only_committer {
+                return Ok(MergeCheck::Merge);
+            }
+        }
+        // A merge bringing in nothing new has no author to compare against
+        if merged_commits == 0 {
+            return Ok(MergeCheck::Merge);
+        }
+        Ok(MergeCheck::SelfMerge { committer, merged_commits })
+    }
+
+    pub fn observe(&mut self, month_key: &str, commit: &Commit, check: MergeCheck) {
+        if matches!(check, MergeCheck::NotMerge) {
+            return;
+        }
+        let month = self.months.entry(month_key.to_string()).or_default();
+        month.merges += 1;
+        if let MergeCheck::SelfMerge { committer, merged_commits } = check {
+            month.self_merged += 1;
+            self.flagged.push(SelfMerge {
+                commit: commit.id().to_string(),
+                month: month_key.to_string(),
+                committer,
+                merged_commits,
+            });
+        }
+    }
+
+    pub fn finish(&mut self) {
+        for month in self.months.values_mut() {
+            if month.merges > 0 {
+                month.self_merged_pct =
+                    (f64::from(month.self_merged) * 10000.0 / f64::from(month.merges)).round() / 100.0;
+            }
+        }
+        self.flagged.sort_by(|a, b| a.month.cmp(&b.month).then_with(|| a.commit.cmp(&b.commit)));
+    }
+}
+
+// Why a commit looks like a squash merge: "subject" matched a pull request
+// pattern, "squashed_log" was committed by GitHub with a body listing the
+// squashed commits as GitHub writes it, "size" changed at least
+// squash_min_lines lines
+#[derive(Debug, Clone, Serialize)]
+pub struct SquashMerge {
+    pub signal: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pull_request: Option<u64>,
+}
+
+// Only single-parent commits are considered; real merges are counted by
+// merge_commits. Branch deletions leave nothing in the history, so a large
+// commit is the only trace of a squashed branch without either message form.
+#[derive(Debug, Clone)]
+pub struct SquashDetector {
+    patterns: Vec<Regex>,
+    min_lines: Option<u64>,
+}
+
+impl SquashDetector {
+    pub fn new(patterns: &[String], min_lines: Option<u64>) -> Result<Self, regex::Error> {
+        let patterns = if patterns.is_empty() {
+            DEFAULT_SQUASH_PATTERNS.iter().map(|p| Regex::new(p)).collect::<Result<_, _>>()?
+        } else {
+            patterns.iter().map(|p| Regex::new(p)).collect::<Result<_, _>>()?
+        };
+        Ok(SquashDetector { patterns, min_lines })
+    }
+
+    pub fn detect(&self, commit: &Commit, parent_count: usize, lines_changed: u64) -> Option<SquashMerge> {
+        if parent_count != 1 {
+            return None;
+        }
+        let (subject, body) = messages::split(commit.message().unwrap_or(""));
+        if let Some(captures) = self.patterns.iter().find_map(|p| p.captures(subject)) {
+            return Some(SquashMerge {
+                signal: "subject",
+                pull_request: captures.get(1).and_then(|m| m.as_str().parse().ok()),
+            });
+        }
+        // A subject naming the pull request was caught above, so the log
+        // only counts on a commit GitHub made itself
+        let committer = commit.committer();
+        let by_github = committer.name() == Some("GitHub") && committer.email() == Some("noreply@github.com");
+        if by_github && is_squashed_log(body) {
+            return Some(SquashMerge { signal: "squashed_log", pull_request: None });
+        }
+        if self.min_lines.is_some_and(|min| lines_changed >= min) {
+            return Some(SquashMerge { signal: "size", pull_request: None });
+        }
+        None
+    }
+}
+
+// GitHub's squash body: "* subject" entries of two commits or more, each
+// after a blank line unless it opens the body, with the squashed commits'
+// bodies and the trailers in between
+fn is_squashed_log(body: &str) -> bool {
+    let lines: Vec<&str> = body.lines().collect();
+    if !lines.first().is_some_and(|line| line.starts_with("* ")) {
+        return false;
+    }
+    let entries: Vec<usize> = (0..lines.len()).filter(|&i| lines[i].starts_with("* ")).collect();
+    entries.len() >= 2 && entries.iter().all(|&i| i == 0 || lines[i - 1].trim().is_empty())
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct SquashMonth {
+    pub commits: i32,
+    // Delivered pull requests, for platforms that squash them into one commit
+    pub squash_merges: i32,
+    pub squash_merges_pct: f64,
+    pub signals: BTreeMap<&'static str, i32>,
+}
+
+impl SquashMonth {
+    pub fn observe(&mut self, squash: Option<&SquashMerge>) {
+        self.commits += 1;
+        if let Some(squash) = squash {
+            self.squash_merges += 1;
+            *self.signals.entry(squash.signal).or_default() += 1;
+        }
+    }
+
+    pub fn finish(&mut self) {
+        if self.commits > 0 {
+            self.squash_merges_pct =
+                (f64::from(self.squash_merges) * 10000.0 / f64::from(self.commits)).round() / 100.0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn github_squash_bodies_are_squashed_logs() {
+        assert!(is_squashed_log("* Add a\n\n* Fix b"));
+        // Commit bodies and trailers sit between and after the entries
+        assert!(is_squashed_log(
+            "* Add a\n\nmore detail\n\n* Fix b\n\n---------\n\nCo-authored-by: B <b@example.com>"
+        ));
+    }
+
+    #[test]
+    fn other_bulleted_bodies_are_not() {
+        // A plain bulleted list has no blank lines between the entries
+        assert!(!is_squashed_log("* item one\n* item two"));
+        // One squashed commit is not a log
+        assert!(!is_squashed_log("* Add a"));
+        // The body has to open with an entry
+        assert!(!is_squashed_log("Changes:\n\n* Add a\n\n* Fix b"));
+        assert!(!is_squashed_log(""));
+    }
+}
@@ -0,0 +1,70 @@
+use serde::Serialize;
+
+// Subject lines longer than this are flagged, following the usual git advice
+pub const MAX_SUBJECT_LENGTH: usize = 72;
+
+// First line of the message, and everything after it with surrounding blank
+// lines removed
+pub fn split(message: &str) -> (&str, &str) {
+    let message = message.trim_start_matches(['\n', '\r']);
+    match message.split_once('\n') {
+        Some((subject, body)) => (subject.trim_end(), body.trim()),
+        None => (message.trim_end(), ""),
+    }
+}
+
+// "Token: value" lines of the message's last paragraph, as git
+// interpret-trailers reads them. Tokens keep their spelling; values are
+// trimmed. Indented continuation lines are skipped.
+pub fn trailers(message: &str) -> Vec<(&str, &str)> {
+    let (_, body) = split(message);
+    let paragraph = body.rsplit("\n\n").next().unwrap_or("");
+    paragraph
+        .lines()
+        .filter_map(|line| {
+            let (token, value) = line.split_once(':')?;
+            let valid = !token.is_empty() && token.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+            valid.then_some((token, value.trim()))
+        })
+        .collect()
+}
+
+// Commit message hygiene for one month
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct MessageMonth {
+    pub commits: i32,
+    pub avg_subject_length: f64,
+    pub with_body_pct: f64,
+    pub long_subject_pct: f64,
+    #[serde(skip)]
+    subject_chars: usize,
+    #[serde(skip)]
+    with_body: i32,
+    #[serde(skip)]
+    long_subjects: i32,
+}
+
+impl MessageMonth {
+    pub fn observe(&mut self, message: &str) {
+        let (subject, body) = split(message);
+        let length = subject.chars().count();
+        self.commits += 1;
+        self.subject_chars += length;
+        if !body.is_empty() {
+            self.with_body += 1;
+        }
+        if length > MAX_SUBJECT_LENGTH {
+            self.long_subjects += 1;
+        }
+    }
+
+    pub fn finish(&mut self) {
+        if self.commits == 0 {
+            return;
+        }
+        let commits = f64::from(self.commits);
+        self.avg_subject_length = (self.subject_chars as f64 * 100.0 / commits).round() / 100.0;
+        self.with_body_pct = (f64::from(self.with_body) * 10000.0 / commits).round() / 100.0;
+        self.long_subject_pct = (f64::from(self.long_subjects) * 10000.0 / commits).round() / 100.0;
+    }
+}
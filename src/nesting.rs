@@ -0,0 +1,77 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::diff::FileDelta;
+
+pub const DEFAULT_INDENT_WIDTH: u32 = 4;
+// Lines at this depth or deeper count as deeply nested
+const DEEP_NESTING: u32 = 4;
+// Deeper lines share the last histogram bucket
+const MAX_DEPTH_BUCKET: u32 = 8;
+
+// Indentation depth of added lines, a rough structural-complexity trend that
+// needs no parsing. Depth is leading tabs plus leading spaces divided by
+// indent_width; blank lines are left out.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct NestingMonth {
+    pub lines: i32,
+    pub mean_depth: f64,
+    // Share of lines at depth 4 or more
+    pub deep_pct: f64,
+    // Lines per depth, the last bucket ("8+") holding everything deeper
+    pub depths: BTreeMap<String, i32>,
+    #[serde(skip)]
+    counts: [i32; MAX_DEPTH_BUCKET as usize + 1],
+    #[serde(skip)]
+    depth_sum: u64,
+}
+
+impl NestingMonth {
+    pub fn observe(&mut self, deltas: &[FileDelta], indent_width: u32) {
+        let lines = deltas.iter().flat_map(|d| &d.hunks).flat_map(|h| &h.added);
+        for line in lines.filter(|line| !line.trim().is_empty()) {
+            let depth = depth(line, indent_width);
+            self.lines += 1;
+            self.depth_sum += u64::from(depth);
+            self.counts[depth.min(MAX_DEPTH_BUCKET) as usize] += 1;
+        }
+    }
+
+    pub fn finish(&mut self) {
+        if self.lines == 0 {
+            return;
+        }
+        let lines = f64::from(self.lines);
+        self.mean_depth = (self.depth_sum as f64 * 100.0 / lines).round() / 100.0;
+        let deep: i32 = self.counts[DEEP_NESTING as usize..].iter().sum();
+        self.deep_pct = (f64::from(deep) * 10000.0 / lines).round() / 100.0;
+        self.depths = self
+            .counts
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .map(|(depth, count)| {
+                let key = if depth as u32 == MAX_DEPTH_BUCKET {
+                    format!("{}+", depth)
+                } else {
+                    depth.to_string()
+                };
+                (key, *count)
+            })
+            .collect();
+    }
+}
+
+fn depth(line: &str, indent_width: u32) -> u32 {
+    let mut tabs = 0;
+    let mut spaces = 0;
+    for c in line.chars() {
+        match c {
+            '\t' => tabs += 1,
+            ' ' => spaces += 1,
+            _ => break,
+        }
+    }
+    tabs + spaces / indent_width
+}
@@ -0,0 +1,27 @@
+use std::collections::BTreeMap;
+
+use git2::{ErrorCode, Oid, Repository};
+
+use crate::AnalyzerError;
+
+pub const DEFAULT_NOTES_REF: &str = "refs/notes/commits";
+
+// Note text attached to a commit, keyed by notes ref. Refs that do not exist
+// or carry no note for the commit are left out.
+pub fn read_notes(
+    repo: &Repository,
+    oid: Oid,
+    refs: &[String],
+) -> Result<BTreeMap<String, String>, AnalyzerError> {
+    let mut notes = BTreeMap::new();
+    for notes_ref in refs {
+        match repo.find_note(Some(notes_ref), oid) {
+            Ok(note) => {
+                notes.insert(notes_ref.clone(), note.message().unwrap_or("").to_string());
+            }
+            Err(e) if e.code() == ErrorCode::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(notes)
+}
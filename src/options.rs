@@ -0,0 +1,1736 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use chrono::{DateTime, NaiveDate, NaiveTime};
+use ignore::gitignore::Gitignore;
+#[cfg(feature = "python")]
+use pyo3::exceptions::PyValueError;
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3::types::{PyDict, PyTuple};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "python")]
+use serde_json::Value;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::anomalies::AnomalyMethod;
+use crate::authors::{self, AuthorField};
+use crate::binaries;
+use crate::buckets::{Bucket, BucketFormat};
+use crate::ci;
+use crate::collaboration;
+use crate::contributors;
+use crate::classify;
+#[cfg(feature = "python")]
+use crate::convert::{from_py, py_to_json, to_py};
+use crate::decay;
+use crate::dependencies;
+use crate::effort::{CocomoCoefficients, CocomoMode};
+use crate::hygiene::HygieneWeights;
+use crate::gitmoji::GitmojiClassifier;
+use crate::hours;
+use crate::latency;
+use crate::merges::SquashDetector;
+use crate::markers;
+use crate::nesting;
+use crate::notes;
+use crate::progress::{self, ProgressCallback, ProgressGroup};
+use crate::secrets;
+use crate::snapshot;
+use crate::AnalyzerError;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    // month -> extension -> stats, as returned since the first release
+    #[default]
+    Nested,
+    // {"months": ..., "metadata": ...} plus any optional report sections
+    Envelope,
+}
+
+impl OutputFormat {
+    #[cfg(feature = "python")]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutputFormat::Nested => "nested",
+            OutputFormat::Envelope => "envelope",
+        }
+    }
+}
+
+// Nesting order of the per-month results
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Pivot {
+    // month -> extension -> stats
+    #[default]
+    Month,
+    // author -> month -> extension -> stats
+    Author,
+    // extension -> month -> stats
+    Extension,
+    // pattern label -> month -> extension -> stats; a commit counts under
+    // every author pattern it matches
+    Pattern,
+}
+
+// Octopus merges (three or more parents) follow the same rule as ordinary
+// merges: parent 0 only for first_parent, every parent for all_parents.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeDiff {
+    // Compare merges against parent 0 only, as before
+    #[default]
+    FirstParent,
+    // Count only lines that differ from every parent (conflict resolutions)
+    AllParents,
+    // Leave merge commits out of the scan
+    Skip,
+}
+
+impl MergeDiff {
+    #[cfg(feature = "python")]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MergeDiff::FirstParent => "first_parent",
+            MergeDiff::AllParents => "all_parents",
+            MergeDiff::Skip => "skip",
+        }
+    }
+}
+
+// What a scan does with commits changing more than max_files_changed files
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkChanges {
+    // Leave them out of the results
+    #[default]
+    Exclude,
+    // Count all their files under a "<bulk>" bucket instead of by extension
+    Separate,
+}
+
+// How a scan decides which files hold countable text
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileDetection {
+    // Only the built-in list of text extensions, as before
+    #[default]
+    Extension,
+    // Any extension, leaving out files git considers binary: a NUL byte near
+    // the start of the content, or a binary/-diff gitattribute
+    Content,
+}
+
+// What a scan does when a commit's objects cannot be read, e.g. blobs missing
+// from a partial clone or a corrupt pack
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OnError {
+    // Abort the whole analysis with the git error
+    #[default]
+    Raise,
+    // Leave the commit out and count it in metadata.commits_failed
+    Skip,
+    // As skip, and list each commit with its error in metadata.errors
+    Collect,
+}
+
+// Group of the commits matching none of the `groups` patterns
+pub const UNMATCHED_GROUP: &str = "unmatched";
+
+#[cfg(feature = "python")]
+const SECRET_FIELDS: &[&str] = &["ssh_passphrase", "https_token"];
+#[cfg(feature = "python")]
+const REDACTED: &str = "***";
+
+// Values derived from the raw options during validation
+#[derive(Debug, Clone, Default)]
+pub struct Compiled {
+    pub patterns: Vec<Regex>,
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+    pub test_matcher: Option<Gitignore>,
+    pub marker_regex: Option<Regex>,
+    pub ci_matcher: Option<Gitignore>,
+    pub infra_matcher: Option<Gitignore>,
+    pub secret_rules: Vec<(String, Regex)>,
+    pub notes_pattern: Option<Regex>,
+    pub gitmoji: Option<GitmojiClassifier>,
+    pub squash: Option<SquashDetector>,
+    pub clone_since: Option<i64>,
+    pub bucket_format: Option<BucketFormat>,
+    pub groups: Vec<(String, Vec<Regex>)>,
+    // Set by the Python wrappers from their `on_progress` argument
+    pub progress_callback: Option<ProgressCallback>,
+    // Set per repository when several are scanned at once
+    pub progress_group: Option<ProgressGroup>,
+    // Set by start_analysis; raising it stops the walk at the next commit
+    pub cancel: Option<Arc<AtomicBool>>,
+}
+
+/// Optional parameters for the analysis functions.
+///
+/// Built from keyword arguments and validated at construction, so a bad regex
+/// or date fails before any history is walked. `replace(**kwargs)` returns a
+/// modified copy.
+///
+/// There is deliberately no thread count: one repository's history is walked
+/// and diffed on a single thread, and the only parallelism, across
+/// repositories, is bounded by `max_parallel_repos`.
+#[cfg_attr(feature = "python", pyclass(module = "repo_scan_rs"))]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AnalysisOptions {
+    pub patterns: Vec<String>,
+    pub show_progress: bool,
+    // indicatif template for the progress bar, redraws per second, and a file
+    // descriptor to draw on instead of stderr. As with stderr, nothing is drawn
+    // unless the descriptor is a terminal.
+    pub progress_template: Option<String>,
+    pub progress_refresh_hz: Option<u8>,
+    pub progress_fd: Option<i32>,
+    // No progress bar even with show_progress; a progress callback still fires
+    pub quiet: bool,
+    // "raise", "skip" or "collect"; see OnError
+    pub on_error: OnError,
+    // When a scan fails part way, return what was processed so far, marked by
+    // metadata.stopped_early, instead of only the error
+    pub partial_results: bool,
+    // Stop walking once this many seconds have passed and return what was
+    // covered, with metadata.time_budget_exhausted telling how far back the
+    // walk got; for a quick look at a large repository
+    pub time_budget_seconds: Option<f64>,
+    // Walk the history as stored, ignoring refs/replace/*, which every
+    // history walk honors by default as git does. info/grafts is applied by
+    // libgit2 itself to every commit it reads and stays in effect either way.
+    pub ignore_replacements: bool,
+    // In a partial clone, fetch the blobs a commit's diff needs from the
+    // promisor remote (runs `git fetch`). Otherwise files whose blobs are
+    // missing count by status only, tallied in metadata.files_without_content.
+    pub fetch_missing_objects: bool,
+    // iter_git_commits hands records to Python in chunks of about this many
+    // megabytes (64 when unset) instead of building the whole history first
+    pub commit_buffer_mb: Option<u64>,
+    // Repositories analyzed at once by scan_directory and scan_fleet (whose
+    // `workers` argument takes precedence); defaults to 4. This stands in for
+    // a general thread count, which a single repository has no use for.
+    pub max_parallel_repos: Option<usize>,
+    pub since: Option<String>,
+    pub until: Option<String>,
+    // Period the results and the per-month sections are keyed by: "month"
+    // (default), "iso_week", "fiscal_quarter" or "fiscal_year". Also keys
+    // branching_metrics and the code_survival cohorts; storage_growth keeps
+    // its own "month" or "year" period.
+    pub bucket: Bucket,
+    // First month (1-12) of the fiscal year for the fiscal buckets; default 1
+    pub fiscal_year_start_month: u32,
+    // strftime pattern for the month keys of the results and the per-month
+    // sections, e.g. "%Y-Q%q" (%q is the quarter) or "%G-W%V"; UTC, like
+    // the default "%Y-%m". Only with the "month" bucket.
+    pub bucket_format: Option<String>,
+    pub output_format: OutputFormat,
+    // Nesting order of the results of analyze_git_repo: "month" (default),
+    // "author", "extension" or "pattern"
+    pub pivot: Pivot,
+    // label -> author regexes. Results are nested by label, a commit counting
+    // under every group it matches and under "unmatched" if none; `patterns`
+    // still filters first. Only with the "month" pivot.
+    pub groups: BTreeMap<String, Vec<String>>,
+    // Names the "pattern" pivot uses for `patterns`, one per pattern; the
+    // pattern's index otherwise. Patterns sharing a label are one group.
+    pub pattern_labels: Vec<String>,
+    // Honor `.repo-scan-ignore` in the repository root
+    pub use_ignore_file: bool,
+    // Git pathspecs handed to libgit2, e.g. ["src/", "*.rs"]
+    pub pathspecs: Vec<String>,
+    // Diff knobs passed straight to libgit2
+    pub ignore_whitespace: bool,
+    pub ignore_whitespace_change: bool,
+    pub ignore_blank_lines: bool,
+    pub include_typechange: bool,
+    pub context_lines: Option<u32>,
+    // Report intra-line words_added/words_removed next to line counts
+    pub word_diff: bool,
+    // Count whitespace-only and formatting-only file changes separately
+    pub detect_formatting: bool,
+    // Split added/removed lines into code, comment and blank counts
+    pub classify_lines: bool,
+    // Split churn into test and production code by path
+    pub classify_tests: bool,
+    // Gitignore-style patterns replacing the built-in test path heuristics
+    pub test_patterns: Vec<String>,
+    // Per-month docs vs code churn (envelope "docs" section) and a per-commit
+    // touches_docs flag
+    pub docs_churn: bool,
+    // Count technical-debt markers in added/removed lines (per-extension
+    // markers_added/markers_removed plus the envelope "markers" section)
+    pub track_markers: bool,
+    pub markers: Vec<String>,
+    // Per-month dependency manifest changes (envelope "dependencies" section),
+    // matched on file name; parse_dependencies also extracts package names
+    pub track_dependencies: bool,
+    pub dependency_manifests: Vec<String>,
+    pub parse_dependencies: bool,
+    // Per-month CI configuration changes and the commits making them
+    // (envelope "ci" section); ci_patterns replaces the built-in paths
+    pub track_ci: bool,
+    pub ci_patterns: Vec<String>,
+    // Count Dockerfiles, Terraform, Kubernetes and Helm files under an "infra"
+    // bucket next to the extensions; infra_patterns extends the built-in rules
+    pub classify_infra: bool,
+    pub infra_patterns: Vec<String>,
+    // License files at HEAD with their detected license, plus every commit
+    // that modified one (envelope "licenses" section)
+    pub track_licenses: bool,
+    // Opt-in scan of added lines for credentials (envelope "secrets" section);
+    // secret_patterns maps rule name -> regex and replaces the built-in rules
+    pub scan_secrets: bool,
+    pub secret_patterns: BTreeMap<String, String>,
+    // Per-month count and bytes of binary files added or modified at or above
+    // large_binary_threshold bytes (envelope "large_binaries" section)
+    pub track_large_binaries: bool,
+    pub large_binary_threshold: u64,
+    // Running net lines per extension by month (envelope "cumulative_lines"
+    // section), ordered so consumers need not re-sort the months
+    pub cumulative_lines: bool,
+    // Each extension's percentage of monthly churn and of cumulative net lines
+    // (envelope "language_share" section)
+    pub language_share: bool,
+    // Shannon and Gini-Simpson diversity of each month's churn across
+    // extensions (envelope "language_diversity" section)
+    pub language_diversity: bool,
+    // Monthly churn per active author, per active day and per commit
+    // (envelope "churn_metrics" section)
+    pub churn_metrics: bool,
+    // How merge commits are diffed: "first_parent", "all_parents" or "skip"
+    pub merge_diff: MergeDiff,
+    // Keep commits that changed no tracked file in analyze_git_commits, with
+    // empty stats, so commit counts and cadence stay accurate
+    pub include_empty: bool,
+    // Leave out commits whose added plus deleted lines in tracked files fall
+    // below or above these bounds, e.g. typo fixes or vendored imports;
+    // tallied in metadata.commits_out_of_size_bounds
+    pub min_lines_changed: Option<u64>,
+    pub max_lines_changed: Option<u64>,
+    // Commits changing more tracked files than this, like tree-wide renames or
+    // license header sweeps, are left out or, with bulk_changes="separate",
+    // counted under a "<bulk>" bucket; tallied in metadata.commits_bulk
+    pub max_files_changed: Option<usize>,
+    pub bulk_changes: BulkChanges,
+    // Count paths without an extension under a "<none>" bucket instead of
+    // dropping them
+    pub include_no_extension: bool,
+    // "extension" or "content"; see FileDetection
+    pub file_detection: FileDetection,
+    // NFC-normalize author names and emails and collapse their whitespace
+    // before matching and grouping
+    pub normalize_authors: bool,
+    // How author patterns match: against "identity" ("Name <email>"), "name"
+    // or "email"; case-insensitively; and/or against the whole value only
+    pub match_author_on: AuthorField,
+    pub ignore_author_case: bool,
+    pub exact_author_match: bool,
+    // Cluster author identities that probably belong to one person and suggest
+    // a .mailmap for them (envelope "duplicate_authors" section)
+    pub detect_duplicate_authors: bool,
+    // Attach git notes from notes_refs to each commit in analyze_git_commits;
+    // notes_pattern keeps only commits with a note matching the regex
+    pub include_notes: bool,
+    pub notes_refs: Vec<String>,
+    pub notes_pattern: Option<String>,
+    // Per-month average subject length and the share of commits with a body
+    // or an over-long subject (envelope "message_metrics" section)
+    pub message_metrics: bool,
+    // Classify commits by a leading gitmoji or :shortcode: (per-commit
+    // "gitmoji" plus the envelope "gitmoji" section); gitmoji_categories maps
+    // extra emoji or shortcodes to categories
+    pub classify_gitmoji: bool,
+    pub gitmoji_categories: BTreeMap<String, String>,
+    // Flag months with outlying churn, deletions or contributor counts
+    // (envelope "anomalies" list); anomaly_method is "zscore" or "iqr" and
+    // anomaly_threshold defaults to 3 and 1.5 respectively
+    pub detect_anomalies: bool,
+    pub anomaly_method: AnomalyMethod,
+    pub anomaly_threshold: Option<f64>,
+    // Author-author edges weighted by files both changed within
+    // collaboration_window_days of each other (envelope "collaboration")
+    pub collaboration_network: bool,
+    pub collaboration_window_days: u32,
+    // Plurality owner of each top-level directory by cumulative churn, month
+    // by month, with the handovers between owners (envelope "ownership")
+    pub ownership_timeline: bool,
+    // Basic COCOMO effort from the final net line count and from total churn
+    // (envelope "effort" section). cocomo_mode picks the published
+    // coefficients; cocomo_coefficients ({"a", "b", "c", "d"}) overrides them
+    pub estimate_effort: bool,
+    pub cocomo_mode: CocomoMode,
+    pub cocomo_coefficients: Option<CocomoCoefficients>,
+    pub cost_per_person_month: Option<f64>,
+    // Per-author commits, churn, first and last commit, and a dormant flag
+    // for authors idle for dormancy_months (envelope "contributors" section)
+    pub contributor_report: bool,
+    pub dormancy_months: u32,
+    // Per-month first-time vs returning authors and their share of churn
+    // (envelope "new_contributors" section)
+    pub new_contributors: bool,
+    // Running total of distinct contributors per month, overall and per
+    // extension, with the month's first-timers (envelope "contributor_growth")
+    pub contributor_growth: bool,
+    // Commits per committer UTC offset, per month and per author, with how
+    // often each author's offset changed (envelope "timezones" section)
+    pub timezone_distribution: bool,
+    // Share of commits and churn made on weekends or outside working_hours
+    // ([start, end) hours, default 9 to 17), per month and per author, in
+    // author-local time (envelope "out_of_hours" section)
+    pub out_of_hours: bool,
+    pub working_hours: (u8, u8),
+    // Per-month distribution of committer time minus author time, listing
+    // commits where it exceeds latency_threshold_hours (default a week)
+    // (envelope "commit_latency" section)
+    pub commit_latency: bool,
+    pub latency_threshold_hours: f64,
+    // Distinct paths changed per month, overall and per extension (envelope
+    // "files_touched" section)
+    pub files_touched: bool,
+    // Mean and median lines changed per modification, per month and
+    // extension (envelope "commit_size" section)
+    pub commit_size: bool,
+    // Per-month Reviewed-by:/Acked-by: trailer counts and a reviewer by
+    // author matrix (envelope "reviews" section)
+    pub review_trailers: bool,
+    // Merges whose committer authored every merged commit and that carry no
+    // review trailers, with their monthly share of merges (envelope
+    // "self_merges" section)
+    pub self_merges: bool,
+    // Flag single-parent commits that look like squash merges: a subject with
+    // a pull request number (squash_patterns, whose first group is the
+    // number, replaces the GitHub and Bitbucket forms), a GitHub-committed
+    // body listing the squashed commits, or with squash_min_lines at least
+    // that many lines changed (per-commit "squash_merge" plus the envelope
+    // "squash_merges" section)
+    pub detect_squash_merges: bool,
+    pub squash_patterns: Vec<String>,
+    pub squash_min_lines: Option<u64>,
+    // Per-commit and per-month message score from subject length, body,
+    // imperative mood and issue references, weighted by hygiene_weights
+    // ({"subject_length", "body", "imperative", "issue_reference"}, equal by
+    // default) (envelope "message_hygiene" section)
+    pub message_hygiene: bool,
+    pub hygiene_weights: HygieneWeights,
+    // Indentation depth distribution of added lines per month, a tab or
+    // indent_width spaces (default 4) making one level (envelope
+    // "nesting_depth" section)
+    pub nesting_depth: bool,
+    pub indent_width: u32,
+    // Most modified functions and methods, found by parsing both sides of
+    // modified Rust, Python, JavaScript, TypeScript, Go and Java files;
+    // needs the "functions" build feature (envelope "function_churn" section)
+    pub function_churn: bool,
+    // pub items of .rs files added, removed or changed per month, by name
+    // (envelope "public_api" section)
+    pub public_api: bool,
+    // Import statements added and removed per month and language, with the
+    // most imported modules (envelope "imports" section)
+    pub import_churn: bool,
+    // Trailing sums over this many days, evaluated at the end of every month
+    // (envelope "rolling" section)
+    pub rolling_window_days: Option<u32>,
+    // Churn per file, directory and author with older commits decayed by
+    // churn_half_life_days (default 90) (envelope "decayed_churn" section)
+    pub decayed_churn: bool,
+    pub churn_half_life_days: f64,
+    // Tracked files per extension in the tree at each month's last commit
+    // (envelope "file_counts" section)
+    pub file_counts: bool,
+    // p50/p90/max line counts of tracked text files per extension at each
+    // month's last commit and at tagged commits, counting files longer than
+    // long_file_lines (default 500) (envelope "file_lengths" section)
+    pub file_lengths: bool,
+    pub long_file_lines: u32,
+    // Pair deleted and added files into renames (libgit2 similarity
+    // detection); rename_lineage exports the resulting rename chains
+    // (envelope "renames" section)
+    pub detect_renames: bool,
+    pub rename_lineage: bool,
+    // List each commit's changed tracked files (path, status, additions,
+    // deletions) under "files" in analyze_git_commits
+    pub include_files: bool,
+    // Repository directory for a work tree at repo_path that has no .git of
+    // its own, as with GIT_DIR and GIT_WORK_TREE
+    pub git_dir: Option<String>,
+    // Treat repo_path as a remote URL and analyze a temporary clone of it,
+    // optionally bare, limited to clone_depth commits, or deepened until it
+    // reaches clone_since (YYYY-MM-DD or RFC 3339)
+    pub clone_remote: bool,
+    pub clone_bare: bool,
+    pub clone_depth: Option<u32>,
+    pub clone_since: Option<String>,
+    // Credentials for private remotes, tried in this order: the SSH agent, an
+    // SSH key file, an HTTPS token. Unset values fall back to the
+    // REPO_SCAN_SSH_KEY, REPO_SCAN_SSH_PASSPHRASE, REPO_SCAN_HTTPS_USERNAME and
+    // REPO_SCAN_HTTPS_TOKEN environment variables. Secrets are redacted from
+    // repr() and to_dict() and left out of pickles and copies.
+    pub ssh_use_agent: bool,
+    pub ssh_key_path: Option<String>,
+    pub ssh_passphrase: Option<String>,
+    pub https_username: Option<String>,
+    pub https_token: Option<String>,
+    #[serde(skip)]
+    pub compiled: Compiled,
+}
+
+impl Default for AnalysisOptions {
+    fn default() -> Self {
+        AnalysisOptions {
+            patterns: Vec::new(),
+            show_progress: false,
+            progress_template: None,
+            progress_refresh_hz: None,
+            progress_fd: None,
+            quiet: false,
+            on_error: OnError::default(),
+            partial_results: false,
+            time_budget_seconds: None,
+            ignore_replacements: false,
+            fetch_missing_objects: false,
+            commit_buffer_mb: None,
+            max_parallel_repos: None,
+            since: None,
+            until: None,
+            bucket: Bucket::default(),
+            fiscal_year_start_month: 1,
+            bucket_format: None,
+            output_format: OutputFormat::default(),
+            pivot: Pivot::default(),
+            pattern_labels: Vec::new(),
+            groups: BTreeMap::new(),
+            use_ignore_file: true,
+            pathspecs: Vec::new(),
+            ignore_whitespace: false,
+            ignore_whitespace_change: false,
+            ignore_blank_lines: false,
+            include_typechange: false,
+            context_lines: None,
+            word_diff: false,
+            detect_formatting: false,
+            classify_lines: false,
+            classify_tests: false,
+            test_patterns: Vec::new(),
+            docs_churn: false,
+            track_markers: false,
+            markers: markers::DEFAULT_MARKERS.iter().map(|m| m.to_string()).collect(),
+            track_dependencies: false,
+            dependency_manifests: dependencies::DEFAULT_MANIFESTS
+                .iter()
+                .map(|m| m.to_string())
+                .collect(),
+            parse_dependencies: false,
+            track_ci: false,
+            ci_patterns: Vec::new(),
+            classify_infra: false,
+            infra_patterns: Vec::new(),
+            track_licenses: false,
+            scan_secrets: false,
+            secret_patterns: BTreeMap::new(),
+            track_large_binaries: false,
+            large_binary_threshold: binaries::DEFAULT_LARGE_BINARY_THRESHOLD,
+            cumulative_lines: false,
+            language_share: false,
+            language_diversity: false,
+            churn_metrics: false,
+            merge_diff: MergeDiff::default(),
+            include_empty: true,
+            min_lines_changed: None,
+            max_lines_changed: None,
+            max_files_changed: None,
+            bulk_changes: BulkChanges::default(),
+            include_no_extension: false,
+            file_detection: FileDetection::default(),
+            normalize_authors: true,
+            match_author_on: AuthorField::default(),
+            ignore_author_case: false,
+            exact_author_match: false,
+            detect_duplicate_authors: false,
+            include_notes: false,
+            notes_refs: vec![notes::DEFAULT_NOTES_REF.to_string()],
+            notes_pattern: None,
+            message_metrics: false,
+            classify_gitmoji: false,
+            gitmoji_categories: BTreeMap::new(),
+            detect_anomalies: false,
+            anomaly_method: AnomalyMethod::default(),
+            anomaly_threshold: None,
+            collaboration_network: false,
+            ownership_timeline: false,
+            estimate_effort: false,
+            cocomo_mode: CocomoMode::default(),
+            cocomo_coefficients: None,
+            cost_per_person_month: None,
+            contributor_report: false,
+            dormancy_months: contributors::DEFAULT_DORMANCY_MONTHS,
+            new_contributors: false,
+            contributor_growth: false,
+            timezone_distribution: false,
+            out_of_hours: false,
+            working_hours: hours::DEFAULT_WORKING_HOURS,
+            commit_latency: false,
+            latency_threshold_hours: latency::DEFAULT_LATENCY_THRESHOLD_HOURS,
+            files_touched: false,
+            commit_size: false,
+            review_trailers: false,
+            self_merges: false,
+            detect_squash_merges: false,
+            squash_patterns: Vec::new(),
+            squash_min_lines: None,
+            message_hygiene: false,
+            hygiene_weights: HygieneWeights::default(),
+            nesting_depth: false,
+            indent_width: nesting::DEFAULT_INDENT_WIDTH,
+            function_churn: false,
+            public_api: false,
+            import_churn: false,
+            rolling_window_days: None,
+            decayed_churn: false,
+            churn_half_life_days: decay::DEFAULT_HALF_LIFE_DAYS,
+            file_counts: false,
+            file_lengths: false,
+            long_file_lines: snapshot::DEFAULT_LONG_FILE_LINES,
+            detect_renames: false,
+            rename_lineage: false,
+            include_files: false,
+            git_dir: None,
+            clone_remote: false,
+            clone_bare: false,
+            clone_depth: None,
+            clone_since: None,
+            ssh_use_agent: true,
+            ssh_key_path: None,
+            ssh_passphrase: None,
+            https_username: None,
+            https_token: None,
+            collaboration_window_days: collaboration::DEFAULT_COLLABORATION_WINDOW_DAYS,
+            compiled: Compiled::default(),
+        }
+    }
+}
+
+impl AnalysisOptions {
+    pub fn validated(mut self) -> Result<Self, AnalyzerError> {
+        // Patterns are normalized like the identities they are matched against
+        self.compiled.patterns = self
+            .patterns
+            .iter()
+            .map(|p| self.author_regex(p))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.compiled.groups = self
+            .groups
+            .iter()
+            .map(|(label, patterns)| {
+                let regexes = patterns.iter().map(|p| self.author_regex(p)).collect::<Result<Vec<_>, _>>()?;
+                Ok::<_, regex::Error>((label.clone(), regexes))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if self.groups.contains_key(UNMATCHED_GROUP) {
+            return Err(AnalyzerError::InvalidOption(format!(
+                "{:?} is reserved for commits no group matches",
+                UNMATCHED_GROUP
+            )));
+        }
+        if !self.groups.is_empty() && self.pivot != Pivot::Month {
+            return Err(AnalyzerError::InvalidOption(
+                "groups cannot be combined with a pivot other than \"month\"".to_string(),
+            ));
+        }
+        self.compiled.since = self.since.as_deref().map(|d| parse_date(d, false)).transpose()?;
+        if self.bucket_format.is_some() && self.bucket != Bucket::Month {
+            return Err(AnalyzerError::InvalidOption(
+                "bucket_format cannot be combined with a bucket other than \"month\"".to_string(),
+            ));
+        }
+        if !(1..=12).contains(&self.fiscal_year_start_month) {
+            return Err(AnalyzerError::InvalidOption(
+                "fiscal_year_start_month must be between 1 and 12".to_string(),
+            ));
+        }
+        self.compiled.bucket_format = self.bucket_format.as_deref().map(BucketFormat::parse).transpose()?;
+        self.compiled.until = self.until.as_deref().map(|d| parse_date(d, true)).transpose()?;
+
+        if !self.pathspecs.is_empty() {
+            git2::Pathspec::new(self.pathspecs.iter())?;
+        }
+
+        self.compiled.test_matcher = if self.classify_tests || !self.test_patterns.is_empty() {
+            Some(classify::test_matcher(&self.test_patterns)?)
+        } else {
+            None
+        };
+
+        if self.track_markers && self.markers.is_empty() {
+            return Err(AnalyzerError::InvalidOption(
+                "track_markers needs at least one marker".to_string(),
+            ));
+        }
+        self.compiled.marker_regex = if self.track_markers {
+            Some(markers::marker_regex(&self.markers)?)
+        } else {
+            None
+        };
+
+        self.compiled.ci_matcher = if self.track_ci || !self.ci_patterns.is_empty() {
+            Some(ci::ci_matcher(&self.ci_patterns)?)
+        } else {
+            None
+        };
+
+        self.compiled.infra_matcher = if self.classify_infra || !self.infra_patterns.is_empty() {
+            Some(classify::infra_matcher(&self.infra_patterns)?)
+        } else {
+            None
+        };
+
+        self.compiled.secret_rules = if self.scan_secrets {
+            secrets::compile_rules(&self.secret_patterns)?
+        } else {
+            Vec::new()
+        };
+
+        self.compiled.gitmoji = self
+            .classify_gitmoji
+            .then(|| GitmojiClassifier::new(&self.gitmoji_categories));
+
+        self.compiled.squash = if self.detect_squash_merges {
+            Some(SquashDetector::new(&self.squash_patterns, self.squash_min_lines)?)
+        } else {
+            None
+        };
+
+        self.compiled.notes_pattern = self.notes_pattern.as_deref().map(Regex::new).transpose()?;
+
+        if self.anomaly_threshold.is_some_and(|t| !t.is_finite() || t <= 0.0) {
+            return Err(AnalyzerError::InvalidOption(
+                "anomaly_threshold must be a positive number".to_string(),
+            ));
+        }
+
+        self.compiled.clone_since = self.clone_since.as_deref().map(|d| parse_date(d, false)).transpose()?;
+        if self.git_dir.is_some() && self.clone_remote {
+            return Err(AnalyzerError::InvalidOption(
+                "git_dir cannot be combined with clone_remote".to_string(),
+            ));
+        }
+        if self.clone_depth == Some(0) {
+            return Err(AnalyzerError::InvalidOption(
+                "clone_depth must be at least 1".to_string(),
+            ));
+        }
+
+        if let Some(template) = &self.progress_template {
+            progress::style(Some(template))?;
+        }
+        if !self.latency_threshold_hours.is_finite() || self.latency_threshold_hours < 0.0 {
+            return Err(AnalyzerError::InvalidOption(
+                "latency_threshold_hours must be a non-negative number".to_string(),
+            ));
+        }
+        self.hygiene_weights.validate()?;
+        if let Some(coefficients) = &self.cocomo_coefficients {
+            coefficients.validate()?;
+        }
+        if self.cost_per_person_month.is_some_and(|cost| !cost.is_finite() || cost < 0.0) {
+            return Err(AnalyzerError::InvalidOption(
+                "cost_per_person_month must be a non-negative number".to_string(),
+            ));
+        }
+        if self.function_churn && !cfg!(feature = "functions") {
+            return Err(AnalyzerError::InvalidOption(
+                "function_churn needs a build with the \"functions\" feature".to_string(),
+            ));
+        }
+        if !self.churn_half_life_days.is_finite() || self.churn_half_life_days <= 0.0 {
+            return Err(AnalyzerError::InvalidOption(
+                "churn_half_life_days must be a positive number".to_string(),
+            ));
+        }
+        if self.rolling_window_days == Some(0) {
+            return Err(AnalyzerError::InvalidOption(
+                "rolling_window_days must be at least 1".to_string(),
+            ));
+        }
+        if !self.pattern_labels.is_empty() && self.pattern_labels.len() != self.patterns.len() {
+            return Err(AnalyzerError::InvalidOption(format!(
+                "pattern_labels has {} labels for {} patterns",
+                self.pattern_labels.len(),
+                self.patterns.len()
+            )));
+        }
+        if self.pivot == Pivot::Pattern && self.patterns.is_empty() {
+            return Err(AnalyzerError::InvalidOption(
+                "pivot=\"pattern\" needs at least one author pattern".to_string(),
+            ));
+        }
+        if self.time_budget_seconds.is_some_and(|budget| !(budget > 0.0 && budget.is_finite())) {
+            return Err(AnalyzerError::InvalidOption(
+                "time_budget_seconds must be a positive number".to_string(),
+            ));
+        }
+        if let (Some(min), Some(max)) = (self.min_lines_changed, self.max_lines_changed) {
+            if min > max {
+                return Err(AnalyzerError::InvalidOption(format!(
+                    "min_lines_changed ({}) is above max_lines_changed ({})",
+                    min, max
+                )));
+            }
+        }
+        if self.max_files_changed == Some(0) {
+            return Err(AnalyzerError::InvalidOption(
+                "max_files_changed must be at least 1".to_string(),
+            ));
+        }
+        if self.bulk_changes == BulkChanges::Separate && self.max_files_changed.is_none() {
+            return Err(AnalyzerError::InvalidOption(
+                "bulk_changes=\"separate\" needs max_files_changed".to_string(),
+            ));
+        }
+        if self.indent_width == 0 {
+            return Err(AnalyzerError::InvalidOption(
+                "indent_width must be at least 1".to_string(),
+            ));
+        }
+        let (start, end) = self.working_hours;
+        if start >= end || end > 24 {
+            return Err(AnalyzerError::InvalidOption(
+                "working_hours must be [start, end) with start < end <= 24".to_string(),
+            ));
+        }
+        if self.max_parallel_repos == Some(0) {
+            return Err(AnalyzerError::InvalidOption(
+                "max_parallel_repos must be at least 1".to_string(),
+            ));
+        }
+        if self.commit_buffer_mb == Some(0) {
+            return Err(AnalyzerError::InvalidOption(
+                "commit_buffer_mb must be at least 1".to_string(),
+            ));
+        }
+        if self.progress_refresh_hz == Some(0) {
+            return Err(AnalyzerError::InvalidOption(
+                "progress_refresh_hz must be at least 1".to_string(),
+            ));
+        }
+
+        if self.rename_lineage && !self.detect_renames {
+            return Err(AnalyzerError::InvalidOption(
+                "rename_lineage needs detect_renames".to_string(),
+            ));
+        }
+
+        if let (Some(since), Some(until)) = (self.compiled.since, self.compiled.until) {
+            if since > until {
+                return Err(AnalyzerError::InvalidOption(
+                    "since must not be later than until".to_string(),
+                ));
+            }
+        }
+
+        Ok(self)
+    }
+
+    // Whether a section reads a commit's diff itself rather than its deltas
+    pub fn reads_commit_diff(&self) -> bool {
+        self.function_churn
+            || self.track_dependencies
+            || self.compiled.ci_matcher.is_some()
+            || self.track_licenses
+            || self.scan_secrets
+            || self.track_large_binaries
+    }
+
+    // Whether any enabled mode inspects the content of changed lines
+    pub fn needs_line_content(&self) -> bool {
+        self.word_diff
+            || self.detect_formatting
+            || self.classify_lines
+            || self.track_markers
+            || self.nesting_depth
+            || self.public_api
+            || self.import_churn
+    }
+
+    // Whether notes have to be read for every commit
+    pub fn reads_notes(&self) -> bool {
+        self.include_notes || self.compiled.notes_pattern.is_some()
+    }
+
+    pub fn matches_notes(&self, notes: &BTreeMap<String, String>) -> bool {
+        self.compiled
+            .notes_pattern
+            .as_ref()
+            .is_none_or(|pattern| notes.values().any(|note| pattern.is_match(note)))
+    }
+
+    // `parent_count` as history rewrites leave it, see history::Replacements
+    pub fn skips_commit(&self, parent_count: usize) -> bool {
+        self.merge_diff == MergeDiff::Skip && parent_count > 1
+    }
+
+    pub fn ignores_whitespace(&self) -> bool {
+        self.ignore_whitespace || self.ignore_whitespace_change
+    }
+
+    pub fn matches_author(&self, signature: &git2::Signature) -> bool {
+        if self.compiled.patterns.is_empty() {
+            return true;
+        }
+        let value = authors::field(signature, self.match_author_on, self.normalize_authors);
+        self.compiled.patterns.iter().any(|p| p.is_match(&value))
+    }
+
+    fn author_regex(&self, pattern: &str) -> Result<Regex, regex::Error> {
+        let pattern = if self.normalize_authors { pattern.nfc().collect() } else { pattern.to_string() };
+        let pattern = if self.exact_author_match {
+            format!("^(?:{})$", pattern)
+        } else {
+            pattern
+        };
+        RegexBuilder::new(&pattern)
+            .case_insensitive(self.ignore_author_case)
+            .build()
+    }
+
+    // Labels of the groups `signature` matches a pattern of, or the unmatched
+    // bucket
+    pub fn group_labels(&self, signature: &git2::Signature) -> Vec<String> {
+        let value = authors::field(signature, self.match_author_on, self.normalize_authors);
+        let labels: Vec<String> = self
+            .compiled
+            .groups
+            .iter()
+            .filter(|(_, regexes)| regexes.iter().any(|r| r.is_match(&value)))
+            .map(|(label, _)| label.clone())
+            .collect();
+        if labels.is_empty() {
+            return vec![UNMATCHED_GROUP.to_string()];
+        }
+        labels
+    }
+
+    // Labels of the author patterns `signature` matches, each once
+    pub fn matching_labels(&self, signature: &git2::Signature) -> Vec<String> {
+        let value = authors::field(signature, self.match_author_on, self.normalize_authors);
+        let mut labels = Vec::new();
+        for (idx, pattern) in self.compiled.patterns.iter().enumerate() {
+            if !pattern.is_match(&value) {
+                continue;
+            }
+            let label = self.pattern_labels.get(idx).cloned().unwrap_or_else(|| idx.to_string());
+            if !labels.contains(&label) {
+                labels.push(label);
+            }
+        }
+        labels
+    }
+
+    // Key of the bucket a commit made at `timestamp` is counted in
+    pub fn bucket_key(&self, timestamp: i64) -> String {
+        match &self.compiled.bucket_format {
+            Some(format) => format.key(timestamp),
+            None => self.bucket.key(timestamp, self.fiscal_year_start_month),
+        }
+    }
+
+    // Whether a walk started at `started` may go on under time_budget_seconds
+    pub fn within_budget(&self, started: Instant) -> bool {
+        self.time_budget_seconds
+            .is_none_or(|budget| started.elapsed().as_secs_f64() < budget)
+    }
+
+    // Whether a commit changing `lines` lines is within min_lines_changed and
+    // max_lines_changed
+    pub fn within_size_bounds(&self, lines: u64) -> bool {
+        self.min_lines_changed.is_none_or(|min| lines >= min)
+            && self.max_lines_changed.is_none_or(|max| lines <= max)
+    }
+
+    // Whether a commit changing `files` tracked files is over max_files_changed
+    pub fn is_bulk(&self, files: usize) -> bool {
+        self.max_files_changed.is_some_and(|max| files > max)
+    }
+
+    pub fn check_cancelled(&self) -> Result<(), AnalyzerError> {
+        match &self.compiled.cancel {
+            Some(cancel) if cancel.load(Ordering::Relaxed) => Err(AnalyzerError::Cancelled),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn in_date_range(&self, timestamp: i64) -> bool {
+        self.compiled.since.is_none_or(|since| timestamp >= since)
+            && self.compiled.until.is_none_or(|until| timestamp <= until)
+    }
+
+    // Name of the first commit-level filter that rejects `commit`, if any
+    pub fn rejected_by(&self, commit: &git2::Commit, parent_count: usize) -> Option<&'static str> {
+        if !self.matches_author(&commit.author()) {
+            Some("author")
+        } else if !self.in_date_range(commit.author().when().seconds()) {
+            Some("date_range")
+        } else if self.skips_commit(parent_count) {
+            Some("merge")
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+impl AnalysisOptions {
+    // Serialized options with secret values masked, for display
+    fn redacted(&self) -> Value {
+        let mut value = serde_json::to_value(self).unwrap_or_default();
+        if let Value::Object(fields) = &mut value {
+            for name in SECRET_FIELDS {
+                if fields.get(*name).is_some_and(|v| !v.is_null()) {
+                    fields.insert(name.to_string(), Value::from(REDACTED));
+                }
+            }
+        }
+        value
+    }
+
+    // Picks the options object if one was passed, otherwise builds one from the
+    // legacy flat arguments. Mixing both is rejected rather than silently merged.
+    pub fn resolve(
+        options: Option<AnalysisOptions>,
+        patterns: Vec<String>,
+        show_progress: Option<bool>,
+    ) -> PyResult<AnalysisOptions> {
+        // Every Python entry point resolves its options first
+        Python::with_gil(crate::logging::sync_level);
+        match options {
+            Some(options) => {
+                if !patterns.is_empty() || show_progress.is_some() {
+                    return Err(PyValueError::new_err(
+                        "pass either an AnalysisOptions object or patterns/show_progress, not both",
+                    ));
+                }
+                Ok(options)
+            }
+            None => AnalysisOptions {
+                patterns,
+                show_progress: show_progress.unwrap_or(false),
+                ..Default::default()
+            }
+            .validated()
+            .map_err(PyErr::from),
+        }
+    }
+}
+
+#[cfg(feature = "python")]
+#[pymethods]
+impl AnalysisOptions {
+    #[new]
+    #[pyo3(signature = (**kwargs))]
+    fn py_new(kwargs: Option<&PyDict>) -> PyResult<Self> {
+        let options: AnalysisOptions = match kwargs {
+            Some(kwargs) => from_py(kwargs)?,
+            None => AnalysisOptions::default(),
+        };
+        options.validated().map_err(PyErr::from)
+    }
+
+    #[pyo3(signature = (**kwargs))]
+    fn replace(&self, kwargs: Option<&PyDict>) -> PyResult<Self> {
+        let mut value = serde_json::to_value(self).map_err(|e| PyValueError::new_err(e.to_string()))?;
+        if let (Some(kwargs), Value::Object(fields)) = (kwargs, &mut value) {
+            if let Value::Object(overrides) = py_to_json(kwargs)? {
+                fields.extend(overrides);
+            }
+        }
+        let options: AnalysisOptions =
+            serde_json::from_value(value).map_err(|e| PyValueError::new_err(e.to_string()))?;
+        options.validated().map_err(PyErr::from)
+    }
+
+    #[getter]
+    fn patterns(&self) -> Vec<String> {
+        self.patterns.clone()
+    }
+
+    #[getter]
+    fn show_progress(&self) -> bool {
+        self.show_progress
+    }
+
+    #[getter]
+    fn progress_template(&self) -> Option<String> {
+        self.progress_template.clone()
+    }
+
+    #[getter]
+    fn progress_refresh_hz(&self) -> Option<u8> {
+        self.progress_refresh_hz
+    }
+
+    #[getter]
+    fn progress_fd(&self) -> Option<i32> {
+        self.progress_fd
+    }
+
+    #[getter]
+    fn quiet(&self) -> bool {
+        self.quiet
+    }
+
+    #[getter]
+    fn on_error(&self, py: Python<'_>) -> PyResult<PyObject> {
+        to_py(py, &self.on_error)
+    }
+
+    #[getter]
+    fn partial_results(&self) -> bool {
+        self.partial_results
+    }
+
+    #[getter]
+    fn time_budget_seconds(&self) -> Option<f64> {
+        self.time_budget_seconds
+    }
+
+    #[getter]
+    fn ignore_replacements(&self) -> bool {
+        self.ignore_replacements
+    }
+
+    #[getter]
+    fn fetch_missing_objects(&self) -> bool {
+        self.fetch_missing_objects
+    }
+
+    #[getter]
+    fn commit_buffer_mb(&self) -> Option<u64> {
+        self.commit_buffer_mb
+    }
+
+    #[getter]
+    fn max_parallel_repos(&self) -> Option<usize> {
+        self.max_parallel_repos
+    }
+
+    #[getter]
+    fn since(&self) -> Option<String> {
+        self.since.clone()
+    }
+
+    #[getter]
+    fn until(&self) -> Option<String> {
+        self.until.clone()
+    }
+
+    #[getter]
+    fn bucket(&self, py: Python<'_>) -> PyResult<PyObject> {
+        to_py(py, &self.bucket)
+    }
+
+    #[getter]
+    fn fiscal_year_start_month(&self) -> u32 {
+        self.fiscal_year_start_month
+    }
+
+    #[getter]
+    fn bucket_format(&self) -> Option<String> {
+        self.bucket_format.clone()
+    }
+
+    #[getter]
+    fn output_format(&self) -> &'static str {
+        self.output_format.as_str()
+    }
+
+    #[getter]
+    fn pivot(&self, py: Python<'_>) -> PyResult<PyObject> {
+        to_py(py, &self.pivot)
+    }
+
+    #[getter]
+    fn groups(&self) -> BTreeMap<String, Vec<String>> {
+        self.groups.clone()
+    }
+
+    #[getter]
+    fn pattern_labels(&self) -> Vec<String> {
+        self.pattern_labels.clone()
+    }
+
+    #[getter]
+    fn use_ignore_file(&self) -> bool {
+        self.use_ignore_file
+    }
+
+    #[getter]
+    fn pathspecs(&self) -> Vec<String> {
+        self.pathspecs.clone()
+    }
+
+    #[getter]
+    fn ignore_whitespace(&self) -> bool {
+        self.ignore_whitespace
+    }
+
+    #[getter]
+    fn ignore_whitespace_change(&self) -> bool {
+        self.ignore_whitespace_change
+    }
+
+    #[getter]
+    fn ignore_blank_lines(&self) -> bool {
+        self.ignore_blank_lines
+    }
+
+    #[getter]
+    fn include_typechange(&self) -> bool {
+        self.include_typechange
+    }
+
+    #[getter]
+    fn context_lines(&self) -> Option<u32> {
+        self.context_lines
+    }
+
+    #[getter]
+    fn word_diff(&self) -> bool {
+        self.word_diff
+    }
+
+    #[getter]
+    fn detect_formatting(&self) -> bool {
+        self.detect_formatting
+    }
+
+    #[getter]
+    fn classify_lines(&self) -> bool {
+        self.classify_lines
+    }
+
+    #[getter]
+    fn classify_tests(&self) -> bool {
+        self.classify_tests
+    }
+
+    #[getter]
+    fn test_patterns(&self) -> Vec<String> {
+        self.test_patterns.clone()
+    }
+
+    #[getter]
+    fn docs_churn(&self) -> bool {
+        self.docs_churn
+    }
+
+    #[getter]
+    fn track_markers(&self) -> bool {
+        self.track_markers
+    }
+
+    #[getter]
+    fn markers(&self) -> Vec<String> {
+        self.markers.clone()
+    }
+
+    #[getter]
+    fn track_dependencies(&self) -> bool {
+        self.track_dependencies
+    }
+
+    #[getter]
+    fn dependency_manifests(&self) -> Vec<String> {
+        self.dependency_manifests.clone()
+    }
+
+    #[getter]
+    fn parse_dependencies(&self) -> bool {
+        self.parse_dependencies
+    }
+
+    #[getter]
+    fn track_ci(&self) -> bool {
+        self.track_ci
+    }
+
+    #[getter]
+    fn ci_patterns(&self) -> Vec<String> {
+        self.ci_patterns.clone()
+    }
+
+    #[getter]
+    fn classify_infra(&self) -> bool {
+        self.classify_infra
+    }
+
+    #[getter]
+    fn infra_patterns(&self) -> Vec<String> {
+        self.infra_patterns.clone()
+    }
+
+    #[getter]
+    fn track_licenses(&self) -> bool {
+        self.track_licenses
+    }
+
+    #[getter]
+    fn scan_secrets(&self) -> bool {
+        self.scan_secrets
+    }
+
+    #[getter]
+    fn secret_patterns(&self) -> BTreeMap<String, String> {
+        self.secret_patterns.clone()
+    }
+
+    #[getter]
+    fn track_large_binaries(&self) -> bool {
+        self.track_large_binaries
+    }
+
+    #[getter]
+    fn large_binary_threshold(&self) -> u64 {
+        self.large_binary_threshold
+    }
+
+    #[getter]
+    fn cumulative_lines(&self) -> bool {
+        self.cumulative_lines
+    }
+
+    #[getter]
+    fn language_share(&self) -> bool {
+        self.language_share
+    }
+
+    #[getter]
+    fn language_diversity(&self) -> bool {
+        self.language_diversity
+    }
+
+    #[getter]
+    fn churn_metrics(&self) -> bool {
+        self.churn_metrics
+    }
+
+    #[getter]
+    fn merge_diff(&self) -> &'static str {
+        self.merge_diff.as_str()
+    }
+
+    #[getter]
+    fn include_empty(&self) -> bool {
+        self.include_empty
+    }
+
+    #[getter]
+    fn min_lines_changed(&self) -> Option<u64> {
+        self.min_lines_changed
+    }
+
+    #[getter]
+    fn max_lines_changed(&self) -> Option<u64> {
+        self.max_lines_changed
+    }
+
+    #[getter]
+    fn max_files_changed(&self) -> Option<usize> {
+        self.max_files_changed
+    }
+
+    #[getter]
+    fn bulk_changes(&self, py: Python<'_>) -> PyResult<PyObject> {
+        to_py(py, &self.bulk_changes)
+    }
+
+    #[getter]
+    fn include_no_extension(&self) -> bool {
+        self.include_no_extension
+    }
+
+    #[getter]
+    fn file_detection(&self, py: Python<'_>) -> PyResult<PyObject> {
+        to_py(py, &self.file_detection)
+    }
+
+    #[getter]
+    fn normalize_authors(&self) -> bool {
+        self.normalize_authors
+    }
+
+    #[getter]
+    fn match_author_on(&self) -> &'static str {
+        self.match_author_on.as_str()
+    }
+
+    #[getter]
+    fn ignore_author_case(&self) -> bool {
+        self.ignore_author_case
+    }
+
+    #[getter]
+    fn exact_author_match(&self) -> bool {
+        self.exact_author_match
+    }
+
+    #[getter]
+    fn detect_duplicate_authors(&self) -> bool {
+        self.detect_duplicate_authors
+    }
+
+    #[getter]
+    fn include_notes(&self) -> bool {
+        self.include_notes
+    }
+
+    #[getter]
+    fn notes_refs(&self) -> Vec<String> {
+        self.notes_refs.clone()
+    }
+
+    #[getter]
+    fn notes_pattern(&self) -> Option<String> {
+        self.notes_pattern.clone()
+    }
+
+    #[getter]
+    fn message_metrics(&self) -> bool {
+        self.message_metrics
+    }
+
+    #[getter]
+    fn classify_gitmoji(&self) -> bool {
+        self.classify_gitmoji
+    }
+
+    #[getter]
+    fn gitmoji_categories(&self) -> BTreeMap<String, String> {
+        self.gitmoji_categories.clone()
+    }
+
+    #[getter]
+    fn detect_anomalies(&self) -> bool {
+        self.detect_anomalies
+    }
+
+    #[getter]
+    fn anomaly_method(&self) -> &'static str {
+        self.anomaly_method.as_str()
+    }
+
+    #[getter]
+    fn anomaly_threshold(&self) -> Option<f64> {
+        self.anomaly_threshold
+    }
+
+    #[getter]
+    fn collaboration_network(&self) -> bool {
+        self.collaboration_network
+    }
+
+    #[getter]
+    fn collaboration_window_days(&self) -> u32 {
+        self.collaboration_window_days
+    }
+
+    #[getter]
+    fn ownership_timeline(&self) -> bool {
+        self.ownership_timeline
+    }
+
+    #[getter]
+    fn estimate_effort(&self) -> bool {
+        self.estimate_effort
+    }
+
+    #[getter]
+    fn cocomo_mode(&self) -> &'static str {
+        self.cocomo_mode.as_str()
+    }
+
+    #[getter]
+    fn cocomo_coefficients(&self, py: Python<'_>) -> PyResult<PyObject> {
+        to_py(py, &self.cocomo_coefficients)
+    }
+
+    #[getter]
+    fn cost_per_person_month(&self) -> Option<f64> {
+        self.cost_per_person_month
+    }
+
+    #[getter]
+    fn contributor_report(&self) -> bool {
+        self.contributor_report
+    }
+
+    #[getter]
+    fn dormancy_months(&self) -> u32 {
+        self.dormancy_months
+    }
+
+    #[getter]
+    fn new_contributors(&self) -> bool {
+        self.new_contributors
+    }
+
+    #[getter]
+    fn contributor_growth(&self) -> bool {
+        self.contributor_growth
+    }
+
+    #[getter]
+    fn timezone_distribution(&self) -> bool {
+        self.timezone_distribution
+    }
+
+    #[getter]
+    fn out_of_hours(&self) -> bool {
+        self.out_of_hours
+    }
+
+    #[getter]
+    fn working_hours(&self) -> (u8, u8) {
+        self.working_hours
+    }
+
+    #[getter]
+    fn commit_latency(&self) -> bool {
+        self.commit_latency
+    }
+
+    #[getter]
+    fn latency_threshold_hours(&self) -> f64 {
+        self.latency_threshold_hours
+    }
+
+    #[getter]
+    fn files_touched(&self) -> bool {
+        self.files_touched
+    }
+
+    #[getter]
+    fn commit_size(&self) -> bool {
+        self.commit_size
+    }
+
+    #[getter]
+    fn review_trailers(&self) -> bool {
+        self.review_trailers
+    }
+
+    #[getter]
+    fn self_merges(&self) -> bool {
+        self.self_merges
+    }
+
+    #[getter]
+    fn detect_squash_merges(&self) -> bool {
+        self.detect_squash_merges
+    }
+
+    #[getter]
+    fn squash_patterns(&self) -> Vec<String> {
+        self.squash_patterns.clone()
+    }
+
+    #[getter]
+    fn squash_min_lines(&self) -> Option<u64> {
+        self.squash_min_lines
+    }
+
+    #[getter]
+    fn message_hygiene(&self) -> bool {
+        self.message_hygiene
+    }
+
+    #[getter]
+    fn hygiene_weights(&self, py: Python<'_>) -> PyResult<PyObject> {
+        to_py(py, &self.hygiene_weights)
+    }
+
+    #[getter]
+    fn nesting_depth(&self) -> bool {
+        self.nesting_depth
+    }
+
+    #[getter]
+    fn indent_width(&self) -> u32 {
+        self.indent_width
+    }
+
+    #[getter]
+    fn function_churn(&self) -> bool {
+        self.function_churn
+    }
+
+    #[getter]
+    fn public_api(&self) -> bool {
+        self.public_api
+    }
+
+    #[getter]
+    fn import_churn(&self) -> bool {
+        self.import_churn
+    }
+
+    #[getter]
+    fn rolling_window_days(&self) -> Option<u32> {
+        self.rolling_window_days
+    }
+
+    #[getter]
+    fn decayed_churn(&self) -> bool {
+        self.decayed_churn
+    }
+
+    #[getter]
+    fn churn_half_life_days(&self) -> f64 {
+        self.churn_half_life_days
+    }
+
+    #[getter]
+    fn file_counts(&self) -> bool {
+        self.file_counts
+    }
+
+    #[getter]
+    fn file_lengths(&self) -> bool {
+        self.file_lengths
+    }
+
+    #[getter]
+    fn long_file_lines(&self) -> u32 {
+        self.long_file_lines
+    }
+
+    #[getter]
+    fn detect_renames(&self) -> bool {
+        self.detect_renames
+    }
+
+    #[getter]
+    fn rename_lineage(&self) -> bool {
+        self.rename_lineage
+    }
+
+    #[getter]
+    fn include_files(&self) -> bool {
+        self.include_files
+    }
+
+    #[getter]
+    fn git_dir(&self) -> Option<String> {
+        self.git_dir.clone()
+    }
+
+    #[getter]
+    fn clone_remote(&self) -> bool {
+        self.clone_remote
+    }
+
+    #[getter]
+    fn clone_bare(&self) -> bool {
+        self.clone_bare
+    }
+
+    #[getter]
+    fn clone_depth(&self) -> Option<u32> {
+        self.clone_depth
+    }
+
+    #[getter]
+    fn clone_since(&self) -> Option<String> {
+        self.clone_since.clone()
+    }
+
+    #[getter]
+    fn ssh_use_agent(&self) -> bool {
+        self.ssh_use_agent
+    }
+
+    #[getter]
+    fn ssh_key_path(&self) -> Option<String> {
+        self.ssh_key_path.clone()
+    }
+
+    // Secrets read back masked, as in to_dict
+    #[getter]
+    fn ssh_passphrase(&self) -> Option<&'static str> {
+        self.ssh_passphrase.as_ref().map(|_| REDACTED)
+    }
+
+    #[getter]
+    fn https_username(&self) -> Option<String> {
+        self.https_username.clone()
+    }
+
+    #[getter]
+    fn https_token(&self) -> Option<&'static str> {
+        self.https_token.as_ref().map(|_| REDACTED)
+    }
+
+    fn to_dict(&self, py: Python<'_>) -> PyResult<PyObject> {
+        to_py(py, &self.redacted())
+    }
+
+    // Pickle and copy support. Like to_dict the state leaves out secret
+    // values, so pickles and copies come without credentials; set them again
+    // with replace() or fall back to the environment variables. The state is
+    // validated again when restored. A progress callback is not kept.
+    fn __getstate__(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let mut value = serde_json::to_value(self).map_err(|e| PyValueError::new_err(e.to_string()))?;
+        if let Value::Object(fields) = &mut value {
+            for name in SECRET_FIELDS {
+                fields.remove(*name);
+            }
+        }
+        to_py(py, &value)
+    }
+
+    fn __setstate__(&mut self, state: &PyDict) -> PyResult<()> {
+        let options: AnalysisOptions = from_py(state)?;
+        *self = options.validated()?;
+        Ok(())
+    }
+
+    // Rebuilt as AnalysisOptions() plus the state, with every pickle protocol
+    fn __reduce__(slf: &PyCell<Self>, py: Python<'_>) -> PyResult<(PyObject, PyObject, PyObject)> {
+        let state = slf.borrow().__getstate__(py)?;
+        Ok((slf.get_type().into_py(py), PyTuple::empty(py).into_py(py), state))
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "AnalysisOptions({})",
+            serde_json::to_string(&self.redacted()).unwrap_or_default()
+        )
+    }
+}
+
+pub fn parse_date(value: &str, end_of_day: bool) -> Result<i64, AnalyzerError> {
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(value) {
+        return Ok(datetime.timestamp());
+    }
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| {
+        AnalyzerError::InvalidOption(format!(
+            "expected a YYYY-MM-DD or RFC 3339 date, got {:?}",
+            value
+        ))
+    })?;
+    let time = if end_of_day {
+        NaiveTime::from_hms_opt(23, 59, 59).unwrap_or_default()
+    } else {
+        NaiveTime::MIN
+    };
+    Ok(date.and_time(time).and_utc().timestamp())
+}
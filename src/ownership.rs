@@ -0,0 +1,105 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Serialize;
+
+use crate::diff::FileDelta;
+
+// Directory bucket for files in the repository root
+pub const ROOT_DIRECTORY: &str = ".";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OwnershipMonth {
+    pub owner: String,
+    // Owner's percentage of the directory's churn to date
+    pub share_pct: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OwnershipTransition {
+    pub month: String,
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct DirectoryOwnership {
+    pub months: BTreeMap<String, OwnershipMonth>,
+    pub transitions: Vec<OwnershipTransition>,
+}
+
+// Plurality owner of each top-level directory by cumulative churn, recorded
+// for every month the directory changed. The walk runs newest first, so
+// changes are buffered and replayed in date order at the end.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct OwnershipTimeline {
+    #[serde(flatten)]
+    pub directories: BTreeMap<String, DirectoryOwnership>,
+    #[serde(skip)]
+    changes: Vec<(i64, String, String, String, i64)>,
+}
+
+impl OwnershipTimeline {
+    pub fn observe(&mut self, timestamp: i64, month_key: &str, author: &str, deltas: &[FileDelta]) {
+        let mut churn: HashMap<&str, i64> = HashMap::new();
+        for delta in deltas {
+            let directory = match delta.path.split_once('/') {
+                Some((top, _)) => top,
+                None => ROOT_DIRECTORY,
+            };
+            *churn.entry(directory).or_default() += i64::from(delta.additions) + i64::from(delta.deletions);
+        }
+        for (directory, lines) in churn {
+            if lines > 0 {
+                self.changes.push((
+                    timestamp,
+                    month_key.to_string(),
+                    directory.to_string(),
+                    author.to_string(),
+                    lines,
+                ));
+            }
+        }
+    }
+
+    pub fn finish(&mut self) {
+        self.changes.sort();
+        let mut totals: HashMap<String, BTreeMap<String, i64>> = HashMap::new();
+        for (_, month, directory, author, lines) in std::mem::take(&mut self.changes) {
+            let by_author = totals.entry(directory.clone()).or_default();
+            *by_author.entry(author).or_default() += lines;
+            let total: i64 = by_author.values().sum();
+
+            let ownership = self.directories.entry(directory).or_default();
+            let previous = ownership.months.values().next_back().map(|m| m.owner.clone());
+            // Ties keep the current owner so ownership does not flap
+            let (owner, owned) = by_author
+                .iter()
+                .max_by(|a, b| {
+                    a.1.cmp(b.1)
+                        .then_with(|| (Some(a.0) == previous.as_ref()).cmp(&(Some(b.0) == previous.as_ref())))
+                        .then_with(|| b.0.cmp(a.0))
+                })
+                .map(|(owner, owned)| (owner.clone(), *owned))
+                .unwrap_or_default();
+
+            let share_pct = (owned as f64 * 10000.0 / total as f64).round() / 100.0;
+            ownership.months.insert(month, OwnershipMonth { owner, share_pct });
+        }
+
+        // Transitions compare month-end owners, so a change undone within the
+        // same month is not reported
+        for ownership in self.directories.values_mut() {
+            let mut previous: Option<&String> = None;
+            for (month, current) in &ownership.months {
+                if let Some(previous) = previous.filter(|p| **p != current.owner) {
+                    ownership.transitions.push(OwnershipTransition {
+                        month: month.clone(),
+                        from: previous.clone(),
+                        to: current.owner.clone(),
+                    });
+                }
+                previous = Some(&current.owner);
+            }
+        }
+    }
+}
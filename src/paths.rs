@@ -0,0 +1,96 @@
+use std::path::Path;
+
+use git2::Repository;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+
+use crate::promisor;
+use crate::AnalyzerError;
+
+pub const TEXT_EXTENSIONS: &[&str] = &[
+    ".txt", ".md", ".rs", ".py", ".js", ".ts", ".jsx", ".tsx",
+    ".html", ".css", ".scss", ".json", ".yaml", ".yml", ".toml",
+    ".c", ".cpp", ".h", ".hpp", ".java", ".go", ".rb", ".php"
+];
+
+pub const IGNORE_FILE_NAME: &str = ".repo-scan-ignore";
+
+// Per-repository exclusions read from `.repo-scan-ignore` (gitignore syntax) in
+// the repository root. Bare repositories read the file from HEAD instead.
+// Partial clones also keep files whose blobs were never fetched, with their
+// status only.
+#[derive(Default)]
+pub struct PathFilter {
+    ignore: Option<Gitignore>,
+    pub partial_clone: bool,
+}
+
+impl PathFilter {
+    pub fn for_repo(repo: &Repository, enabled: bool) -> Result<Self, AnalyzerError> {
+        let partial_clone = promisor::promisor_remote(repo).is_some();
+        if !enabled {
+            return Ok(PathFilter { ignore: None, partial_clone });
+        }
+
+        let contents = match repo.workdir() {
+            Some(workdir) => std::fs::read_to_string(workdir.join(IGNORE_FILE_NAME)).ok(),
+            None => read_from_head(repo)?,
+        };
+        let Some(contents) = contents else {
+            return Ok(PathFilter { ignore: None, partial_clone });
+        };
+
+        let ignore = build_matcher(contents.lines())
+            .map_err(|e| AnalyzerError::InvalidOption(format!("{}: {}", IGNORE_FILE_NAME, e)))?;
+
+        Ok(PathFilter { ignore: Some(ignore), partial_clone })
+    }
+
+    // The same filter without the ignore file
+    pub fn without_ignore(&self) -> PathFilter {
+        PathFilter {
+            ignore: None,
+            partial_clone: self.partial_clone,
+        }
+    }
+
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        self.ignore
+            .as_ref()
+            .is_some_and(|ignore| ignore.matched_path_or_any_parents(path, false).is_ignore())
+    }
+}
+
+// Compiles gitignore-syntax patterns into a matcher for repository-relative paths
+pub fn build_matcher<'a>(
+    patterns: impl IntoIterator<Item = &'a str>,
+) -> Result<Gitignore, ignore::Error> {
+    let mut builder = GitignoreBuilder::new("");
+    for pattern in patterns {
+        builder.add_line(None, pattern)?;
+    }
+    builder.build()
+}
+
+pub fn matches(matcher: &Gitignore, path: &str) -> bool {
+    matcher.matched_path_or_any_parents(path, false).is_ignore()
+}
+
+fn read_from_head(repo: &Repository) -> Result<Option<String>, AnalyzerError> {
+    let Ok(head) = repo.head() else {
+        return Ok(None);
+    };
+    let tree = head.peel_to_tree()?;
+    let Some(entry) = tree.get_name(IGNORE_FILE_NAME) else {
+        return Ok(None);
+    };
+    let blob = entry.to_object(repo)?.peel_to_blob()?;
+    Ok(Some(String::from_utf8_lossy(blob.content()).into_owned()))
+}
+
+// Lower-cased extension with a leading dot, or an empty string if there is none
+pub fn extension_of(path: &Path) -> String {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| format!(".{}", e.to_lowercase()))
+        .unwrap_or_default()
+}
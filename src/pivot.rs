@@ -0,0 +1,48 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::options::Pivot;
+use crate::{convert_to_python_format, MonthlyStats, PyMonthlyStats};
+
+// The results of analyze_git_repo nested the way the pivot option asks for;
+// grouped months, collected for the author and pattern pivots and for
+// `groups`, come first
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum PivotedStats {
+    // month -> extension -> stats, or extension -> month -> stats
+    Nested(PyMonthlyStats),
+    // author, pattern label or group -> month -> extension -> stats
+    Grouped(HashMap<String, PyMonthlyStats>),
+}
+
+pub fn pivot(
+    months: &MonthlyStats,
+    group_months: Option<&HashMap<String, MonthlyStats>>,
+    pivot: Pivot,
+) -> PivotedStats {
+    if let Some(group_months) = group_months {
+        return PivotedStats::Grouped(
+            group_months
+                .iter()
+                .map(|(group, months)| (group.clone(), convert_to_python_format(months)))
+                .collect(),
+        );
+    }
+    match pivot {
+        Pivot::Extension => {
+            let mut result: PyMonthlyStats = HashMap::new();
+            for (month, exts) in months {
+                for (ext, stats) in exts {
+                    result
+                        .entry(ext.clone())
+                        .or_default()
+                        .insert(month.clone(), stats.to_map(true));
+                }
+            }
+            PivotedStats::Nested(result)
+        }
+        _ => PivotedStats::Nested(convert_to_python_format(months)),
+    }
+}
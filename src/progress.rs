@@ -0,0 +1,159 @@
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+
+use crate::options::AnalysisOptions;
+use crate::AnalyzerError;
+
+// The history is walked lazily, so there is no total for a bar to fill
+pub const DEFAULT_TEMPLATE: &str = "[{elapsed_precise}] {spinner:.cyan} {pos} commits";
+// Used when several repositories draw side by side; {prefix} is the label
+pub const DEFAULT_GROUP_TEMPLATE: &str = "{prefix} [{elapsed_precise}] {spinner:.cyan} {pos} commits";
+// indicatif's own default redraw rate
+pub const DEFAULT_REFRESH_HZ: u8 = 20;
+
+// Called with (commits done, commits total) at most once per refresh interval
+// and once more when the walk ends. The total is None until the walk is over.
+#[derive(Clone)]
+pub struct ProgressCallback(Arc<dyn Fn(u64, Option<u64>) + Send + Sync>);
+
+#[cfg(feature = "python")]
+impl ProgressCallback {
+    pub fn new(callback: impl Fn(u64, Option<u64>) + Send + Sync + 'static) -> Self {
+        ProgressCallback(Arc::new(callback))
+    }
+}
+
+impl fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressCallback")
+    }
+}
+
+// One bar per repository of a multi-repository scan, drawn as a single block
+#[derive(Debug, Clone)]
+pub struct ProgressGroup {
+    multi: MultiProgress,
+    label: String,
+}
+
+impl ProgressGroup {
+    // None unless the options ask for a visible bar
+    pub fn new(options: &AnalysisOptions) -> Result<Option<Self>, AnalyzerError> {
+        if !options.show_progress || options.quiet {
+            return Ok(None);
+        }
+        let refresh_hz = options.progress_refresh_hz.unwrap_or(DEFAULT_REFRESH_HZ);
+        Ok(Some(ProgressGroup {
+            multi: MultiProgress::with_draw_target(draw_target(options, refresh_hz)?),
+            label: String::new(),
+        }))
+    }
+
+    pub fn with_label(&self, label: &str) -> Self {
+        ProgressGroup {
+            multi: self.multi.clone(),
+            label: label.to_string(),
+        }
+    }
+}
+
+pub fn style(template: Option<&str>) -> Result<ProgressStyle, AnalyzerError> {
+    ProgressStyle::with_template(template.unwrap_or(DEFAULT_TEMPLATE))
+        .map_err(|e| AnalyzerError::InvalidOption(format!("progress_template: {}", e)))
+}
+
+// Progress over a commit walk: the terminal bar when `show_progress` is set
+// and `quiet` is not, plus the caller's callback if one was given
+pub struct Progress {
+    bar: Option<ProgressBar>,
+    callback: Option<ProgressCallback>,
+    done: u64,
+    finished: bool,
+    interval: Duration,
+    reported_at: Option<Instant>,
+}
+
+impl Progress {
+    pub fn new(options: &AnalysisOptions) -> Result<Self, AnalyzerError> {
+        let refresh_hz = options.progress_refresh_hz.unwrap_or(DEFAULT_REFRESH_HZ);
+        let bar = if let Some(group) = &options.compiled.progress_group {
+            let bar = group.multi.add(ProgressBar::new_spinner());
+            let template = options.progress_template.as_deref().unwrap_or(DEFAULT_GROUP_TEMPLATE);
+            bar.set_style(style(Some(template))?);
+            bar.set_prefix(group.label.clone());
+            Some(bar)
+        } else if options.show_progress && !options.quiet {
+            let bar = ProgressBar::with_draw_target(None, draw_target(options, refresh_hz)?);
+            bar.set_style(style(options.progress_template.as_deref())?);
+            Some(bar)
+        } else {
+            None
+        };
+        Ok(Progress {
+            bar,
+            callback: options.compiled.progress_callback.clone(),
+            done: 0,
+            finished: false,
+            interval: Duration::from_secs(1) / u32::from(refresh_hz),
+            reported_at: None,
+        })
+    }
+
+    pub fn inc(&mut self) {
+        self.done += 1;
+        if let Some(bar) = &self.bar {
+            bar.inc(1);
+        }
+        if self.reported_at.is_none_or(|at| at.elapsed() >= self.interval) {
+            self.report();
+        }
+    }
+
+    pub fn finish(&mut self) {
+        if let Some(bar) = &self.bar {
+            bar.finish();
+        }
+        self.finished = true;
+        self.report();
+    }
+
+    fn report(&mut self) {
+        if let Some(callback) = &self.callback {
+            (callback.0)(self.done, self.finished.then_some(self.done));
+        }
+        self.reported_at = Some(Instant::now());
+    }
+}
+
+// stderr unless `progress_fd` names another open descriptor, which is
+// duplicated so the caller keeps ownership of theirs
+fn draw_target(options: &AnalysisOptions, refresh_hz: u8) -> Result<ProgressDrawTarget, AnalyzerError> {
+    let Some(fd) = options.progress_fd else {
+        return Ok(ProgressDrawTarget::stderr_with_hz(refresh_hz));
+    };
+    #[cfg(unix)]
+    {
+        use std::fs::File;
+        use std::os::fd::BorrowedFd;
+
+        let invalid = |e: std::io::Error| AnalyzerError::InvalidOption(format!("progress_fd {}: {}", fd, e));
+        if fd < 0 {
+            return Err(AnalyzerError::InvalidOption(format!("progress_fd {} is not a descriptor", fd)));
+        }
+        // SAFETY: the descriptor is only borrowed for the duration of the dup
+        let owned = unsafe { BorrowedFd::borrow_raw(fd) }.try_clone_to_owned().map_err(invalid)?;
+        let write = File::from(owned);
+        let read = write.try_clone().map_err(invalid)?;
+        Ok(ProgressDrawTarget::term(console::Term::read_write_pair(read, write), refresh_hz))
+    }
+    #[cfg(not(unix))]
+    {
+        Err(AnalyzerError::InvalidOption(format!(
+            "progress_fd {} is only supported on Unix",
+            fd
+        )))
+    }
+}
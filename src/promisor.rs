@@ -0,0 +1,63 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use git2::{Diff, Oid, Repository};
+use tracing::debug;
+
+use crate::AnalyzerError;
+
+// The remote a partial clone (--filter=blob:none and the like) fetches left
+// out objects from, if the repository is one
+pub fn promisor_remote(repo: &Repository) -> Option<String> {
+    let config = repo.config().ok()?.snapshot().ok()?;
+    if let Ok(remote) = config.get_string("extensions.partialclone") {
+        return Some(remote);
+    }
+    let remotes = repo.remotes().ok()?;
+    let remote = remotes
+        .iter()
+        .flatten()
+        .find(|name| config.get_bool(&format!("remote.{}.promisor", name)).unwrap_or(false))
+        .map(str::to_string);
+    remote
+}
+
+// Fetches the blobs of `diff` missing from the object database in one
+// request, the way git fetches them lazily. libgit2 cannot talk to promisor
+// remotes, so this runs the git executable.
+pub fn fetch_missing(repo: &Repository, remote: &str, diff: &Diff) -> Result<usize, AnalyzerError> {
+    let odb = repo.odb()?;
+    let mut missing: Vec<Oid> = diff
+        .deltas()
+        .flat_map(|delta| [delta.old_file().id(), delta.new_file().id()])
+        .filter(|oid| !oid.is_zero() && !odb.exists(*oid))
+        .collect();
+    missing.sort();
+    missing.dedup();
+    if missing.is_empty() {
+        return Ok(0);
+    }
+    debug!(remote, objects = missing.len(), "fetching missing objects");
+
+    let failed = |e: String| git2::Error::from_str(&format!("fetching missing objects from {}: {}", remote, e));
+    let mut child = Command::new("git")
+        .arg("--git-dir")
+        .arg(repo.path())
+        .args(["-c", "fetch.negotiationAlgorithm=noop", "fetch", remote])
+        .args(["--no-tags", "--no-write-fetch-head", "--recurse-submodules=no", "--filter=blob:none", "--stdin"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| failed(e.to_string()))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        for oid in &missing {
+            writeln!(stdin, "{}", oid).map_err(|e| failed(e.to_string()))?;
+        }
+    }
+    let output = child.wait_with_output().map_err(|e| failed(e.to_string()))?;
+    if !output.status.success() {
+        return Err(failed(String::from_utf8_lossy(&output.stderr).trim().to_string()).into());
+    }
+    Ok(missing.len())
+}
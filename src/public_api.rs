@@ -0,0 +1,76 @@
+use std::collections::BTreeSet;
+
+use git2::Commit;
+use regex::Regex;
+use serde::Serialize;
+
+use crate::diff::FileDelta;
+
+// `pub` items declared on one line; pub(crate) and other restricted
+// visibilities are not public API
+pub fn pub_item_regex() -> Regex {
+    Regex::new(
+        r#"^\s*pub\s+(?:(?:unsafe|async|const|default|extern\s+"[^"]*")\s+)*(fn|struct|enum|union|trait|type|const|static|mod|macro)\s+(?:mut\s+)?([A-Za-z_][A-Za-z0-9_]*)"#,
+    )
+    .expect("valid regex")
+}
+
+// (kind, name) of the items declared on the lines
+fn declared<'a>(lines: impl Iterator<Item = &'a String>, regex: &Regex) -> BTreeSet<(String, String)> {
+    lines
+        .filter_map(|line| regex.captures(line))
+        .map(|c| (c[1].to_string(), c[2].to_string()))
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct ApiItem {
+    pub path: String,
+    pub kind: String,
+    pub name: String,
+    pub commit: String,
+}
+
+// Public items of .rs files whose declaration line was added, removed, or
+// both in one commit (a changed signature). Moving an item to another file
+// shows as a removal and an addition.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct ApiMonth {
+    pub added: i32,
+    pub removed: i32,
+    pub changed: i32,
+    pub items_added: Vec<ApiItem>,
+    pub items_removed: Vec<ApiItem>,
+    pub items_changed: Vec<ApiItem>,
+}
+
+impl ApiMonth {
+    pub fn observe(&mut self, commit: &Commit, deltas: &[FileDelta], regex: &Regex) {
+        for delta in deltas.iter().filter(|d| d.path.ends_with(".rs")) {
+            let added = declared(delta.hunks.iter().flat_map(|h| &h.added), regex);
+            let removed = declared(delta.hunks.iter().flat_map(|h| &h.removed), regex);
+            let item = |(kind, name): &(String, String)| ApiItem {
+                path: delta.path.clone(),
+                kind: kind.clone(),
+                name: name.clone(),
+                commit: commit.id().to_string(),
+            };
+            for declared in added.union(&removed) {
+                match (added.contains(declared), removed.contains(declared)) {
+                    (true, true) => self.items_changed.push(item(declared)),
+                    (true, false) => self.items_added.push(item(declared)),
+                    _ => self.items_removed.push(item(declared)),
+                }
+            }
+        }
+        self.added = self.items_added.len() as i32;
+        self.removed = self.items_removed.len() as i32;
+        self.changed = self.items_changed.len() as i32;
+    }
+
+    pub fn finish(&mut self) {
+        self.items_added.sort();
+        self.items_removed.sort();
+        self.items_changed.sort();
+    }
+}
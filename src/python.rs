@@ -0,0 +1,943 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Receiver;
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use parking_lot::{Condvar, Mutex};
+use pyo3::exceptions::PyTimeoutError;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+
+use crate::exceptions::{self, RepoScanError};
+use crate::options::{AnalysisOptions, OutputFormat};
+use crate::pivot::{self, PivotedStats};
+use crate::progress::ProgressCallback;
+use crate::report::Sections;
+use crate::{
+    analyze_commits_internal, analyze_commits_with, analyze_repo_internal, branching, changes, compare, convert,
+    convert_to_python_format, directory, fleet, graph, logging, matrix, merge, messages, remote, stash, storage, survival, worktree,
+    AnalyzerError, CommitData, FileStats, MonthlyStats, RepoAnalysis, ScanMetadata,
+};
+
+// The Python module: thin wrappers that resolve options, release the GIL for
+// the analysis and convert the result
+
+#[pyfunction]
+#[pyo3(signature = (repo_path, patterns=Vec::new(), show_progress=None, options=None, on_progress=None))]
+fn analyze_git_commits(
+    repo_path: String,
+    patterns: Vec<String>,
+    show_progress: Option<bool>,
+    options: Option<AnalysisOptions>,
+    on_progress: Option<PyObject>,
+    py: Python<'_>,
+) -> PyResult<PyObject> {
+    let options = AnalysisOptions::resolve(options, patterns, show_progress)?;
+    commits_output(py, repo_path, None, options, on_progress)
+}
+
+// analyze_git_commits on repo_path, or on `clone` when a RepoAnalyzer holds one
+fn commits_output(
+    py: Python<'_>,
+    repo_path: String,
+    clone: Option<&str>,
+    mut options: AnalysisOptions,
+    on_progress: Option<PyObject>,
+) -> PyResult<PyObject> {
+    let callback_error = attach_progress(&mut options, on_progress);
+
+    let result = PyDict::new(py);
+    let (commits, mut metadata) =
+        py.allow_threads(|| with_repo(&repo_path, clone, &options, |path| analyze_commits_internal(path, &options)))?;
+    for (commit_id, commit_data) in commits {
+        result.set_item(commit_id, commit_to_py(py, commit_data)?)?;
+    }
+    if let Some(e) = callback_error.lock().take() {
+        return Err(e);
+    }
+    metadata.repo_path = remote::redacted_url(&repo_path);
+    
+    let output = wrap_output(py, &options, "commits", result.into_py(py), &metadata, &Sections::default())?;
+    exceptions::check_partial(py, output, &metadata)
+}
+
+fn commit_to_py(py: Python<'_>, commit_data: CommitData) -> PyResult<&PyDict> {
+    let commit_dict = PyDict::new(py);
+    
+    // Add timestamp, message and author
+    commit_dict.set_item("timestamp", commit_data.timestamp)?;
+    let (subject, body) = messages::split(&commit_data.message);
+    commit_dict.set_item("subject", subject)?;
+    commit_dict.set_item("body", body)?;
+    commit_dict.set_item("message", &commit_data.message)?;
+    commit_dict.set_item("author", commit_data.author)?;
+    commit_dict.set_item("parent_count", commit_data.parents.len())?;
+    commit_dict.set_item("is_merge", commit_data.parents.len() > 1)?;
+    commit_dict.set_item("parents", commit_data.parents)?;
+    commit_dict.set_item("tree", commit_data.tree)?;
+    if let Some(gitmoji) = commit_data.gitmoji {
+        commit_dict.set_item("gitmoji", convert::to_py(py, &gitmoji)?)?;
+    }
+    if let Some(squash_merge) = commit_data.squash_merge {
+        commit_dict.set_item("squash_merge", convert::to_py(py, &squash_merge)?)?;
+    }
+    if let Some(notes) = commit_data.notes {
+        commit_dict.set_item("notes", notes)?;
+    }
+    if let Some(touches_docs) = commit_data.touches_docs {
+        commit_dict.set_item("touches_docs", touches_docs)?;
+    }
+    if let Some(files) = commit_data.files {
+        commit_dict.set_item("files", convert::to_py(py, &files)?)?;
+    }
+    
+    // Convert file stats
+    let stats_dict: HashMap<String, HashMap<String, i32>> = commit_data.stats
+        .into_iter()
+        .map(|(ext, stats)| (ext, stats.to_map(false)))
+        .collect();
+
+    commit_dict.set_item("stats", stats_dict)?;
+    Ok(commit_dict)
+}
+
+// Chunk size of iter_git_commits when commit_buffer_mb is not set
+const DEFAULT_COMMIT_BUFFER_MB: u64 = 64;
+
+type CommitChunk = Vec<(String, CommitData)>;
+
+/// analyze_git_commits records handed over in chunks, returned by
+/// `iter_git_commits`. Dropping the iterator stops the walk.
+#[pyclass(module = "repo_scan_rs")]
+struct CommitChunks {
+    repo_path: String,
+    receiver: Mutex<Receiver<CommitChunk>>,
+    worker: Option<JoinHandle<Result<ScanMetadata, AnalyzerError>>>,
+    cancel: Arc<AtomicBool>,
+    callback_error: Arc<Mutex<Option<PyErr>>>,
+    metadata: Option<PyObject>,
+}
+
+#[pymethods]
+impl CommitChunks {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    // The next {commit id: record} dict, keyed in the order analyze_git_commits
+    // uses; the walk raises its error here
+    fn __next__(&mut self, py: Python<'_>) -> PyResult<Option<PyObject>> {
+        let receiver = &self.receiver;
+        if let Ok(mut chunk) = py.allow_threads(|| receiver.lock().recv()) {
+            chunk.sort_by(|a, b| a.0.cmp(&b.0));
+            let commits = PyDict::new(py);
+            for (commit_id, commit_data) in chunk {
+                commits.set_item(commit_id, commit_to_py(py, commit_data)?)?;
+            }
+            return Ok(Some(commits.into_py(py)));
+        }
+        // The walk is over: this is the end of the iteration, or its error
+        let Some(worker) = self.worker.take() else {
+            return Ok(None);
+        };
+        let mut metadata = py
+            .allow_threads(|| worker.join())
+            .map_err(|_| RepoScanError::new_err("commit walk panicked"))??;
+        if let Some(e) = self.callback_error.lock().take() {
+            return Err(e);
+        }
+        metadata.repo_path = remote::redacted_url(&self.repo_path);
+        self.metadata = Some(convert::to_py(py, &metadata)?);
+        Ok(None)
+    }
+
+    // Scan metadata, once the iteration is over
+    #[getter]
+    fn metadata(&self, py: Python<'_>) -> Option<PyObject> {
+        self.metadata.as_ref().map(|metadata| metadata.clone_ref(py))
+    }
+
+    fn __repr__(&self) -> String {
+        let status = if self.worker.is_some() { "running" } else { "done" };
+        format!("CommitChunks({:?}, {})", self.repo_path, status)
+    }
+}
+
+impl Drop for CommitChunks {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+// analyze_git_commits for histories too large to hold at once: the walk runs
+// on a background thread and hands over records in chunks of about
+// commit_buffer_mb, so at most two chunks exist on the Rust side at a time.
+// Chunks come in walk order, each keyed by commit id like analyze_git_commits.
+#[pyfunction]
+#[pyo3(signature = (repo_path, options=None, on_progress=None))]
+fn iter_git_commits(
+    repo_path: String,
+    options: Option<AnalysisOptions>,
+    on_progress: Option<PyObject>,
+) -> PyResult<CommitChunks> {
+    let mut options = AnalysisOptions::resolve(options, Vec::new(), None)?;
+    let callback_error = attach_progress(&mut options, on_progress);
+    let cancel = Arc::new(AtomicBool::new(false));
+    options.compiled.cancel = Some(Arc::clone(&cancel));
+
+    let budget = (options.commit_buffer_mb.unwrap_or(DEFAULT_COMMIT_BUFFER_MB) as usize).saturating_mul(1 << 20);
+    let (sender, receiver) = std::sync::mpsc::sync_channel::<CommitChunk>(0);
+    let worker_path = repo_path.clone();
+    let worker = std::thread::Builder::new()
+        .name("repo-scan-commits".to_string())
+        .spawn(move || {
+            let mut chunk = Vec::new();
+            let mut chunk_bytes = 0;
+            let metadata = remote::with_local_repo(&worker_path, &options, |path| {
+                analyze_commits_with(path, &options, |commit_id, commit_data| {
+                    chunk_bytes += commit_data.approx_bytes();
+                    chunk.push((commit_id, commit_data));
+                    if chunk_bytes >= budget {
+                        chunk_bytes = 0;
+                        // The iterator was dropped
+                        sender.send(std::mem::take(&mut chunk)).map_err(|_| AnalyzerError::Cancelled)?;
+                    }
+                    Ok(())
+                })
+            })?;
+            if !chunk.is_empty() {
+                let _ = sender.send(chunk);
+            }
+            Ok(metadata)
+        })
+        .map_err(|e| RepoScanError::new_err(format!("cannot start the commit walk thread: {}", e)))?;
+
+    Ok(CommitChunks {
+        repo_path,
+        receiver: Mutex::new(receiver),
+        worker: Some(worker),
+        cancel,
+        callback_error,
+        metadata: None,
+    })
+}
+
+#[pyfunction]
+#[pyo3(signature = (repo_path, patterns=Vec::new(), show_progress=None, options=None, on_progress=None))]
+fn analyze_git_repo(
+    repo_path: String,
+    patterns: Vec<String>,
+    show_progress: Option<bool>,
+    options: Option<AnalysisOptions>,
+    on_progress: Option<PyObject>,
+    py: Python<'_>,
+) -> PyResult<PyObject> {
+    let options = AnalysisOptions::resolve(options, patterns, show_progress)?;
+    repo_output(py, repo_path, None, options, on_progress)
+}
+
+// analyze_git_repo on repo_path, or on `clone` when a RepoAnalyzer holds one
+fn repo_output(
+    py: Python<'_>,
+    repo_path: String,
+    clone: Option<&str>,
+    mut options: AnalysisOptions,
+    on_progress: Option<PyObject>,
+) -> PyResult<PyObject> {
+    let callback_error = attach_progress(&mut options, on_progress);
+
+    let mut analysis =
+        py.allow_threads(|| with_repo(&repo_path, clone, &options, |path| analyze_repo_internal(path, &options)))?;
+    if let Some(e) = callback_error.lock().take() {
+        return Err(e);
+    }
+    analysis.metadata.repo_path = remote::redacted_url(&repo_path);
+    repo_result(py, &options, analysis)
+}
+
+fn repo_result(py: Python<'_>, options: &AnalysisOptions, analysis: RepoAnalysis) -> PyResult<PyObject> {
+    let output = wrap_output(
+        py,
+        options,
+        "months",
+        match pivot::pivot(&analysis.months, analysis.sections.group_months.as_ref(), options.pivot) {
+            PivotedStats::Nested(stats) => stats.into_py(py),
+            PivotedStats::Grouped(stats) => stats.into_py(py),
+        },
+        &analysis.metadata,
+        &analysis.sections,
+    )?;
+    exceptions::check_partial(py, output, &analysis.metadata)
+}
+
+#[pyfunction]
+#[pyo3(signature = (repo_path, options=None, include_untracked=false, split_staged=false))]
+fn analyze_working_tree(
+    repo_path: String,
+    options: Option<AnalysisOptions>,
+    include_untracked: bool,
+    split_staged: bool,
+    py: Python<'_>,
+) -> PyResult<PyObject> {
+    let options = AnalysisOptions::resolve(options, Vec::new(), None)?;
+
+    let to_maps = |stats: HashMap<String, FileStats>| -> HashMap<String, HashMap<String, i32>> {
+        stats
+            .into_iter()
+            .map(|(ext, stats)| (ext, stats.to_map(false)))
+            .collect()
+    };
+    let data = if split_staged {
+        let breakdown = py
+            .allow_threads(|| worktree::working_tree_breakdown(&repo_path, &options, include_untracked))?;
+        let dict = PyDict::new(py);
+        dict.set_item("staged", to_maps(breakdown.staged))?;
+        dict.set_item("unstaged", to_maps(breakdown.unstaged))?;
+        dict.into_py(py)
+    } else {
+        let stats = py
+            .allow_threads(|| worktree::working_tree_stats(&repo_path, &options, include_untracked))?;
+        to_maps(stats).into_py(py)
+    };
+
+    let metadata = ScanMetadata {
+        repo_path,
+        ..Default::default()
+    };
+    wrap_output(py, &options, "stats", data, &metadata, &Sections::default())
+}
+
+#[pyfunction]
+#[pyo3(signature = (repo_path, options=None))]
+fn analyze_stashes(
+    repo_path: String,
+    options: Option<AnalysisOptions>,
+    py: Python<'_>,
+) -> PyResult<PyObject> {
+    let options = AnalysisOptions::resolve(options, Vec::new(), None)?;
+
+    let entries = py
+        .allow_threads(|| stash::stash_entries(&repo_path, &options))?;
+
+    let result = PyList::empty(py);
+    for entry in entries {
+        let entry_dict = PyDict::new(py);
+        entry_dict.set_item("index", entry.index)?;
+        entry_dict.set_item("commit", entry.oid.to_string())?;
+        entry_dict.set_item("message", entry.message)?;
+        entry_dict.set_item("timestamp", entry.timestamp)?;
+        entry_dict.set_item("author", entry.author)?;
+        let stats_dict: HashMap<String, HashMap<String, i32>> = entry
+            .stats
+            .into_iter()
+            .map(|(ext, stats)| (ext, stats.to_map(false)))
+            .collect();
+        entry_dict.set_item("stats", stats_dict)?;
+        result.append(entry_dict)?;
+    }
+
+    let metadata = ScanMetadata {
+        repo_path,
+        ..Default::default()
+    };
+    wrap_output(py, &options, "stashes", result.into_py(py), &metadata, &Sections::default())
+}
+
+// Compares two date windows, each a (since, until) pair of YYYY-MM-DD or
+// RFC 3339 dates, e.g. this quarter against last quarter
+#[pyfunction]
+#[pyo3(signature = (repo_path, window_a, window_b, options=None))]
+fn compare_windows(
+    repo_path: String,
+    window_a: (String, String),
+    window_b: (String, String),
+    options: Option<AnalysisOptions>,
+    py: Python<'_>,
+) -> PyResult<PyObject> {
+    let options = AnalysisOptions::resolve(options, Vec::new(), None)?;
+
+    let (comparison, mut metadata) = py
+        .allow_threads(|| {
+            remote::with_local_repo(&repo_path, &options, |path| {
+                compare::compare_windows(
+                    path,
+                    &options,
+                    (&window_a.0, &window_a.1),
+                    (&window_b.0, &window_b.1),
+                )
+            })
+        })?;
+    metadata.repo_path = remote::redacted_url(&repo_path);
+
+    let data = convert::to_py(py, &comparison)?;
+    wrap_output(py, &options, "comparison", data, &metadata, &Sections::default())
+}
+
+// Commit graph as DOT or GraphML text, with author, date and churn per node
+#[pyfunction]
+#[pyo3(signature = (repo_path, format="dot", simplify=false, options=None))]
+fn export_commit_graph(
+    repo_path: String,
+    format: &str,
+    simplify: bool,
+    options: Option<AnalysisOptions>,
+    py: Python<'_>,
+) -> PyResult<String> {
+    let options = AnalysisOptions::resolve(options, Vec::new(), None)?;
+    let format = graph::GraphFormat::parse(format)?;
+
+    py.allow_threads(|| remote::with_local_repo(&repo_path, &options, |path| graph::export(path, &options, format, simplify)))
+        .map_err(PyErr::from)
+}
+
+// Per-month merge frequency, branch lifetimes and concurrent branches
+#[pyfunction]
+#[pyo3(signature = (repo_path, options=None))]
+fn branching_metrics(
+    repo_path: String,
+    options: Option<AnalysisOptions>,
+    py: Python<'_>,
+) -> PyResult<PyObject> {
+    let options = AnalysisOptions::resolve(options, Vec::new(), None)?;
+
+    let (months, mut metadata) = py
+        .allow_threads(|| remote::with_local_repo(&repo_path, &options, |path| branching::branching_metrics(path, &options)))?;
+    metadata.repo_path = remote::redacted_url(&repo_path);
+
+    let data = convert::to_py(py, &months)?;
+    wrap_output(py, &options, "months", data, &metadata, &Sections::default())
+}
+
+// Kaplan-Meier survival of added lines per extension, author and month of
+// introduction, evaluated after each of `checkpoints_days`
+#[pyfunction]
+#[pyo3(signature = (repo_path, options=None, checkpoints_days=None))]
+fn code_survival(
+    repo_path: String,
+    options: Option<AnalysisOptions>,
+    checkpoints_days: Option<Vec<u32>>,
+    py: Python<'_>,
+) -> PyResult<PyObject> {
+    let options = AnalysisOptions::resolve(options, Vec::new(), None)?;
+    let checkpoints_days = checkpoints_days.unwrap_or_else(|| survival::DEFAULT_CHECKPOINTS_DAYS.to_vec());
+
+    let report = py
+        .allow_threads(|| {
+            remote::with_local_repo(&repo_path, &options, |path| survival::code_survival(path, &options, &checkpoints_days))
+        })?;
+
+    let metadata = ScanMetadata {
+        repo_path: remote::redacted_url(&repo_path),
+        ..Default::default()
+    };
+    let data = convert::to_py(py, &report)?;
+    wrap_output(py, &options, "survival", data, &metadata, &Sections::default())
+}
+
+// Objects reachable from the history at the end of each "month" or "year",
+// what each period added and its largest new blobs, plus the current size of
+// the object database on disk
+#[pyfunction]
+#[pyo3(signature = (repo_path, period="month", options=None))]
+fn storage_growth(
+    repo_path: String,
+    period: &str,
+    options: Option<AnalysisOptions>,
+    py: Python<'_>,
+) -> PyResult<PyObject> {
+    let options = AnalysisOptions::resolve(options, Vec::new(), None)?;
+    let period = storage::StoragePeriod::parse(period)?;
+
+    let (report, mut metadata) = py
+        .allow_threads(|| {
+            remote::with_local_repo(&repo_path, &options, |path| storage::storage_growth(path, &options, period))
+        })?;
+    metadata.repo_path = remote::redacted_url(&repo_path);
+
+    let data = convert::to_py(py, &report)?;
+    wrap_output(py, &options, "storage", data, &metadata, &Sections::default())
+}
+
+// Author x extension matrix of commits, additions, deletions or churn over
+// the options' since/until window, ready for a heatmap
+#[pyfunction]
+#[pyo3(signature = (repo_path, metric="additions", options=None))]
+fn author_extension_matrix(
+    repo_path: String,
+    metric: &str,
+    options: Option<AnalysisOptions>,
+    py: Python<'_>,
+) -> PyResult<PyObject> {
+    let options = AnalysisOptions::resolve(options, Vec::new(), None)?;
+    let metric = matrix::MatrixMetric::parse(metric)?;
+
+    let (matrix, mut metadata) = py
+        .allow_threads(|| {
+            remote::with_local_repo(&repo_path, &options, |path| matrix::author_extension_matrix(path, &options, metric))
+        })?;
+    metadata.repo_path = remote::redacted_url(&repo_path);
+
+    let data = convert::to_py(py, &matrix)?;
+    wrap_output(py, &options, "matrix", data, &metadata, &Sections::default())
+}
+
+// Analyzes every git repository found under `root`, descending at most
+// `max_depth` directories and skipping paths excluded by .gitignore/.ignore
+// files. Returns per-repository results plus the months merged across them,
+// where "repos" counts the repositories contributing to each extension.
+#[pyfunction]
+#[pyo3(signature = (root, max_depth=None, options=None))]
+fn scan_directory(
+    root: String,
+    max_depth: Option<usize>,
+    options: Option<AnalysisOptions>,
+    py: Python<'_>,
+) -> PyResult<PyObject> {
+    let options = AnalysisOptions::resolve(options, Vec::new(), None)?;
+    let max_depth = max_depth.unwrap_or(directory::DEFAULT_MAX_DEPTH);
+
+    let scan = py
+        .allow_threads(|| directory::scan_directory(&root, &options, max_depth))?;
+
+    let repos = PyDict::new(py);
+    for (name, analysis) in scan.repos {
+        let months = convert_to_python_format(&analysis.months).into_py(py);
+        repos.set_item(name, wrap_output(py, &options, "months", months, &analysis.metadata, &analysis.sections)?)?;
+    }
+    let data = PyDict::new(py);
+    data.set_item("merged", convert_to_python_format(&scan.merged))?;
+    data.set_item("repos", repos)?;
+    data.set_item("errors", scan.errors)?;
+    wrap_output(py, &options, "results", data.into_py(py), &scan.metadata, &Sections::default())
+}
+
+// Scans a manifest of local paths and remote URLs, each a string or a
+// {"repo": ..., "label": ...} dict, on `workers` threads (default: the
+// options' max_parallel_repos, else 4). `on_complete` is
+// called with an event dict as each repository finishes; the result holds
+// per-repository output and errors keyed by label plus the merged months.
+#[pyfunction]
+#[pyo3(signature = (manifest, workers=None, on_complete=None, options=None))]
+fn scan_fleet(
+    manifest: &PyAny,
+    workers: Option<usize>,
+    on_complete: Option<PyObject>,
+    options: Option<AnalysisOptions>,
+    py: Python<'_>,
+) -> PyResult<PyObject> {
+    let options = AnalysisOptions::resolve(options, Vec::new(), None)?;
+    let entries = fleet::parse_manifest(convert::from_py(manifest)?)?;
+    let workers = fleet::worker_count(workers, &options);
+
+    let repos = PyDict::new(py);
+    let errors = PyDict::new(py);
+    let mut merged = MonthlyStats::new();
+    let mut metadata = ScanMetadata::default();
+    let mut callback_error = None;
+    let mut event_error = None;
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    // Receiver is not Sync, which allow_threads needs to borrow it
+    let receiver = Mutex::new(receiver);
+    let scan = std::thread::scope(|scope| {
+        let worker = scope.spawn(|| fleet::scan_fleet(&entries, &options, workers, sender));
+        let mut completed = 0;
+        // Events are handled on this thread, releasing the GIL while waiting.
+        // Nothing may return early from here: the scope joins the worker on
+        // exit, which would block with the GIL held while the worker's
+        // threads wait on it. The first error is kept and raised afterwards.
+        while let Ok(event) = py.allow_threads(|| receiver.lock().recv()) {
+            completed += 1;
+            if event_error.is_some() {
+                continue;
+            }
+            let handled = (|| -> PyResult<()> {
+                let entry = &entries[event.index];
+                let info = PyDict::new(py);
+                info.set_item("label", &entry.label)?;
+                info.set_item("repo", remote::redacted_url(&entry.repo))?;
+                info.set_item("index", event.index)?;
+                info.set_item("completed", completed)?;
+                info.set_item("total", entries.len())?;
+                info.set_item("elapsed_seconds", event.elapsed_seconds)?;
+                match event.result {
+                    Ok(analysis) => {
+                        metadata.absorb(&analysis.metadata);
+                        directory::merge_months(&mut merged, &analysis.months);
+                        let months = convert_to_python_format(&analysis.months).into_py(py);
+                        let output = wrap_output(py, &options, "months", months, &analysis.metadata, &analysis.sections)?;
+                        repos.set_item(&entry.label, output)?;
+                        info.set_item("ok", true)?;
+                        info.set_item("error", py.None())?;
+                    }
+                    Err(e) => {
+                        let message = entry.error_message(&e);
+                        errors.set_item(&entry.label, &message)?;
+                        info.set_item("ok", false)?;
+                        info.set_item("error", message)?;
+                    }
+                }
+                // A failing callback is re-raised once every scan has finished
+                if let (Some(callback), None) = (&on_complete, &callback_error) {
+                    if let Err(e) = callback.call1(py, (info,)) {
+                        callback_error = Some(e);
+                    }
+                }
+                Ok(())
+            })();
+            if let Err(e) = handled {
+                event_error = Some(e);
+            }
+        }
+        py.allow_threads(|| worker.join())
+    });
+    match scan {
+        Ok(result) => result?,
+        Err(_) => return Err(RepoScanError::new_err("fleet worker pool panicked")),
+    }
+    if let Some(e) = event_error.or(callback_error) {
+        return Err(e);
+    }
+
+    let data = PyDict::new(py);
+    data.set_item("merged", convert_to_python_format(&merged))?;
+    data.set_item("repos", repos)?;
+    data.set_item("errors", errors)?;
+    wrap_output(py, &options, "results", data.into_py(py), &metadata, &Sections::default())
+}
+
+// Routes commit-walk progress to `on_progress(done, total)`. After the first
+// exception the callback is no longer called; the error is handed back for the
+// wrapper to raise once the scan returns.
+fn attach_progress(options: &mut AnalysisOptions, on_progress: Option<PyObject>) -> Arc<Mutex<Option<PyErr>>> {
+    let error = Arc::new(Mutex::new(None));
+    if let Some(callback) = on_progress {
+        let callback_error = Arc::clone(&error);
+        options.compiled.progress_callback = Some(ProgressCallback::new(move |done, total| {
+            Python::with_gil(|py| {
+                let mut error = callback_error.lock();
+                if error.is_none() {
+                    if let Err(e) = callback.call1(py, (done, total)) {
+                        *error = Some(e);
+                    }
+                }
+            })
+        }));
+    }
+    error
+}
+
+// Returns the bare result for the nested format, or an envelope carrying the
+// scan metadata and any enabled report sections alongside it.
+fn wrap_output(
+    py: Python<'_>,
+    options: &AnalysisOptions,
+    key: &str,
+    data: PyObject,
+    metadata: &ScanMetadata,
+    sections: &Sections,
+) -> PyResult<PyObject> {
+    match options.output_format {
+        OutputFormat::Nested => Ok(data),
+        OutputFormat::Envelope => {
+            let envelope: &PyDict = convert::to_py(py, sections)?.into_ref(py).downcast()?;
+            envelope.set_item(key, data)?;
+            envelope.set_item("metadata", convert::to_py(py, metadata)?)?;
+            Ok(envelope.into_py(py))
+        }
+    }
+}
+
+// Combines results of analyze_git_repo (from different repositories, shards
+// or incremental runs) into one: counters are summed and "repos" counts
+// distinct repositories. Envelope results give an envelope with the months
+// and summed metadata.
+#[pyfunction]
+fn merge_results(results: &PyList, py: Python<'_>) -> PyResult<PyObject> {
+    let results = results.iter().map(convert::py_to_json).collect::<PyResult<Vec<_>>>()?;
+    let merged = merge::merge_results(&results)?;
+    Ok(convert::json_to_py(py, &merged))
+}
+
+// Reports what changed from `old` to `new`, two stored analyze_git_repo
+// results: months added or removed, counters that differ and, when both are
+// envelopes with a "contributors" section, authors not seen before
+#[pyfunction]
+fn diff_results(old: &PyAny, new: &PyAny, py: Python<'_>) -> PyResult<PyObject> {
+    let changes = changes::diff_results(&convert::py_to_json(old)?, &convert::py_to_json(new)?)?;
+    convert::to_py(py, &changes)
+}
+
+/// Analyses of one repository that share its setup.
+///
+/// With clone_remote the repository is cloned once, when the analyzer is
+/// created, and every analysis reads that clone. `close()`, or leaving a
+/// `with` block, deletes the clone; the analyzer cannot be used afterwards.
+#[pyclass(module = "repo_scan_rs")]
+struct RepoAnalyzer {
+    repo_path: String,
+    options: AnalysisOptions,
+    clone: Option<remote::TempClone>,
+    closed: bool,
+}
+
+impl RepoAnalyzer {
+    fn check_open(&self) -> PyResult<()> {
+        if self.closed {
+            return Err(RepoScanError::new_err("RepoAnalyzer is closed"));
+        }
+        Ok(())
+    }
+
+    fn clone_path(&self) -> Option<String> {
+        self.clone.as_ref().map(|clone| clone.path().to_string_lossy().into_owned())
+    }
+}
+
+#[pymethods]
+impl RepoAnalyzer {
+    #[new]
+    #[pyo3(signature = (repo_path, options=None))]
+    fn new(repo_path: String, options: Option<AnalysisOptions>, py: Python<'_>) -> PyResult<Self> {
+        let options = AnalysisOptions::resolve(options, Vec::new(), None)?;
+        let clone = if options.clone_remote {
+            Some(py.allow_threads(|| remote::clone_remote(&repo_path, &options))?)
+        } else {
+            None
+        };
+        Ok(RepoAnalyzer {
+            repo_path,
+            options,
+            clone,
+            closed: false,
+        })
+    }
+
+    #[pyo3(signature = (on_progress=None))]
+    fn analyze(&self, on_progress: Option<PyObject>, py: Python<'_>) -> PyResult<PyObject> {
+        self.check_open()?;
+        let clone = self.clone_path();
+        repo_output(py, self.repo_path.clone(), clone.as_deref(), self.options.clone(), on_progress)
+    }
+
+    #[pyo3(signature = (on_progress=None))]
+    fn analyze_commits(&self, on_progress: Option<PyObject>, py: Python<'_>) -> PyResult<PyObject> {
+        self.check_open()?;
+        let clone = self.clone_path();
+        commits_output(py, self.repo_path.clone(), clone.as_deref(), self.options.clone(), on_progress)
+    }
+
+    // Deletes the clone, if any; closing again does nothing
+    fn close(&mut self) {
+        self.clone = None;
+        self.closed = true;
+    }
+
+    #[getter]
+    fn closed(&self) -> bool {
+        self.closed
+    }
+
+    fn __enter__(slf: PyRef<'_, Self>) -> PyResult<PyRef<'_, Self>> {
+        slf.check_open()?;
+        Ok(slf)
+    }
+
+    // Exceptions raised in the block propagate
+    fn __exit__(&mut self, _exc_type: &PyAny, _exc_value: &PyAny, _traceback: &PyAny) -> bool {
+        self.close();
+        false
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "RepoAnalyzer({:?}{})",
+            self.repo_path,
+            if self.closed { ", closed" } else { "" }
+        )
+    }
+}
+
+// Where a started analysis is
+enum JobOutcome {
+    Running,
+    Finished(Box<Result<RepoAnalysis, AnalyzerError>>),
+    // Converted by the first result() call and handed out again after that
+    Converted(PyResult<PyObject>),
+}
+
+struct JobState {
+    // (commits done, commits total), as passed to on_progress
+    progress: Mutex<(u64, Option<u64>)>,
+    outcome: Mutex<JobOutcome>,
+    finished: Condvar,
+}
+
+/// An analyze_git_repo scan running on a background thread, returned by
+/// `start_analysis`. Dropping the job cancels the scan.
+#[pyclass(module = "repo_scan_rs")]
+struct AnalysisJob {
+    repo_path: String,
+    options: AnalysisOptions,
+    cancel: Arc<AtomicBool>,
+    state: Arc<JobState>,
+}
+
+#[pymethods]
+impl AnalysisJob {
+    // (commits done, commits total); the total is None until the walk is over
+    fn progress(&self) -> (u64, Option<u64>) {
+        *self.state.progress.lock()
+    }
+
+    fn done(&self) -> bool {
+        !matches!(*self.state.outcome.lock(), JobOutcome::Running)
+    }
+
+    // Asks the scan to stop at the next commit; result() then raises
+    // Cancelled, or PartialResultError with partial_results
+    fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    // Waits up to `timeout` seconds, or for as long as it takes when None,
+    // and raises TimeoutError if the scan is still running
+    #[pyo3(signature = (timeout=None))]
+    fn result(&self, timeout: Option<f64>, py: Python<'_>) -> PyResult<PyObject> {
+        let deadline = timeout.and_then(|t| {
+            let wait = Duration::try_from_secs_f64(t.max(0.0)).ok()?;
+            Instant::now().checked_add(wait)
+        });
+        let state = Arc::clone(&self.state);
+        let finished = py.allow_threads(|| {
+            let mut outcome = state.outcome.lock();
+            while matches!(*outcome, JobOutcome::Running) {
+                match deadline {
+                    Some(deadline) => {
+                        if state.finished.wait_until(&mut outcome, deadline).timed_out() {
+                            break;
+                        }
+                    }
+                    None => state.finished.wait(&mut outcome),
+                }
+            }
+            !matches!(*outcome, JobOutcome::Running)
+        });
+        if !finished {
+            return Err(PyTimeoutError::new_err("the analysis is still running"));
+        }
+
+        let mut outcome = self.state.outcome.lock();
+        let converted = match std::mem::replace(&mut *outcome, JobOutcome::Running) {
+            JobOutcome::Finished(finished) => (*finished).map_err(PyErr::from).and_then(|mut analysis| {
+                analysis.metadata.repo_path = remote::redacted_url(&self.repo_path);
+                repo_result(py, &self.options, analysis)
+            }),
+            JobOutcome::Converted(converted) => converted,
+            JobOutcome::Running => unreachable!("checked above"),
+        };
+        let output = match &converted {
+            Ok(output) => Ok(output.clone_ref(py)),
+            Err(e) => Err(e.clone_ref(py)),
+        };
+        *outcome = JobOutcome::Converted(converted);
+        output
+    }
+
+    fn __repr__(&self) -> String {
+        let (done, _) = self.progress();
+        let status = if self.done() { "done" } else { "running" };
+        format!("AnalysisJob({:?}, {}, {} commits)", self.repo_path, status, done)
+    }
+}
+
+impl Drop for AnalysisJob {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+// Starts analyze_git_repo on a background thread and returns at once. The job
+// reports progress, can be cancelled and hands over the usual result.
+#[pyfunction]
+#[pyo3(signature = (repo_path, options=None))]
+fn start_analysis(repo_path: String, options: Option<AnalysisOptions>) -> PyResult<AnalysisJob> {
+    let mut options = AnalysisOptions::resolve(options, Vec::new(), None)?;
+    let cancel = Arc::new(AtomicBool::new(false));
+    let state = Arc::new(JobState {
+        progress: Mutex::new((0, None)),
+        outcome: Mutex::new(JobOutcome::Running),
+        finished: Condvar::new(),
+    });
+
+    let progress_state = Arc::clone(&state);
+    options.compiled.progress_callback = Some(ProgressCallback::new(move |done, total| {
+        *progress_state.progress.lock() = (done, total);
+    }));
+    options.compiled.cancel = Some(Arc::clone(&cancel));
+
+    let worker_state = Arc::clone(&state);
+    let worker_options = options.clone();
+    let worker_path = repo_path.clone();
+    std::thread::Builder::new()
+        .name("repo-scan-job".to_string())
+        .spawn(move || {
+            let result = remote::with_local_repo(&worker_path, &worker_options, |path| {
+                analyze_repo_internal(path, &worker_options)
+            });
+            *worker_state.outcome.lock() = JobOutcome::Finished(Box::new(result));
+            worker_state.finished.notify_all();
+        })
+        .map_err(|e| RepoScanError::new_err(format!("cannot start the analysis thread: {}", e)))?;
+
+    Ok(AnalysisJob {
+        repo_path,
+        options,
+        cancel,
+        state,
+    })
+}
+
+// Runs `f` on an analyzer's clone if there is one, otherwise on repo_path,
+// cloning it for the call when clone_remote is set
+fn with_repo<T>(
+    repo_path: &str,
+    clone: Option<&str>,
+    options: &AnalysisOptions,
+    f: impl FnOnce(&str) -> Result<T, AnalyzerError>,
+) -> Result<T, AnalyzerError> {
+    match clone {
+        Some(path) => f(path),
+        None => remote::with_local_repo(repo_path, options, f),
+    }
+}
+
+#[pymodule]
+fn repo_scan_rs(py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    logging::install(py)?;
+    exceptions::register(py, m)?;
+    m.add_function(wrap_pyfunction!(analyze_git_repo, m)?)?;
+    m.add_function(wrap_pyfunction!(analyze_git_commits, m)?)?;
+    m.add_function(wrap_pyfunction!(iter_git_commits, m)?)?;
+    m.add_function(wrap_pyfunction!(analyze_working_tree, m)?)?;
+    m.add_function(wrap_pyfunction!(analyze_stashes, m)?)?;
+    m.add_function(wrap_pyfunction!(compare_windows, m)?)?;
+    m.add_function(wrap_pyfunction!(export_commit_graph, m)?)?;
+    m.add_function(wrap_pyfunction!(branching_metrics, m)?)?;
+    m.add_function(wrap_pyfunction!(code_survival, m)?)?;
+    m.add_function(wrap_pyfunction!(storage_growth, m)?)?;
+    m.add_function(wrap_pyfunction!(author_extension_matrix, m)?)?;
+    m.add_function(wrap_pyfunction!(scan_directory, m)?)?;
+    m.add_function(wrap_pyfunction!(scan_fleet, m)?)?;
+    m.add_function(wrap_pyfunction!(merge_results, m)?)?;
+    m.add_function(wrap_pyfunction!(diff_results, m)?)?;
+    m.add_function(wrap_pyfunction!(start_analysis, m)?)?;
+    m.add_class::<AnalysisOptions>()?;
+    m.add_class::<RepoAnalyzer>()?;
+    m.add_class::<AnalysisJob>()?;
+    m.add_class::<CommitChunks>()?;
+    Ok(())
+}
+
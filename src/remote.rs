@@ -0,0 +1,228 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use git2::build::RepoBuilder;
+use git2::{Cred, CredentialType, FetchOptions, Oid, RemoteCallbacks, Repository};
+use tracing::info;
+
+use crate::options::AnalysisOptions;
+use crate::AnalyzerError;
+
+// Initial depth when deepening a clone until it reaches clone_since
+const SHALLOW_SINCE_START_DEPTH: i32 = 64;
+
+// Names tried before giving up on creating a clone directory
+const TEMP_DIR_ATTEMPTS: usize = 16;
+
+// Environment fallbacks for the credential options, for headless scans
+pub const ENV_HTTPS_USERNAME: &str = "REPO_SCAN_HTTPS_USERNAME";
+pub const ENV_HTTPS_TOKEN: &str = "REPO_SCAN_HTTPS_TOKEN";
+pub const ENV_SSH_KEY: &str = "REPO_SCAN_SSH_KEY";
+pub const ENV_SSH_PASSPHRASE: &str = "REPO_SCAN_SSH_PASSPHRASE";
+
+// Username sent with a token when none is configured; GitHub, GitLab and
+// Bitbucket all accept a token with an arbitrary user name
+const DEFAULT_TOKEN_USERNAME: &str = "x-access-token";
+
+// Credentials resolved from the options, falling back to the environment
+#[derive(Debug, Clone, Default)]
+struct Credentials {
+    use_ssh_agent: bool,
+    ssh_key: Option<PathBuf>,
+    ssh_passphrase: Option<String>,
+    https_username: Option<String>,
+    https_token: Option<String>,
+}
+
+impl Credentials {
+    fn resolve(options: &AnalysisOptions) -> Self {
+        let env = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+        Credentials {
+            use_ssh_agent: options.ssh_use_agent,
+            ssh_key: options.ssh_key_path.clone().or_else(|| env(ENV_SSH_KEY)).map(PathBuf::from),
+            ssh_passphrase: options.ssh_passphrase.clone().or_else(|| env(ENV_SSH_PASSPHRASE)),
+            https_username: options.https_username.clone().or_else(|| env(ENV_HTTPS_USERNAME)),
+            https_token: options.https_token.clone().or_else(|| env(ENV_HTTPS_TOKEN)),
+        }
+    }
+
+    // libgit2 calls back until a credential works, so each method is offered
+    // once, in order: SSH agent, SSH key file, token, then the system default
+    fn callbacks<'cb>(self) -> RemoteCallbacks<'cb> {
+        let mut tried_agent = false;
+        let mut tried_key = false;
+        let mut tried_token = false;
+        let mut tried_default = false;
+        let mut callbacks = RemoteCallbacks::new();
+        callbacks.credentials(move |_url, username_from_url, allowed| {
+            let ssh_user = username_from_url.unwrap_or("git");
+            if allowed.contains(CredentialType::SSH_KEY) {
+                if self.use_ssh_agent && !tried_agent {
+                    tried_agent = true;
+                    return Cred::ssh_key_from_agent(ssh_user);
+                }
+                if let (Some(key), false) = (&self.ssh_key, tried_key) {
+                    tried_key = true;
+                    return Cred::ssh_key(ssh_user, None, key, self.ssh_passphrase.as_deref());
+                }
+            }
+            if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) {
+                if let (Some(token), false) = (&self.https_token, tried_token) {
+                    tried_token = true;
+                    let username = self
+                        .https_username
+                        .as_deref()
+                        .or(username_from_url)
+                        .unwrap_or(DEFAULT_TOKEN_USERNAME);
+                    return Cred::userpass_plaintext(username, token);
+                }
+            }
+            if allowed.contains(CredentialType::DEFAULT) && !tried_default {
+                tried_default = true;
+                return Cred::default();
+            }
+            Err(git2::Error::from_str("no credentials left to try for the remote"))
+        });
+        callbacks
+    }
+}
+
+pub fn is_remote_url(value: &str) -> bool {
+    ["https://", "http://", "ssh://", "git://", "file://"]
+        .iter()
+        .any(|scheme| value.starts_with(scheme))
+        // scp-like syntax, e.g. git@github.com:org/repo.git
+        || value.split_once(':').is_some_and(|(host, _)| host.contains('@') && !host.contains('/'))
+}
+
+// The URL as it may be logged or shown: user names, passwords and tokens in
+// the userinfo are dropped, as is any query, leaving the host and path
+pub fn redacted_url(url: &str) -> String {
+    match url.split_once("://") {
+        Some((scheme, rest)) => {
+            let rest = rest.split(['?', '#']).next().unwrap_or_default();
+            let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+            let host = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+            format!("{}://{}{}", scheme, host, path)
+        }
+        // scp-like syntax: the user name goes, host:path stays
+        None if is_remote_url(url) => url.split_once('@').map_or(url, |(_, rest)| rest).to_string(),
+        None => url.to_string(),
+    }
+}
+
+// A clone in the temporary directory, deleted again when dropped
+pub struct TempClone {
+    path: PathBuf,
+}
+
+impl TempClone {
+    // Creates a fresh directory under a random name, readable only by this
+    // user. The directory must not exist yet, so a guard never owns (and
+    // deletes) one made by someone else, e.g. planted in a shared /tmp.
+    fn create() -> Result<Self, AnalyzerError> {
+        for _ in 0..TEMP_DIR_ATTEMPTS {
+            let suffix = RandomState::new().build_hasher().finish();
+            let path = std::env::temp_dir().join(format!("repo-scan-{}-{:016x}", std::process::id(), suffix));
+            let mut builder = std::fs::DirBuilder::new();
+            #[cfg(unix)]
+            std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+            match builder.create(&path) {
+                Ok(()) => return Ok(TempClone { path }),
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+                Err(e) => {
+                    return Err(AnalyzerError::InvalidOption(format!(
+                        "cannot create a clone directory in {}: {}",
+                        std::env::temp_dir().display(),
+                        e
+                    )))
+                }
+            }
+        }
+        Err(AnalyzerError::InvalidOption(format!(
+            "no free clone directory name in {}",
+            std::env::temp_dir().display()
+        )))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempClone {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+// Runs `f` on `repo_path`, or on a temporary clone of it when clone_remote is
+// set. The clone is removed afterwards whether or not `f` succeeded.
+pub fn with_local_repo<T>(
+    repo_path: &str,
+    options: &AnalysisOptions,
+    f: impl FnOnce(&str) -> Result<T, AnalyzerError>,
+) -> Result<T, AnalyzerError> {
+    if !options.clone_remote {
+        return f(repo_path);
+    }
+    let clone = clone_remote(repo_path, options)?;
+    f(&clone.path().to_string_lossy())
+}
+
+pub fn clone_remote(url: &str, options: &AnalysisOptions) -> Result<TempClone, AnalyzerError> {
+    if !is_remote_url(url) {
+        return Err(AnalyzerError::InvalidOption(format!(
+            "clone_remote expects an https://, ssh:// or git@host: URL, got {:?}",
+            redacted_url(url)
+        )));
+    }
+
+    let depth = options
+        .clone_depth
+        .map(|d| d as i32)
+        .or(options.compiled.clone_since.map(|_| SHALLOW_SINCE_START_DEPTH));
+
+    let clone = TempClone::create()?;
+    info!(url = redacted_url(url), depth, "cloning remote repository");
+    let credentials = Credentials::resolve(options);
+    let mut fetch_opts = fetch_options(&credentials, depth);
+    let repo = RepoBuilder::new()
+        .bare(options.clone_bare)
+        .fetch_options(fetch_opts)
+        .clone(url, clone.path())?;
+
+    // libgit2 has no --shallow-since, so the clone is deepened until its
+    // shallow boundary is older than the date or the full history is present
+    if let (Some(since), Some(mut depth)) = (options.compiled.clone_since, depth) {
+        while repo.is_shallow() && boundary_newer_than(&repo, since)? {
+            depth = depth.saturating_mul(2);
+            fetch_opts = fetch_options(&credentials, Some(depth));
+            repo.find_remote("origin")?.fetch(&[] as &[&str], Some(&mut fetch_opts), None)?;
+        }
+    }
+    Ok(clone)
+}
+
+fn fetch_options<'cb>(credentials: &Credentials, depth: Option<i32>) -> FetchOptions<'cb> {
+    let mut fetch_opts = FetchOptions::new();
+    fetch_opts.remote_callbacks(credentials.clone().callbacks());
+    if let Some(depth) = depth {
+        fetch_opts.depth(depth);
+    }
+    fetch_opts
+}
+
+// Whether any commit on the shallow boundary was made at or after `since`
+fn boundary_newer_than(repo: &Repository, since: i64) -> Result<bool, AnalyzerError> {
+    let shallow = std::fs::read_to_string(repo.path().join("shallow")).unwrap_or_default();
+    for line in shallow.lines() {
+        let commit = repo.find_commit(Oid::from_str(line.trim())?)?;
+        if commit.time().seconds() >= since {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
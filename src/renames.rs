@@ -0,0 +1,53 @@
+use std::collections::BTreeMap;
+
+use git2::{Commit, Delta};
+use serde::Serialize;
+
+use crate::diff::FileDelta;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Rename {
+    pub commit: String,
+    pub timestamp: i64,
+    pub old_path: String,
+    pub new_path: String,
+}
+
+// Rename chains of tracked files, so metrics recorded under old paths can be
+// mapped onto the paths they live under now
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct RenameLineage {
+    // Every rename seen, oldest first
+    pub renames: Vec<Rename>,
+    // Latest path -> the renames leading to it, oldest first
+    pub chains: BTreeMap<String, Vec<Rename>>,
+}
+
+impl RenameLineage {
+    pub fn observe(&mut self, commit: &Commit, deltas: &[FileDelta]) {
+        for delta in deltas.iter().filter(|d| d.status == Delta::Renamed) {
+            let Some(old_path) = &delta.old_path else {
+                continue;
+            };
+            self.renames.push(Rename {
+                commit: commit.id().to_string(),
+                timestamp: commit.author().when().seconds(),
+                old_path: old_path.clone(),
+                new_path: delta.path.clone(),
+            });
+        }
+    }
+
+    pub fn finish(&mut self) {
+        // The walk runs newest first; chains are built forward in time
+        self.renames.reverse();
+        self.renames.sort_by_key(|r| r.timestamp);
+        let mut chains: BTreeMap<String, Vec<Rename>> = BTreeMap::new();
+        for rename in &self.renames {
+            let mut chain = chains.remove(&rename.old_path).unwrap_or_default();
+            chain.push(rename.clone());
+            chains.insert(rename.new_path.clone(), chain);
+        }
+        self.chains = chains;
+    }
+}
@@ -0,0 +1,550 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use serde::Serialize;
+
+use git2::{Commit, Oid, Repository};
+use ignore::gitignore::Gitignore;
+
+use crate::binaries::LargeBinaryReport;
+use crate::churn::ChurnMonth;
+use crate::ci::CiTimeline;
+use crate::collaboration::CollaborationNetwork;
+use crate::contributors::{ContributorGrowth, ContributorReport, NewContributors};
+use crate::renames::RenameLineage;
+use crate::classify::{self, DocsMonth};
+use crate::decay::DecayedChurn;
+use crate::dependencies::{DependencyMonth, DependencyTracker};
+use crate::diff::{FileDelta, ObservedDiff};
+use crate::duplicates::DuplicateAuthors;
+use crate::effort::EffortEstimate;
+use crate::anomalies::{self, Anomaly, AnomalyMethod};
+use crate::authors::{self, AuthorField};
+use crate::license::LicenseReport;
+#[cfg(feature = "functions")]
+use crate::functions::FunctionChurn;
+use crate::gitmoji::{GitmojiClassifier, GitmojiMonth};
+use crate::history::Replacements;
+use crate::hours::HoursReport;
+use crate::hygiene::HygieneReport;
+use crate::imports::ImportReport;
+use crate::latency::LatencyReport;
+use crate::markers::MarkersMonth;
+use crate::merges::{SelfMergeReport, SquashDetector, SquashMonth};
+use crate::messages::{self, MessageMonth};
+use crate::nesting::NestingMonth;
+use crate::options::{AnalysisOptions, Pivot};
+use crate::ownership::OwnershipTimeline;
+use crate::reviews::ReviewReport;
+use crate::rolling::RollingWindow;
+use crate::secrets::SecretsReport;
+use crate::paths::PathFilter;
+use crate::public_api::{self, ApiMonth};
+use crate::series::{self, Diversity, MonthlySeries, Share};
+use crate::sizes::SizeMonth;
+use crate::snapshot::{FileCountSnapshot, FileLengthSnapshot};
+use crate::timezones::TimezoneReport;
+use crate::touched::TouchedMonth;
+use crate::{AnalyzerError, MonthlyStats};
+
+// Optional report sections of the envelope output, keyed by section name.
+// Each is `None` unless the option enabling it was set.
+#[derive(Default, Serialize)]
+pub struct Sections {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub docs: Option<BTreeMap<String, DocsMonth>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub markers: Option<BTreeMap<String, MarkersMonth>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dependencies: Option<BTreeMap<String, DependencyMonth>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ci: Option<CiTimeline>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub licenses: Option<LicenseReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secrets: Option<SecretsReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub large_binaries: Option<LargeBinaryReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cumulative_lines: Option<MonthlySeries<i64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language_share: Option<MonthlySeries<Share>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language_diversity: Option<BTreeMap<String, Diversity>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub churn_metrics: Option<BTreeMap<String, ChurnMonth>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duplicate_authors: Option<DuplicateAuthors>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_metrics: Option<BTreeMap<String, MessageMonth>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gitmoji: Option<BTreeMap<String, GitmojiMonth>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub anomalies: Option<Vec<Anomaly>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub collaboration: Option<CollaborationNetwork>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ownership: Option<OwnershipTimeline>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub effort: Option<EffortEstimate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contributors: Option<ContributorReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_contributors: Option<NewContributors>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contributor_growth: Option<ContributorGrowth>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub renames: Option<RenameLineage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timezones: Option<TimezoneReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub out_of_hours: Option<HoursReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit_latency: Option<LatencyReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub files_touched: Option<BTreeMap<String, TouchedMonth>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit_size: Option<BTreeMap<String, SizeMonth>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reviews: Option<ReviewReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub self_merges: Option<SelfMergeReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub squash_merges: Option<BTreeMap<String, SquashMonth>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_hygiene: Option<HygieneReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nesting_depth: Option<BTreeMap<String, NestingMonth>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_api: Option<BTreeMap<String, ApiMonth>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub imports: Option<ImportReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rolling: Option<RollingWindow>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decayed_churn: Option<DecayedChurn>,
+    #[cfg(feature = "functions")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function_churn: Option<FunctionChurn>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_counts: Option<FileCountSnapshot>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_lengths: Option<FileLengthSnapshot>,
+    #[serde(skip)]
+    marker_regex: Option<regex::Regex>,
+    #[serde(skip)]
+    ci_matcher: Option<Gitignore>,
+    #[serde(skip)]
+    secret_rules: Vec<(String, regex::Regex)>,
+    #[serde(skip)]
+    dependency_tracker: Option<DependencyTracker>,
+    #[serde(skip)]
+    large_binary_threshold: u64,
+    #[serde(skip)]
+    normalize_authors: bool,
+    #[serde(skip)]
+    gitmoji_classifier: Option<GitmojiClassifier>,
+    #[serde(skip)]
+    squash_detector: Option<SquashDetector>,
+    #[serde(skip)]
+    anomaly_method: (AnomalyMethod, f64),
+    #[serde(skip)]
+    month_authors: BTreeMap<String, BTreeSet<String>>,
+    #[serde(skip)]
+    dormancy_months: u32,
+    #[serde(skip)]
+    indent_width: u32,
+    #[serde(skip)]
+    pub_item_regex: Option<regex::Regex>,
+    // author, pattern label or group -> month -> extension, for the "author"
+    // and "pattern" pivots and for groups
+    #[serde(skip)]
+    pub group_months: Option<HashMap<String, MonthlyStats>>,
+}
+
+impl Sections {
+    pub fn new(options: &AnalysisOptions) -> Self {
+        Sections {
+            docs: options.docs_churn.then(BTreeMap::new),
+            markers: options.compiled.marker_regex.as_ref().map(|_| BTreeMap::new()),
+            dependencies: options.track_dependencies.then(BTreeMap::new),
+            ci: options.compiled.ci_matcher.as_ref().map(|_| CiTimeline::default()),
+            licenses: options.track_licenses.then(LicenseReport::default),
+            secrets: options.scan_secrets.then(SecretsReport::default),
+            large_binaries: options.track_large_binaries.then(LargeBinaryReport::default),
+            cumulative_lines: options.cumulative_lines.then(BTreeMap::new),
+            language_share: options.language_share.then(BTreeMap::new),
+            language_diversity: options.language_diversity.then(BTreeMap::new),
+            churn_metrics: options.churn_metrics.then(BTreeMap::new),
+            duplicate_authors: options.detect_duplicate_authors.then(DuplicateAuthors::default),
+            message_metrics: options.message_metrics.then(BTreeMap::new),
+            gitmoji: options.compiled.gitmoji.as_ref().map(|_| BTreeMap::new()),
+            anomalies: options.detect_anomalies.then(Vec::new),
+            collaboration: options
+                .collaboration_network
+                .then(|| CollaborationNetwork::new(options.collaboration_window_days)),
+            ownership: options.ownership_timeline.then(OwnershipTimeline::default),
+            contributors: options.contributor_report.then(ContributorReport::default),
+            new_contributors: options.new_contributors.then(NewContributors::default),
+            contributor_growth: options.contributor_growth.then(ContributorGrowth::default),
+            renames: options.rename_lineage.then(RenameLineage::default),
+            timezones: options.timezone_distribution.then(TimezoneReport::default),
+            out_of_hours: options.out_of_hours.then(|| HoursReport::new(options.working_hours)),
+            commit_latency: options.commit_latency.then(|| LatencyReport::new(options.latency_threshold_hours)),
+            files_touched: options.files_touched.then(BTreeMap::new),
+            commit_size: options.commit_size.then(BTreeMap::new),
+            reviews: options.review_trailers.then(|| ReviewReport::new(options.normalize_authors)),
+            self_merges: options.self_merges.then(|| SelfMergeReport::new(options.normalize_authors)),
+            squash_merges: options.compiled.squash.as_ref().map(|_| BTreeMap::new()),
+            message_hygiene: options.message_hygiene.then(|| HygieneReport::new(options.hygiene_weights)),
+            nesting_depth: options.nesting_depth.then(BTreeMap::new),
+            public_api: options.public_api.then(BTreeMap::new),
+            imports: options.import_churn.then(ImportReport::default),
+            rolling: options.rolling_window_days.map(RollingWindow::new),
+            decayed_churn: options.decayed_churn.then(|| DecayedChurn::new(options.churn_half_life_days)),
+            #[cfg(feature = "functions")]
+            function_churn: options.function_churn.then(FunctionChurn::default),
+            file_counts: options.file_counts.then(FileCountSnapshot::default),
+            file_lengths: options.file_lengths.then(|| FileLengthSnapshot::new(options.long_file_lines)),
+            effort: options.estimate_effort.then(|| {
+                let coefficients = options
+                    .cocomo_coefficients
+                    .unwrap_or_else(|| options.cocomo_mode.coefficients());
+                EffortEstimate::new(coefficients, options.cost_per_person_month)
+            }),
+            marker_regex: options.compiled.marker_regex.clone(),
+            ci_matcher: options.compiled.ci_matcher.clone(),
+            secret_rules: options.compiled.secret_rules.clone(),
+            dependency_tracker: options.track_dependencies.then(|| {
+                DependencyTracker::new(&options.dependency_manifests, options.parse_dependencies)
+            }),
+            large_binary_threshold: options.large_binary_threshold,
+            normalize_authors: options.normalize_authors,
+            gitmoji_classifier: options.compiled.gitmoji.clone(),
+            squash_detector: options.compiled.squash.clone(),
+            anomaly_method: (
+                options.anomaly_method,
+                options
+                    .anomaly_threshold
+                    .unwrap_or_else(|| options.anomaly_method.default_threshold()),
+            ),
+            month_authors: BTreeMap::new(),
+            dormancy_months: options.dormancy_months,
+            indent_width: options.indent_width,
+            pub_item_regex: options.public_api.then(public_api::pub_item_regex),
+            group_months: (matches!(options.pivot, Pivot::Author | Pivot::Pattern) || !options.groups.is_empty())
+                .then(HashMap::new),
+        }
+    }
+
+    // `parent_ids` are the commit's parents after history rewrites
+    #[allow(clippy::too_many_arguments)]
+    pub fn observe(
+        &mut self,
+        repo: &Repository,
+        replacements: &Replacements,
+        month_key: &str,
+        commit: &Commit,
+        parent_ids: &[Oid],
+        author: &str,
+        observed: &ObservedDiff,
+        deltas: &[FileDelta],
+    ) -> Result<(), AnalyzerError> {
+        let diff = &observed.diff;
+        // Everything that can fail is read before any section counts the
+        // commit, so a commit failing part way leaves no section half counted
+        let merge_check = match &self.self_merges {
+            Some(self_merges) => Some(self_merges.check(repo, replacements, commit, parent_ids)?),
+            None => None,
+        };
+        #[cfg(feature = "functions")]
+        let function_changes = match &mut self.function_churn {
+            Some(function_churn) => Some(function_churn.changes(repo, observed, deltas)?),
+            None => None,
+        };
+        if let Some(file_lengths) = &mut self.file_lengths {
+            file_lengths.read_tags(repo)?;
+        }
+        let manifest_changes = match (&self.dependencies, &self.dependency_tracker) {
+            (Some(_), Some(tracker)) => Some(tracker.changes(diff)?),
+            _ => None,
+        };
+        let secret_findings = match &self.secrets {
+            Some(_) => Some(SecretsReport::scan(commit, author, observed, &self.secret_rules)?),
+            None => None,
+        };
+        let large_binaries = match &mut self.large_binaries {
+            Some(report) => Some(report.scan(repo, commit, author, diff, self.large_binary_threshold)?),
+            None => None,
+        };
+
+        if let Some(churn_metrics) = &mut self.churn_metrics {
+            churn_metrics.entry(month_key.to_string()).or_default().observe(commit, author, deltas);
+        }
+        if let Some(duplicate_authors) = &mut self.duplicate_authors {
+            let signature = commit.author();
+            duplicate_authors.observe(
+                &authors::field(&signature, AuthorField::Name, self.normalize_authors),
+                &authors::field(&signature, AuthorField::Email, self.normalize_authors),
+            );
+        }
+        if let Some(message_metrics) = &mut self.message_metrics {
+            message_metrics
+                .entry(month_key.to_string())
+                .or_default()
+                .observe(commit.message().unwrap_or(""));
+        }
+        if let (Some(gitmoji), Some(classifier)) = (&mut self.gitmoji, &self.gitmoji_classifier) {
+            let (subject, _) = messages::split(commit.message().unwrap_or(""));
+            gitmoji
+                .entry(month_key.to_string())
+                .or_default()
+                .observe(classifier.classify(subject).as_ref());
+        }
+        if self.anomalies.is_some() {
+            self.month_authors.entry(month_key.to_string()).or_default().insert(author.to_string());
+        }
+        if let Some(collaboration) = &mut self.collaboration {
+            collaboration.observe(commit.author().when().seconds(), author, deltas);
+        }
+        if let Some(ownership) = &mut self.ownership {
+            ownership.observe(commit.author().when().seconds(), month_key, author, deltas);
+        }
+        if let Some(contributors) = &mut self.contributors {
+            contributors.observe(commit, author, deltas);
+        }
+        if let Some(new_contributors) = &mut self.new_contributors {
+            new_contributors.observe(commit.author().when().seconds(), month_key, author, deltas);
+        }
+        if let Some(contributor_growth) = &mut self.contributor_growth {
+            contributor_growth.observe(commit.author().when().seconds(), month_key, author, deltas);
+        }
+        if let Some(renames) = &mut self.renames {
+            renames.observe(commit, deltas);
+        }
+        if let Some(timezones) = &mut self.timezones {
+            timezones.observe(month_key, commit, author);
+        }
+        if let Some(out_of_hours) = &mut self.out_of_hours {
+            out_of_hours.observe(month_key, commit, author, deltas);
+        }
+        if let Some(commit_latency) = &mut self.commit_latency {
+            commit_latency.observe(month_key, commit, author);
+        }
+        if let Some(files_touched) = &mut self.files_touched {
+            files_touched.entry(month_key.to_string()).or_default().observe(deltas);
+        }
+        if let Some(commit_size) = &mut self.commit_size {
+            commit_size.entry(month_key.to_string()).or_default().observe(deltas);
+        }
+        if let Some(reviews) = &mut self.reviews {
+            reviews.observe(month_key, commit, author);
+        }
+        if let (Some(self_merges), Some(check)) = (&mut self.self_merges, merge_check) {
+            self_merges.observe(month_key, commit, check);
+        }
+        if let (Some(squash_merges), Some(detector)) = (&mut self.squash_merges, &self.squash_detector) {
+            squash_merges
+                .entry(month_key.to_string())
+                .or_default()
+                .observe(detector.detect(commit, parent_ids.len(), crate::lines_changed(deltas)).as_ref());
+        }
+        if let Some(message_hygiene) = &mut self.message_hygiene {
+            message_hygiene.observe(month_key, commit);
+        }
+        if let Some(nesting_depth) = &mut self.nesting_depth {
+            nesting_depth.entry(month_key.to_string()).or_default().observe(deltas, self.indent_width);
+        }
+        if let (Some(public_api), Some(regex)) = (&mut self.public_api, &self.pub_item_regex) {
+            public_api.entry(month_key.to_string()).or_default().observe(commit, deltas, regex);
+        }
+        if let Some(imports) = &mut self.imports {
+            imports.observe(month_key, deltas);
+        }
+        if let Some(rolling) = &mut self.rolling {
+            rolling.observe(commit.author().when().seconds(), deltas);
+        }
+        if let Some(decayed_churn) = &mut self.decayed_churn {
+            decayed_churn.observe(commit.author().when().seconds(), author, deltas);
+        }
+        #[cfg(feature = "functions")]
+        if let (Some(function_churn), Some(changes)) = (&mut self.function_churn, function_changes) {
+            function_churn.observe(author, changes);
+        }
+        if let Some(file_counts) = &mut self.file_counts {
+            file_counts.observe(month_key, commit);
+        }
+        if let Some(file_lengths) = &mut self.file_lengths {
+            file_lengths.observe(month_key, commit);
+        }
+        if let (Some(markers), Some(regex)) = (&mut self.markers, &self.marker_regex) {
+            markers.entry(month_key.to_string()).or_default().observe(deltas, regex);
+        }
+        if let (Some(dependencies), Some(tracker), Some(changes)) =
+            (&mut self.dependencies, &self.dependency_tracker, manifest_changes)
+        {
+            tracker.observe(dependencies.entry(month_key.to_string()).or_default(), changes);
+        }
+        if let (Some(ci), Some(matcher)) = (&mut self.ci, &self.ci_matcher) {
+            ci.observe(month_key, commit, author, diff, matcher);
+        }
+        if let Some(licenses) = &mut self.licenses {
+            licenses.observe(commit, author, diff);
+        }
+        if let (Some(secrets), Some(findings)) = (&mut self.secrets, secret_findings) {
+            secrets.observe(findings);
+        }
+        if let (Some(report), Some(files)) = (&mut self.large_binaries, large_binaries) {
+            report.observe(month_key, files);
+        }
+        Ok(())
+    }
+
+    // The docs section also sees documentation files outside the tracked
+    // extensions, which nothing else counts
+    pub fn observe_docs(&mut self, month_key: &str, deltas: &[FileDelta], docs_deltas: &[FileDelta]) {
+        if let Some(docs) = &mut self.docs {
+            docs.entry(month_key.to_string()).or_default().observe(deltas, docs_deltas);
+        }
+    }
+
+    // Fills in values that can only be computed once the walk is complete
+    pub fn finish(
+        &mut self,
+        repo: &Repository,
+        monthly_stats: &MonthlyStats,
+        options: &AnalysisOptions,
+        path_filter: &PathFilter,
+    ) -> Result<(), AnalyzerError> {
+        if let Some(docs) = &mut self.docs {
+            docs.values_mut().for_each(DocsMonth::finish);
+        }
+        if let Some(churn_metrics) = &mut self.churn_metrics {
+            churn_metrics.values_mut().for_each(ChurnMonth::finish);
+        }
+        if let Some(duplicate_authors) = &mut self.duplicate_authors {
+            duplicate_authors.finish();
+        }
+        if let Some(collaboration) = &mut self.collaboration {
+            collaboration.finish();
+        }
+        if let Some(ownership) = &mut self.ownership {
+            ownership.finish();
+        }
+        if let Some(contributors) = &mut self.contributors {
+            contributors.finish(self.dormancy_months);
+        }
+        if let Some(new_contributors) = &mut self.new_contributors {
+            new_contributors.finish();
+        }
+        if let Some(contributor_growth) = &mut self.contributor_growth {
+            contributor_growth.finish();
+        }
+        if let Some(renames) = &mut self.renames {
+            renames.finish();
+        }
+        if let Some(timezones) = &mut self.timezones {
+            timezones.finish();
+        }
+        if let Some(out_of_hours) = &mut self.out_of_hours {
+            out_of_hours.finish();
+        }
+        if let Some(commit_latency) = &mut self.commit_latency {
+            commit_latency.finish();
+        }
+        if let Some(files_touched) = &mut self.files_touched {
+            files_touched.values_mut().for_each(TouchedMonth::finish);
+        }
+        if let Some(commit_size) = &mut self.commit_size {
+            commit_size.values_mut().for_each(SizeMonth::finish);
+        }
+        if let Some(reviews) = &mut self.reviews {
+            reviews.finish();
+        }
+        if let Some(self_merges) = &mut self.self_merges {
+            self_merges.finish();
+        }
+        if let Some(squash_merges) = &mut self.squash_merges {
+            squash_merges.values_mut().for_each(SquashMonth::finish);
+        }
+        if let Some(message_hygiene) = &mut self.message_hygiene {
+            message_hygiene.finish();
+        }
+        if let Some(nesting_depth) = &mut self.nesting_depth {
+            nesting_depth.values_mut().for_each(NestingMonth::finish);
+        }
+        if let Some(public_api) = &mut self.public_api {
+            public_api.values_mut().for_each(ApiMonth::finish);
+        }
+        if let Some(imports) = &mut self.imports {
+            imports.finish();
+        }
+        if let Some(rolling) = &mut self.rolling {
+            rolling.finish();
+        }
+        if let Some(decayed_churn) = &mut self.decayed_churn {
+            decayed_churn.finish();
+        }
+        #[cfg(feature = "functions")]
+        if let Some(function_churn) = &mut self.function_churn {
+            function_churn.finish();
+        }
+        if let Some(message_metrics) = &mut self.message_metrics {
+            message_metrics.values_mut().for_each(MessageMonth::finish);
+        }
+        if let Some(ci) = &mut self.ci {
+            ci.finish();
+        }
+        if let Some(licenses) = &mut self.licenses {
+            licenses.finish(repo)?;
+        }
+        if let Some(file_counts) = &mut self.file_counts {
+            file_counts.finish(repo, options, path_filter)?;
+        }
+        if let Some(file_lengths) = &mut self.file_lengths {
+            file_lengths.finish(repo, options, path_filter)?;
+        }
+        if let Some(secrets) = &mut self.secrets {
+            secrets.finish();
+        }
+        if let Some(large_binaries) = &mut self.large_binaries {
+            large_binaries.finish();
+        }
+        if let Some(cumulative_lines) = &mut self.cumulative_lines {
+            *cumulative_lines = series::cumulative_lines(monthly_stats);
+        }
+        if let Some(language_share) = &mut self.language_share {
+            *language_share = series::language_share(monthly_stats);
+        }
+        if let Some(language_diversity) = &mut self.language_diversity {
+            *language_diversity = series::language_diversity(monthly_stats);
+        }
+        if let Some(effort) = &mut self.effort {
+            effort.finish(monthly_stats);
+        }
+        if let Some(anomalies) = &mut self.anomalies {
+            let (method, threshold) = self.anomaly_method;
+            let mut churn = BTreeMap::new();
+            let mut deletions = BTreeMap::new();
+            for (month, exts) in monthly_stats {
+                let month_deletions: i64 = exts.values().map(|s| i64::from(s.deletions)).sum();
+                let month_additions: i64 = exts.values().map(|s| i64::from(s.additions)).sum();
+                churn.insert(month.clone(), (month_additions + month_deletions) as f64);
+                deletions.insert(month.clone(), month_deletions as f64);
+            }
+            let contributors = self
+                .month_authors
+                .iter()
+                .map(|(month, authors)| (month.clone(), authors.len() as f64))
+                .collect();
+            anomalies.extend(anomalies::detect("churn", &churn, method, threshold));
+            anomalies.extend(anomalies::detect("deletions", &deletions, method, threshold));
+            anomalies.extend(anomalies::detect("contributors", &contributors, method, threshold));
+            anomalies.sort_by(|a, b| a.month.cmp(&b.month).then_with(|| a.metric.cmp(&b.metric)));
+        }
+        Ok(())
+    }
+}
+
+pub fn touches_docs(deltas: &[FileDelta], docs_deltas: &[FileDelta]) -> bool {
+    !docs_deltas.is_empty() || deltas.iter().any(|d| classify::is_docs(&d.ext))
+}
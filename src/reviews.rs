@@ -0,0 +1,117 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use git2::Commit;
+use serde::Serialize;
+
+use crate::authors;
+use crate::messages;
+
+// Whether a trailer token records a review ("Reviewed-by") or an ack
+// ("Acked-by"); None for any other trailer
+pub fn review_kind(token: &str) -> Option<bool> {
+    if token.eq_ignore_ascii_case("Reviewed-by") {
+        Some(true)
+    } else if token.eq_ignore_ascii_case("Acked-by") {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+pub fn has_review(message: &str) -> bool {
+    messages::trailers(message).iter().any(|(token, _)| review_kind(token).is_some())
+}
+
+// Review activity recorded in Reviewed-by: and Acked-by: trailers, the way
+// kernel-style mailing list workflows and Gerrit exports record it
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct ReviewMonth {
+    pub commits: i32,
+    // Commits carrying at least one review or ack trailer
+    pub reviewed_commits: i32,
+    pub reviewed_pct: f64,
+    pub reviews: i32,
+    pub acks: i32,
+    // Review and ack trailers per reviewer
+    pub reviewers: BTreeMap<String, i32>,
+}
+
+// Reviewers as rows and commit authors as columns, both sorted; values[i][j]
+// is how many commits of authors[j] reviewers[i] reviewed or acked
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct ReviewMatrix {
+    pub reviewers: Vec<String>,
+    pub authors: Vec<String>,
+    pub values: Vec<Vec<i32>>,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct ReviewReport {
+    pub months: BTreeMap<String, ReviewMonth>,
+    pub matrix: ReviewMatrix,
+    #[serde(skip)]
+    normalize: bool,
+    #[serde(skip)]
+    pairs: BTreeMap<(String, String), i32>,
+}
+
+impl ReviewReport {
+    pub fn new(normalize: bool) -> Self {
+        ReviewReport {
+            normalize,
+            ..Default::default()
+        }
+    }
+
+    pub fn observe(&mut self, month_key: &str, commit: &Commit, author: &str) {
+        let month = self.months.entry(month_key.to_string()).or_default();
+        month.commits += 1;
+
+        // A reviewer listed twice on one commit counts once
+        let mut reviewers = BTreeSet::new();
+        for (token, value) in messages::trailers(commit.message().unwrap_or("")) {
+            let Some(is_review) = review_kind(token) else {
+                continue;
+            };
+            let reviewer = authors::parse_identity(value, self.normalize);
+            if !reviewers.insert(reviewer.clone()) {
+                continue;
+            }
+            if is_review {
+                month.reviews += 1;
+            } else {
+                month.acks += 1;
+            }
+            *month.reviewers.entry(reviewer.clone()).or_default() += 1;
+            *self.pairs.entry((reviewer, author.to_string())).or_default() += 1;
+        }
+        if !reviewers.is_empty() {
+            month.reviewed_commits += 1;
+        }
+    }
+
+    pub fn finish(&mut self) {
+        for month in self.months.values_mut() {
+            if month.commits > 0 {
+                month.reviewed_pct =
+                    (f64::from(month.reviewed_commits) * 10000.0 / f64::from(month.commits)).round() / 100.0;
+            }
+        }
+
+        let pairs = std::mem::take(&mut self.pairs);
+        let reviewers: BTreeSet<&String> = pairs.keys().map(|(reviewer, _)| reviewer).collect();
+        let authors: BTreeSet<&String> = pairs.keys().map(|(_, author)| author).collect();
+        let reviewers: Vec<String> = reviewers.into_iter().cloned().collect();
+        let authors: Vec<String> = authors.into_iter().cloned().collect();
+        let values = reviewers
+            .iter()
+            .map(|reviewer| {
+                authors
+                    .iter()
+                    .map(|author| pairs.get(&(reviewer.clone(), author.clone())).copied().unwrap_or(0))
+                    .collect()
+            })
+            .collect();
+        self.matrix = ReviewMatrix { reviewers, authors, values };
+    }
+}
@@ -0,0 +1,206 @@
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, NaiveDate};
+use serde::Serialize;
+
+use crate::diff::FileDelta;
+use crate::stats_by_extension;
+
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+pub struct RollingStats {
+    pub additions: i64,
+    pub deletions: i64,
+    pub lines: i64,
+    pub modifications: i64,
+}
+
+impl RollingStats {
+    fn apply(&mut self, other: &RollingStats, sign: i64) {
+        self.additions += sign * other.additions;
+        self.deletions += sign * other.deletions;
+        self.lines += sign * other.lines;
+        self.modifications += sign * other.modifications;
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct RollingPoint {
+    pub commits: i64,
+    pub extensions: BTreeMap<String, RollingStats>,
+}
+
+// Sums over the window_days before the end of every calendar month from the
+// first commit to the last, months without commits included, so the series
+// is smooth where the per-month buckets are not. Evaluated monthly whatever
+// the bucket option.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct RollingWindow {
+    pub window_days: u32,
+    pub months: BTreeMap<String, RollingPoint>,
+    #[serde(skip)]
+    commits: Vec<(i64, Vec<(String, RollingStats)>)>,
+}
+
+impl RollingWindow {
+    pub fn new(window_days: u32) -> Self {
+        RollingWindow {
+            window_days,
+            ..Default::default()
+        }
+    }
+
+    pub fn observe(&mut self, timestamp: i64, deltas: &[FileDelta]) {
+        // A date chrono cannot represent has no month to fall in
+        if chrono::DateTime::from_timestamp(timestamp, 0).is_none() {
+            return;
+        }
+        let stats = stats_by_extension(deltas)
+            .into_iter()
+            .map(|(ext, stats)| {
+                let stats = RollingStats {
+                    additions: i64::from(stats.additions),
+                    deletions: i64::from(stats.deletions),
+                    lines: i64::from(stats.lines),
+                    modifications: i64::from(stats.modifications),
+                };
+                (ext, stats)
+            })
+            .collect();
+        self.commits.push((timestamp, stats));
+    }
+
+    pub fn finish(&mut self) {
+        let mut commits = std::mem::take(&mut self.commits);
+        commits.sort_by_key(|(timestamp, _)| *timestamp);
+        let (Some(first), Some(last)) = (commits.first(), commits.last()) else {
+            return;
+        };
+        let window = i64::from(self.window_days) * 86_400;
+        let last_month = month_start(last.0);
+
+        let mut point = RollingPoint::default();
+        // Commits before `entered` have been added to the sums, and those
+        // before `left` taken out again
+        let (mut entered, mut left) = (0, 0);
+        let mut month = month_start(first.0);
+        while month <= last_month {
+            let end = next_month(month);
+            let end_timestamp = match end {
+                Some(end) => end.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc().timestamp(),
+                None => NaiveDate::MAX.and_hms_opt(23, 59, 59).unwrap_or_default().and_utc().timestamp() + 1,
+            };
+            while entered < commits.len() && commits[entered].0 < end_timestamp {
+                add(&mut point, &commits[entered].1, 1);
+                entered += 1;
+            }
+            while left < entered && commits[left].0 < end_timestamp - window {
+                add(&mut point, &commits[left].1, -1);
+                left += 1;
+            }
+            point
+                .extensions
+                .retain(|_, stats| stats.modifications != 0 || stats.additions != 0 || stats.deletions != 0);
+            let key = format!("{}-{:02}", month.year(), month.month());
+            self.months.insert(key, point.clone());
+            // The last month chrono can represent has no next one
+            let Some(end) = end else {
+                break;
+            };
+            month = end;
+        }
+    }
+}
+
+fn add(point: &mut RollingPoint, stats: &[(String, RollingStats)], sign: i64) {
+    point.commits += sign;
+    for (ext, stats) in stats {
+        point.extensions.entry(ext.clone()).or_default().apply(stats, sign);
+    }
+}
+
+fn month_start(timestamp: i64) -> NaiveDate {
+    let date = chrono::DateTime::from_timestamp(timestamp, 0).unwrap_or_default().date_naive();
+    date.with_day(1).unwrap_or(date)
+}
+
+fn next_month(month: NaiveDate) -> Option<NaiveDate> {
+    let (year, month) = if month.month() == 12 {
+        (month.year() + 1, 1)
+    } else {
+        (month.year(), month.month() + 1)
+    };
+    NaiveDate::from_ymd_opt(year, month, 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use git2::Delta;
+
+    use super::*;
+
+    fn at(date: &str) -> i64 {
+        chrono::DateTime::parse_from_rfc3339(&format!("{}T12:00:00Z", date)).unwrap().timestamp()
+    }
+
+    fn added(ext: &str, additions: i32) -> Vec<FileDelta> {
+        vec![FileDelta {
+            path: format!("file{}", ext),
+            ext: ext.to_string(),
+            status: Delta::Modified,
+            old_path: None,
+            is_binary: false,
+            additions,
+            deletions: 0,
+            has_hunks: true,
+            hunks: Vec::new(),
+            metrics: BTreeMap::new(),
+            content_missing: false,
+        }]
+    }
+
+    #[test]
+    fn months_run_from_the_first_commit_to_the_last() {
+        let mut window = RollingWindow::new(30);
+        // Observed newest first, as the walk yields them
+        window.observe(at("2024-03-10"), &added(".rs", 5));
+        window.observe(at("2024-01-15"), &added(".rs", 10));
+        window.finish();
+        let months: Vec<&str> = window.months.keys().map(String::as_str).collect();
+        assert_eq!(months, ["2024-01", "2024-02", "2024-03"]);
+        assert_eq!(window.months["2024-01"].commits, 1);
+        assert_eq!(window.months["2024-01"].extensions[".rs"].additions, 10);
+        // The January commit is more than 30 days before the end of February
+        assert_eq!(window.months["2024-02"].commits, 0);
+        assert!(window.months["2024-02"].extensions.is_empty());
+        assert_eq!(window.months["2024-03"].extensions[".rs"].additions, 5);
+    }
+
+    #[test]
+    fn a_december_commit_ends_the_series() {
+        let mut window = RollingWindow::new(30);
+        window.observe(at("2024-12-31"), &added(".rs", 1));
+        window.finish();
+        assert_eq!(window.months.keys().collect::<Vec<_>>(), ["2024-12"]);
+    }
+
+    #[test]
+    fn unrepresentable_dates_are_skipped() {
+        let mut window = RollingWindow::new(30);
+        window.observe(i64::MAX, &added(".rs", 1));
+        window.observe(at("2024-05-01"), &added(".rs", 1));
+        window.finish();
+        assert_eq!(window.months.keys().collect::<Vec<_>>(), ["2024-05"]);
+    }
+
+    #[test]
+    fn the_last_representable_month_ends_the_series() {
+        let last = NaiveDate::MAX.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp();
+        let mut window = RollingWindow::new(30);
+        window.observe(last, &added(".rs", 1));
+        window.finish();
+        assert_eq!(window.months.len(), 1);
+        assert_eq!(window.months.values().next().unwrap().commits, 1);
+    }
+}
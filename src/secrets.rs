@@ -0,0 +1,127 @@
+use std::collections::BTreeMap;
+
+use git2::{Commit, Patch};
+use path_slash::PathExt;
+use regex::Regex;
+use serde::Serialize;
+
+use crate::diff::ObservedDiff;
+use crate::AnalyzerError;
+
+// Built-in rules, used unless the caller supplies `secret_patterns`
+pub const DEFAULT_SECRET_PATTERNS: &[(&str, &str)] = &[
+    ("aws_access_key_id", r"\b(AKIA|ASIA)[0-9A-Z]{16}\b"),
+    (
+        "aws_secret_access_key",
+        r#"(?i)aws.{0,20}(secret|private).{0,20}['"][0-9a-zA-Z/+]{40}['"]"#,
+    ),
+    (
+        "private_key",
+        r"-----BEGIN (RSA |EC |DSA |OPENSSH |PGP |ENCRYPTED )?PRIVATE KEY( BLOCK)?-----",
+    ),
+    (
+        "github_token",
+        r"\b(ghp|gho|ghu|ghs|ghr)_[A-Za-z0-9]{36}\b|\bgithub_pat_[A-Za-z0-9_]{22,}",
+    ),
+    ("slack_token", r"\bxox[baprs]-[A-Za-z0-9-]{10,}"),
+    (
+        "generic_secret",
+        r#"(?i)\b(api[_-]?key|secret|token|passw(or)?d)\b["']?\s*[:=]\s*["'][^"'\s]{12,}["']"#,
+    ),
+];
+
+pub fn compile_rules(
+    patterns: &BTreeMap<String, String>,
+) -> Result<Vec<(String, Regex)>, regex::Error> {
+    if patterns.is_empty() {
+        DEFAULT_SECRET_PATTERNS
+            .iter()
+            .map(|(name, pattern)| Ok((name.to_string(), Regex::new(pattern)?)))
+            .collect()
+    } else {
+        patterns
+            .iter()
+            .map(|(name, pattern)| Ok((name.clone(), Regex::new(pattern)?)))
+            .collect()
+    }
+}
+
+// A rule hit on an added line. The matched text itself is deliberately left
+// out so the report does not become a secrets inventory.
+#[derive(Debug, Clone, Serialize)]
+pub struct SecretFinding {
+    pub commit: String,
+    pub timestamp: i64,
+    pub author: String,
+    pub path: String,
+    pub line: Option<u32>,
+    pub rule: String,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct SecretsReport {
+    pub commits_with_findings: i32,
+    pub by_rule: BTreeMap<String, i32>,
+    pub findings: Vec<SecretFinding>,
+}
+
+impl SecretsReport {
+    // Rule hits on the lines a commit added; observe records them
+    pub fn scan(
+        commit: &Commit,
+        author: &str,
+        observed: &ObservedDiff,
+        rules: &[(String, Regex)],
+    ) -> Result<Vec<SecretFinding>, AnalyzerError> {
+        let diff = &observed.diff;
+        let mut findings = Vec::new();
+
+        for (idx, delta) in diff.deltas().enumerate() {
+            let Some(path) = delta.new_file().path() else {
+                continue;
+            };
+            let path_str = path.to_slash_lossy();
+            let Some(patch) = Patch::from_diff(diff, idx)? else {
+                continue;
+            };
+            for hunk_idx in 0..patch.num_hunks() {
+                for line_idx in 0..patch.num_lines_in_hunk(hunk_idx)? {
+                    let line = patch.line_in_hunk(hunk_idx, line_idx)?;
+                    if line.origin() != '+' || !observed.keeps_line(&path_str, '+', line.content()) {
+                        continue;
+                    }
+                    let content = String::from_utf8_lossy(line.content());
+                    for (name, regex) in rules {
+                        if !regex.is_match(&content) {
+                            continue;
+                        }
+                        findings.push(SecretFinding {
+                            commit: commit.id().to_string(),
+                            timestamp: commit.author().when().seconds(),
+                            author: author.to_string(),
+                            path: path_str.clone().into_owned(),
+                            line: line.new_lineno(),
+                            rule: name.clone(),
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(findings)
+    }
+
+    pub fn observe(&mut self, findings: Vec<SecretFinding>) {
+        if !findings.is_empty() {
+            self.commits_with_findings += 1;
+        }
+        for finding in &findings {
+            *self.by_rule.entry(finding.rule.clone()).or_default() += 1;
+        }
+        self.findings.extend(findings);
+    }
+
+    pub fn finish(&mut self) {
+        self.findings.sort_by_key(|f| f.timestamp);
+    }
+}
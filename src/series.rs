@@ -0,0 +1,107 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::MonthlyStats;
+
+// month -> extension -> value, ordered by month
+pub type MonthlySeries<T> = BTreeMap<String, BTreeMap<String, T>>;
+
+// Running sum of net lines (additions - deletions) per extension. Every month
+// carries all extensions seen so far, so each entry is a complete snapshot.
+pub fn cumulative_lines(monthly_stats: &MonthlyStats) -> MonthlySeries<i64> {
+    let months: BTreeMap<&String, _> = monthly_stats.iter().collect();
+    let mut running: BTreeMap<String, i64> = BTreeMap::new();
+    let mut series = BTreeMap::new();
+    for (month, exts) in months {
+        for (ext, stats) in exts {
+            *running.entry(ext.clone()).or_default() += i64::from(stats.lines);
+        }
+        series.insert(month.clone(), running.clone());
+    }
+    series
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Share {
+    // Percent of the month's additions + deletions
+    pub churn_pct: f64,
+    // Percent of the cumulative net lines at the end of the month; extensions
+    // whose running total went negative count as zero
+    pub loc_pct: f64,
+}
+
+pub fn language_share(monthly_stats: &MonthlyStats) -> MonthlySeries<Share> {
+    let cumulative = cumulative_lines(monthly_stats);
+    let mut series = BTreeMap::new();
+    for (month, totals) in cumulative {
+        let churn: BTreeMap<&String, i64> = monthly_stats[&month]
+            .iter()
+            .map(|(ext, stats)| (ext, i64::from(stats.additions) + i64::from(stats.deletions)))
+            .collect();
+        let churn_total: i64 = churn.values().sum();
+        let loc_total: i64 = totals.values().map(|lines| (*lines).max(0)).sum();
+
+        let shares = totals
+            .iter()
+            .map(|(ext, lines)| {
+                let share = Share {
+                    churn_pct: percent(churn.get(ext).copied().unwrap_or(0), churn_total),
+                    loc_pct: percent((*lines).max(0), loc_total),
+                };
+                (ext.clone(), share)
+            })
+            .collect();
+        series.insert(month, shares);
+    }
+    series
+}
+
+// How evenly a month's churn (additions + deletions) spreads over extensions
+#[derive(Debug, Clone, Serialize)]
+pub struct Diversity {
+    // Extensions with any churn
+    pub extensions: usize,
+    // -sum(p * ln p) over the churn shares p; 0 for a single extension
+    pub shannon: f64,
+    // shannon / ln(extensions): 1 when churn is spread evenly, null for a
+    // single extension
+    pub evenness: Option<f64>,
+    // 1 - sum(p^2), the chance two changed lines are of different extensions
+    pub gini_simpson: f64,
+}
+
+pub fn language_diversity(monthly_stats: &MonthlyStats) -> BTreeMap<String, Diversity> {
+    let round = |value: f64| (value * 10000.0).round() / 10000.0;
+    monthly_stats
+        .iter()
+        .map(|(month, exts)| {
+            let churn: Vec<f64> = exts
+                .values()
+                .map(|stats| f64::from(stats.additions) + f64::from(stats.deletions))
+                .filter(|churn| *churn > 0.0)
+                .collect();
+            let total: f64 = churn.iter().sum();
+            let shares: Vec<f64> = churn.iter().map(|c| c / total).collect();
+            let shannon = -shares.iter().map(|p| p * p.ln()).sum::<f64>();
+            let diversity = Diversity {
+                extensions: shares.len(),
+                shannon: round(shannon.abs()),
+                evenness: (shares.len() > 1).then(|| round(shannon / (shares.len() as f64).ln())),
+                gini_simpson: if shares.is_empty() {
+                    0.0
+                } else {
+                    round(1.0 - shares.iter().map(|p| p * p).sum::<f64>())
+                },
+            };
+            (month.clone(), diversity)
+        })
+        .collect()
+}
+
+fn percent(part: i64, total: i64) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    (part as f64 * 10000.0 / total as f64).round() / 100.0
+}
@@ -0,0 +1,249 @@
+use std::collections::{hash_map::DefaultHasher, HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::io::Read;
+use std::sync::Arc;
+
+use git2::Repository;
+use parking_lot::Mutex;
+use serde_json::{json, Value};
+use tiny_http::{Header, Method, Response, Server};
+
+use crate::api::{self, Request};
+use crate::options::AnalysisOptions;
+use crate::paths::IGNORE_FILE_NAME;
+use crate::{open_repository, AnalyzerError};
+
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:8765";
+pub const DEFAULT_CACHE_ENTRIES: usize = 256;
+
+// Daemon behind `repo-scan serve`. Endpoints:
+//   POST /analyze   a JSON request as for `repo-scan run`
+//   DELETE /cache   drop every cached result
+//   GET /health     liveness and cache size
+// Responses are {"ok": true, "result": ..., "cached": bool} or
+// {"ok": false, "error": "..."}, with status 400 for invalid requests, 413
+// for bodies over MAX_BODY_BYTES and 500 for scans failing. Results of single
+// local repositories are cached until one of their refs, grafts or their
+// ignore file changes; working tree, stash, remote, directory and fleet
+// requests, and those depending on the current time, always run.
+pub struct AnalysisServer {
+    // Opened once per path; only used to fingerprint the repository state
+    repos: Mutex<Lru<Repository>>,
+    cache: Mutex<Lru<(u64, Value)>>,
+}
+
+// Repositories kept open for fingerprinting, least recently used dropped first
+const OPEN_REPOSITORIES: usize = 64;
+// Larger request bodies are refused with 413
+const MAX_BODY_BYTES: u64 = 1 << 20;
+
+// Keyed entries bounded to `capacity`, evicting the least recently used
+struct Lru<V> {
+    entries: HashMap<String, V>,
+    // Keys least recently used first
+    order: VecDeque<String>,
+    capacity: usize,
+}
+
+impl<V> Lru<V> {
+    fn new(capacity: usize) -> Self {
+        Lru {
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    fn get(&mut self, key: &str) -> Option<&V> {
+        if !self.entries.contains_key(key) {
+            return None;
+        }
+        if let Some(position) = self.order.iter().position(|k| k == key) {
+            if let Some(key) = self.order.remove(position) {
+                self.order.push_back(key);
+            }
+        }
+        self.entries.get(key)
+    }
+
+    fn insert(&mut self, key: String, value: V) {
+        if self.entries.insert(key.clone(), value).is_some() {
+            self.order.retain(|k| *k != key);
+        }
+        self.order.push_back(key);
+        while self.entries.len() > self.capacity {
+            match self.order.pop_front() {
+                Some(oldest) => {
+                    self.entries.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+}
+
+impl AnalysisServer {
+    pub fn new(cache_entries: usize) -> Self {
+        AnalysisServer {
+            repos: Mutex::new(Lru::new(OPEN_REPOSITORIES)),
+            cache: Mutex::new(Lru::new(cache_entries)),
+        }
+    }
+
+    // Serves until the process is stopped, handling requests on `workers` threads
+    pub fn serve(self, address: &str, workers: usize) -> Result<(), AnalyzerError> {
+        let server = Server::http(address)
+            .map_err(|e| AnalyzerError::InvalidOption(format!("cannot listen on {}: {}", address, e)))?;
+        eprintln!("repo-scan: listening on http://{}", address);
+        let server = Arc::new(server);
+        let state = Arc::new(self);
+        let handles: Vec<_> = (0..workers.max(1))
+            .map(|_| {
+                let server = Arc::clone(&server);
+                let state = Arc::clone(&state);
+                std::thread::spawn(move || {
+                    for request in server.incoming_requests() {
+                        state.respond(request);
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            let _ = handle.join();
+        }
+        Ok(())
+    }
+
+    fn respond(&self, mut request: tiny_http::Request) {
+        let (status, body) = match (request.method(), request.url()) {
+            (Method::Post, "/analyze") => {
+                let mut body = String::new();
+                match request.as_reader().take(MAX_BODY_BYTES + 1).read_to_string(&mut body) {
+                    Ok(read) if read as u64 > MAX_BODY_BYTES => {
+                        let error = format!("request body exceeds {} bytes", MAX_BODY_BYTES);
+                        (413, json!({"ok": false, "error": error}))
+                    }
+                    Ok(_) => match self.analyze(&body) {
+                        Ok((result, cached)) => (200, json!({"ok": true, "result": result, "cached": cached})),
+                        Err(e) => (error_status(&e), json!({"ok": false, "error": e.to_string()})),
+                    },
+                    Err(e) => (400, json!({"ok": false, "error": e.to_string()})),
+                }
+            }
+            (Method::Delete, "/cache") => {
+                self.cache.lock().clear();
+                (200, json!({"ok": true}))
+            }
+            (Method::Get, "/health") => {
+                let cached = self.cache.lock().entries.len();
+                (200, json!({"ok": true, "version": env!("CARGO_PKG_VERSION"), "cached_results": cached}))
+            }
+            _ => (404, json!({"ok": false, "error": "unknown endpoint"})),
+        };
+        let response = Response::from_string(body.to_string())
+            .with_status_code(status)
+            .with_header(Header::from_bytes("Content-Type", "application/json").expect("static header is valid"));
+        // The client may have disconnected; there is nobody left to tell
+        let _ = request.respond(response);
+    }
+
+    fn analyze(&self, body: &str) -> Result<(Value, bool), AnalyzerError> {
+        let value: Value = serde_json::from_str(body)?;
+        // serde_json keeps object keys sorted, so equal requests print alike
+        let key = value.to_string();
+        let request: Request = serde_json::from_value(value)?;
+
+        let fingerprint = match cache_scope(&request) {
+            Some(repo_path) => Some(self.fingerprint(repo_path, request.options())?),
+            None => None,
+        };
+        if let Some(fingerprint) = fingerprint {
+            if let Some((cached, result)) = self.cache.lock().get(&key) {
+                if *cached == fingerprint {
+                    return Ok((result.clone(), true));
+                }
+            }
+        }
+
+        let result = api::run(request)?;
+        if let Some(fingerprint) = fingerprint {
+            self.cache.lock().insert(key, (fingerprint, result.clone()));
+        }
+        Ok((result, false))
+    }
+
+    // Hash of every ref target plus the ignore, grafts and shallow files,
+    // which is all a history analysis of the repository depends on
+    fn fingerprint(&self, repo_path: &str, options: &AnalysisOptions) -> Result<u64, AnalyzerError> {
+        // The same work tree may be paired with different git dirs
+        let key = match &options.git_dir {
+            Some(git_dir) => format!("{}\0{}", repo_path, git_dir),
+            None => repo_path.to_string(),
+        };
+        let mut repos = self.repos.lock();
+        if repos.get(&key).is_none() {
+            repos.insert(key.clone(), open_repository(repo_path, options)?);
+        }
+        let repo = &repos.entries[&key];
+
+        let mut hasher = DefaultHasher::new();
+        if let Ok(head) = repo.head() {
+            head.target().map(|oid| oid.as_bytes().to_vec()).hash(&mut hasher);
+        }
+        for reference in repo.references()? {
+            let reference = reference?;
+            reference.name_bytes().hash(&mut hasher);
+            reference.target().map(|oid| oid.as_bytes().to_vec()).hash(&mut hasher);
+        }
+        if let Some(workdir) = repo.workdir() {
+            std::fs::read(workdir.join(IGNORE_FILE_NAME)).ok().hash(&mut hasher);
+        }
+        // Grafts rewrite parents without touching any ref (refs/replace is
+        // covered above); fetching into a shallow clone moves its boundary
+        for file in ["info/grafts", "shallow"] {
+            std::fs::read(repo.path().join(file)).ok().hash(&mut hasher);
+        }
+        Ok(hasher.finish())
+    }
+}
+
+// The local repository a request's result depends on, if it can be cached
+fn cache_scope(request: &Request) -> Option<&str> {
+    let repo_path = match request {
+        Request::AnalyzeGitRepo { repo_path, .. }
+        | Request::AnalyzeGitCommits { repo_path, .. }
+        | Request::CompareWindows { repo_path, .. }
+        | Request::ExportCommitGraph { repo_path, .. }
+        | Request::BranchingMetrics { repo_path, .. }
+        | Request::CodeSurvival { repo_path, .. }
+        | Request::AuthorExtensionMatrix { repo_path, .. } => repo_path,
+        // Disk usage changes on repack without any ref moving
+        Request::StorageGrowth { .. }
+        | Request::AnalyzeWorkingTree { .. }
+        | Request::AnalyzeStashes { .. }
+        | Request::ScanDirectory { .. }
+        | Request::ScanFleet { .. } => return None,
+    };
+    let options = request.options();
+    // Dormant contributors are judged against the current time and a time
+    // budget stops wherever the clock says, so neither result can be reused
+    let clock_dependent = options.contributor_report || options.time_budget_seconds.is_some();
+    (!options.clone_remote && !clock_dependent).then_some(repo_path.as_str())
+}
+
+// 400 for requests that can never succeed as sent, 500 for failures while
+// running them
+fn error_status(error: &AnalyzerError) -> u16 {
+    match error {
+        AnalyzerError::InvalidOption(_)
+        | AnalyzerError::RegexError(_)
+        | AnalyzerError::Json(_)
+        | AnalyzerError::Open { .. } => 400,
+        _ => 500,
+    }
+}
@@ -0,0 +1,51 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::anomalies::quantile;
+use crate::diff::FileDelta;
+
+// Lines changed (additions plus deletions) per modification, where a
+// modification is one commit changing files of the extension, as in the
+// `modifications` counter. A few huge modifications pull the mean far above
+// the median, which is typical of generated or vendored files.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct SizeStats {
+    pub modifications: i32,
+    pub mean_lines: f64,
+    pub median_lines: f64,
+    #[serde(skip)]
+    sizes: Vec<i32>,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct SizeMonth {
+    #[serde(flatten)]
+    pub extensions: BTreeMap<String, SizeStats>,
+}
+
+impl SizeMonth {
+    pub fn observe(&mut self, deltas: &[FileDelta]) {
+        let mut commit_sizes: BTreeMap<&str, i32> = BTreeMap::new();
+        for delta in deltas.iter().filter(|d| d.has_hunks) {
+            *commit_sizes.entry(delta.ext.as_str()).or_default() += delta.additions + delta.deletions;
+        }
+        for (ext, size) in commit_sizes {
+            self.extensions.entry(ext.to_string()).or_default().sizes.push(size);
+        }
+    }
+
+    pub fn finish(&mut self) {
+        for stats in self.extensions.values_mut() {
+            let mut sorted: Vec<f64> = stats.sizes.iter().map(|&s| f64::from(s)).collect();
+            if sorted.is_empty() {
+                continue;
+            }
+            sorted.sort_by(f64::total_cmp);
+            stats.modifications = sorted.len() as i32;
+            stats.mean_lines = (sorted.iter().sum::<f64>() / sorted.len() as f64 * 100.0).round() / 100.0;
+            stats.median_lines = quantile(&sorted, 0.5);
+            stats.sizes = Vec::new();
+        }
+    }
+}
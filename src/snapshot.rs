@@ -0,0 +1,236 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use git2::{Commit, ObjectType, Oid, Repository, TreeWalkMode, TreeWalkResult};
+use path_slash::PathExt;
+use serde::Serialize;
+
+use crate::anomalies::quantile;
+use crate::binaries;
+use crate::classify;
+use crate::options::{AnalysisOptions, FileDetection};
+use crate::paths::PathFilter;
+use crate::series::MonthlySeries;
+use crate::AnalyzerError;
+
+// How many tracked files of each extension existed in the tree at the last
+// commit of each month, as opposed to the `files` counter, which counts paths
+// the first time they were changed
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct FileCountSnapshot {
+    #[serde(flatten)]
+    pub months: MonthlySeries<i32>,
+    // Latest commit of each month as (timestamp, tree)
+    #[serde(skip)]
+    last_commits: BTreeMap<String, (i64, Oid)>,
+}
+
+impl FileCountSnapshot {
+    pub fn observe(&mut self, month_key: &str, commit: &Commit) {
+        let timestamp = commit.author().when().seconds();
+        let last = self.last_commits.entry(month_key.to_string()).or_insert((timestamp, commit.tree_id()));
+        if timestamp > last.0 {
+            *last = (timestamp, commit.tree_id());
+        }
+    }
+
+    pub fn finish(
+        &mut self,
+        repo: &Repository,
+        options: &AnalysisOptions,
+        path_filter: &PathFilter,
+    ) -> Result<(), AnalyzerError> {
+        let mut binary = HashMap::new();
+        for (month, (_, tree_id)) in std::mem::take(&mut self.last_commits) {
+            let mut counts: BTreeMap<String, i32> = BTreeMap::new();
+            for (ext, _) in tracked_blobs(repo, tree_id, options, path_filter, &mut binary)? {
+                *counts.entry(ext).or_default() += 1;
+            }
+            self.months.insert(month, counts);
+        }
+        Ok(())
+    }
+}
+
+pub const DEFAULT_LONG_FILE_LINES: u32 = 500;
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct LengthStats {
+    pub files: i32,
+    pub p50_lines: f64,
+    pub p90_lines: f64,
+    pub max_lines: u32,
+    // Files longer than long_file_lines
+    pub long_files: i32,
+}
+
+// Line counts of the tracked text files of each extension in the tree at the
+// last commit of each month and at every tagged commit the scan saw
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct FileLengthSnapshot {
+    pub long_file_lines: u32,
+    pub months: BTreeMap<String, BTreeMap<String, LengthStats>>,
+    pub tags: BTreeMap<String, BTreeMap<String, LengthStats>>,
+    #[serde(skip)]
+    last_commits: BTreeMap<String, (i64, Oid)>,
+    // Tag names by the commit they point to, read on the first commit
+    #[serde(skip)]
+    tag_targets: Option<HashMap<Oid, Vec<String>>>,
+    #[serde(skip)]
+    tagged_trees: BTreeMap<String, Oid>,
+}
+
+impl FileLengthSnapshot {
+    pub fn new(long_file_lines: u32) -> Self {
+        FileLengthSnapshot {
+            long_file_lines,
+            ..Default::default()
+        }
+    }
+
+    // Reads the tag targets once, before the first commit is observed
+    pub fn read_tags(&mut self, repo: &Repository) -> Result<(), AnalyzerError> {
+        if self.tag_targets.is_none() {
+            let mut targets: HashMap<Oid, Vec<String>> = HashMap::new();
+            for name in repo.tag_names(None)?.iter().flatten() {
+                let target = repo
+                    .revparse_single(&format!("refs/tags/{}", name))
+                    .and_then(|object| object.peel_to_commit());
+                if let Ok(target) = target {
+                    targets.entry(target.id()).or_default().push(name.to_string());
+                }
+            }
+            self.tag_targets = Some(targets);
+        }
+        Ok(())
+    }
+
+    pub fn observe(&mut self, month_key: &str, commit: &Commit) {
+        let timestamp = commit.author().when().seconds();
+        let last = self.last_commits.entry(month_key.to_string()).or_insert((timestamp, commit.tree_id()));
+        if timestamp > last.0 {
+            *last = (timestamp, commit.tree_id());
+        }
+        if let Some(names) = self.tag_targets.as_ref().and_then(|targets| targets.get(&commit.id())) {
+            for name in names {
+                self.tagged_trees.insert(name.clone(), commit.tree_id());
+            }
+        }
+    }
+
+    pub fn finish(
+        &mut self,
+        repo: &Repository,
+        options: &AnalysisOptions,
+        path_filter: &PathFilter,
+    ) -> Result<(), AnalyzerError> {
+        // Most blobs are unchanged from one snapshot to the next
+        let mut line_counts: HashMap<Oid, Option<u32>> = HashMap::new();
+        let mut binary = HashMap::new();
+        let mut stats = |tree_id: Oid| -> Result<BTreeMap<String, LengthStats>, AnalyzerError> {
+            let mut lengths: BTreeMap<String, Vec<u32>> = BTreeMap::new();
+            for (ext, blob_id) in tracked_blobs(repo, tree_id, options, path_filter, &mut binary)? {
+                let lines = match line_counts.get(&blob_id) {
+                    Some(lines) => *lines,
+                    None => {
+                        let lines = line_count(repo, blob_id)?;
+                        line_counts.insert(blob_id, lines);
+                        lines
+                    }
+                };
+                if let Some(lines) = lines {
+                    lengths.entry(ext).or_default().push(lines);
+                }
+            }
+            Ok(lengths
+                .into_iter()
+                .map(|(ext, lengths)| (ext, length_stats(lengths, self.long_file_lines)))
+                .collect())
+        };
+
+        let mut months = BTreeMap::new();
+        for (month, (_, tree_id)) in &self.last_commits {
+            months.insert(month.clone(), stats(*tree_id)?);
+        }
+        let mut tags = BTreeMap::new();
+        for (name, tree_id) in &self.tagged_trees {
+            tags.insert(name.clone(), stats(*tree_id)?);
+        }
+        self.months = months;
+        self.tags = tags;
+        self.last_commits = BTreeMap::new();
+        self.tagged_trees = BTreeMap::new();
+        self.tag_targets = None;
+        Ok(())
+    }
+}
+
+fn length_stats(mut lengths: Vec<u32>, long_file_lines: u32) -> LengthStats {
+    lengths.sort_unstable();
+    let sorted: Vec<f64> = lengths.iter().map(|&l| f64::from(l)).collect();
+    LengthStats {
+        files: lengths.len() as i32,
+        p50_lines: quantile(&sorted, 0.5),
+        p90_lines: quantile(&sorted, 0.9),
+        max_lines: lengths.last().copied().unwrap_or_default(),
+        long_files: lengths.iter().filter(|&&l| l > long_file_lines).count() as i32,
+    }
+}
+
+// None for binary blobs; a last line without a newline counts too
+fn line_count(repo: &Repository, blob_id: Oid) -> Result<Option<u32>, AnalyzerError> {
+    let blob = repo.find_blob(blob_id)?;
+    if blob.is_binary() {
+        return Ok(None);
+    }
+    let content = blob.content();
+    let newlines = content.iter().filter(|&&b| b == b'\n').count();
+    let unterminated = content.last().is_some_and(|&b| b != b'\n');
+    Ok(Some((newlines + usize::from(unterminated)) as u32))
+}
+
+// (extension bucket, blob) of every tracked file in the tree. Under
+// file_detection="content" binary blobs are left out; `binary` keeps each
+// blob's verdict across snapshots, which mostly share blobs.
+fn tracked_blobs(
+    repo: &Repository,
+    tree_id: Oid,
+    options: &AnalysisOptions,
+    path_filter: &PathFilter,
+    binary: &mut HashMap<Oid, bool>,
+) -> Result<Vec<(String, Oid)>, AnalyzerError> {
+    let tree = repo.find_tree(tree_id)?;
+    let mut blobs = Vec::new();
+    tree.walk(TreeWalkMode::PreOrder, |dir, entry| {
+        if entry.kind() == Some(ObjectType::Blob) {
+            let path = format!("{}{}", dir, entry.name().unwrap_or(""));
+            let path = Path::new(&path);
+            let slash_path = path.to_slash_lossy();
+            if let Some(ext) = classify::bucket_for(path, &slash_path, options) {
+                if !path_filter.is_excluded(path) {
+                    blobs.push((ext, entry.id()));
+                }
+            }
+        }
+        TreeWalkResult::Ok
+    })?;
+    if options.file_detection == FileDetection::Content {
+        let odb = repo.odb()?;
+        let mut text = Vec::with_capacity(blobs.len());
+        for (ext, blob_id) in blobs {
+            let is_binary = match binary.get(&blob_id) {
+                Some(is_binary) => *is_binary,
+                None => {
+                    let is_binary = binaries::is_binary(repo, &odb, blob_id)?;
+                    binary.insert(blob_id, is_binary);
+                    is_binary
+                }
+            };
+            if !is_binary {
+                text.push((ext, blob_id));
+            }
+        }
+        blobs = text;
+    }
+    Ok(blobs)
+}
@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+
+use git2::Oid;
+
+use crate::authors;
+use crate::diff::{diff_options, file_deltas};
+use crate::options::AnalysisOptions;
+use crate::paths::PathFilter;
+use crate::{open_repository, stats_by_extension, AnalyzerError, FileStats};
+
+#[derive(Debug)]
+pub struct StashEntry {
+    pub index: usize,
+    pub oid: Oid,
+    pub message: String,
+    pub timestamp: i64,
+    pub author: String,
+    pub stats: HashMap<String, FileStats>,
+}
+
+// Stash entries, newest first, with the changes each one parked. A stash
+// commit's first parent is the commit it was made on and its tree is the
+// stashed working directory; a third parent holds untracked files, if any.
+pub fn stash_entries(
+    repo_path: &str,
+    options: &AnalysisOptions,
+) -> Result<Vec<StashEntry>, AnalyzerError> {
+    let mut repo = open_repository(repo_path, options)?;
+    let mut stashes = Vec::new();
+    repo.stash_foreach(|index, message, oid| {
+        stashes.push((index, message.to_string(), *oid));
+        true
+    })?;
+
+    let path_filter = PathFilter::for_repo(&repo, options.use_ignore_file)?;
+    let mut entries = Vec::with_capacity(stashes.len());
+    for (index, message, oid) in stashes {
+        let commit = repo.find_commit(oid)?;
+        let base = commit.parent(0)?.tree()?;
+
+        let mut deltas = Vec::new();
+        let mut diff_opts = diff_options(options);
+        let diff = repo.diff_tree_to_tree(Some(&base), Some(&commit.tree()?), Some(&mut diff_opts))?;
+        deltas.extend(file_deltas(&diff, &path_filter, options)?);
+        if let Ok(untracked) = commit.parent(2) {
+            let diff = repo.diff_tree_to_tree(None, Some(&untracked.tree()?), Some(&mut diff_opts))?;
+            deltas.extend(file_deltas(&diff, &path_filter, options)?);
+        }
+
+        let mut stats = stats_by_extension(&deltas);
+        for delta in &deltas {
+            stats.entry(delta.ext.clone()).or_default().files += 1;
+        }
+        entries.push(StashEntry {
+            index,
+            oid,
+            message,
+            timestamp: commit.author().when().seconds(),
+            author: authors::identity(&commit.author(), options.normalize_authors),
+            stats,
+        });
+    }
+    Ok(entries)
+}
@@ -0,0 +1,228 @@
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+use git2::{ObjectType, Odb, Oid, Repository, Sort, Tree};
+use serde::Serialize;
+
+use crate::history::Replacements;
+use crate::options::AnalysisOptions;
+use crate::{month_key, open_repository, AnalyzerError, ScanMetadata};
+
+// Largest blobs listed per period
+const TOP_BLOBS: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoragePeriod {
+    Month,
+    Year,
+}
+
+impl StoragePeriod {
+    pub fn parse(value: &str) -> Result<Self, AnalyzerError> {
+        match value {
+            "month" => Ok(StoragePeriod::Month),
+            "year" => Ok(StoragePeriod::Year),
+            other => Err(AnalyzerError::InvalidOption(format!(
+                "storage period must be \"month\" or \"year\", got {:?}",
+                other
+            ))),
+        }
+    }
+
+    fn key(&self, timestamp: i64) -> String {
+        let month = month_key(timestamp);
+        match self {
+            StoragePeriod::Month => month,
+            StoragePeriod::Year => month[..4].to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+pub struct ObjectCounts {
+    pub commits: u64,
+    pub trees: u64,
+    pub blobs: u64,
+    // Uncompressed object sizes; packing and deltas make the disk usage smaller
+    pub bytes: u64,
+}
+
+impl ObjectCounts {
+    fn add(&mut self, kind: ObjectType, size: usize) {
+        match kind {
+            ObjectType::Commit => self.commits += 1,
+            ObjectType::Tree => self.trees += 1,
+            _ => self.blobs += 1,
+        }
+        self.bytes += size as u64;
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BlobRef {
+    pub path: String,
+    pub commit: String,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StoragePoint {
+    // Last commit of the period
+    pub tip: String,
+    // Everything reachable from the history up to the end of the period
+    pub total: ObjectCounts,
+    // Objects first reachable during the period
+    pub added: ObjectCounts,
+    pub largest_blobs: Vec<BlobRef>,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct DiskUsage {
+    pub packs: u64,
+    pub pack_bytes: u64,
+    pub loose_objects: u64,
+    pub loose_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageReport {
+    pub period: &'static str,
+    pub periods: BTreeMap<String, StoragePoint>,
+    // What the object database takes on disk now
+    pub disk: DiskUsage,
+}
+
+// Replays the HEAD history oldest first, adding every object as it becomes
+// reachable, and records the running totals at the end of each period. Only
+// periods within the options' since/until window are reported.
+pub fn storage_growth(
+    repo_path: &str,
+    options: &AnalysisOptions,
+    period: StoragePeriod,
+) -> Result<(StorageReport, ScanMetadata), AnalyzerError> {
+    let repo = open_repository(repo_path, options)?;
+    let odb = repo.odb()?;
+    let mut metadata = ScanMetadata {
+        repo_path: repo_path.to_string(),
+        ..Default::default()
+    };
+
+    let replacements = Replacements::load(&repo, !options.ignore_replacements)?;
+    let revwalk = replacements.walk_sorted(&repo, Sort::TOPOLOGICAL | Sort::TIME | Sort::REVERSE)?;
+
+    let mut seen: HashSet<Oid> = HashSet::new();
+    let mut total = ObjectCounts::default();
+    let mut periods: BTreeMap<String, StoragePoint> = BTreeMap::new();
+    for oid in revwalk {
+        let oid = oid?;
+        let commit = replacements.find_commit(&repo, oid)?;
+        metadata.commits_scanned += 1;
+        if replacements.affects(oid) {
+            metadata.commits_replaced += 1;
+        }
+        let timestamp = commit.author().when().seconds();
+
+        let before = total;
+        let mut blobs = Vec::new();
+        // A replaced commit is counted as the object read in its place
+        seen.insert(commit.id());
+        total.add(ObjectType::Commit, odb.read_header(commit.id())?.0);
+        add_tree(&repo, &odb, &commit.tree()?, "", &mut seen, &mut total, &mut blobs)?;
+
+        if !options.in_date_range(timestamp) {
+            continue;
+        }
+        metadata.record_match(replacements.parent_ids(oid, &commit).len());
+        let point = periods.entry(period.key(timestamp)).or_insert_with(|| StoragePoint {
+            tip: String::new(),
+            total,
+            added: ObjectCounts::default(),
+            largest_blobs: Vec::new(),
+        });
+        point.tip = oid.to_string();
+        point.total = total;
+        point.added.commits += total.commits - before.commits;
+        point.added.trees += total.trees - before.trees;
+        point.added.blobs += total.blobs - before.blobs;
+        point.added.bytes += total.bytes - before.bytes;
+        point.largest_blobs.extend(blobs.into_iter().map(|(path, bytes)| BlobRef {
+            path,
+            commit: oid.to_string(),
+            bytes,
+        }));
+        point.largest_blobs.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.path.cmp(&b.path)));
+        point.largest_blobs.truncate(TOP_BLOBS);
+    }
+
+    let report = StorageReport {
+        period: match period {
+            StoragePeriod::Month => "month",
+            StoragePeriod::Year => "year",
+        },
+        periods,
+        disk: disk_usage(&repo.path().join("objects")),
+    };
+    Ok((report, metadata))
+}
+
+// Adds the objects of `tree` not seen before. A seen subtree is skipped whole,
+// since everything below it was added with it.
+fn add_tree(
+    repo: &Repository,
+    odb: &Odb,
+    tree: &Tree,
+    prefix: &str,
+    seen: &mut HashSet<Oid>,
+    total: &mut ObjectCounts,
+    blobs: &mut Vec<(String, u64)>,
+) -> Result<(), AnalyzerError> {
+    if !seen.insert(tree.id()) {
+        return Ok(());
+    }
+    total.add(ObjectType::Tree, odb.read_header(tree.id())?.0);
+    for entry in tree.iter() {
+        let path = format!("{}{}", prefix, entry.name().unwrap_or(""));
+        match entry.kind() {
+            Some(ObjectType::Tree) if !seen.contains(&entry.id()) => {
+                let subtree = repo.find_tree(entry.id())?;
+                add_tree(repo, odb, &subtree, &format!("{}/", path), seen, total, blobs)?;
+            }
+            Some(ObjectType::Blob) if seen.insert(entry.id()) => {
+                let (size, _) = odb.read_header(entry.id())?;
+                total.add(ObjectType::Blob, size);
+                blobs.push((path, size as u64));
+            }
+            // Submodule commits live in another repository
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+// Best effort: entries that cannot be read are left out
+fn disk_usage(objects: &Path) -> DiskUsage {
+    let files = |dir: &Path| -> Vec<std::fs::DirEntry> {
+        std::fs::read_dir(dir).map(|entries| entries.flatten().collect()).unwrap_or_default()
+    };
+    let size = |entry: &std::fs::DirEntry| entry.metadata().map(|m| m.len()).unwrap_or(0);
+
+    let mut usage = DiskUsage::default();
+    for entry in files(objects) {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name == "pack" {
+            for file in files(&entry.path()) {
+                // Indexes and other pack companions count toward the total too
+                usage.pack_bytes += size(&file);
+                if file.path().extension().is_some_and(|ext| ext == "pack") {
+                    usage.packs += 1;
+                }
+            }
+        } else if name.len() == 2 && name.chars().all(|c| c.is_ascii_hexdigit()) {
+            for file in files(&entry.path()) {
+                usage.loose_objects += 1;
+                usage.loose_bytes += size(&file);
+            }
+        }
+    }
+    usage
+}
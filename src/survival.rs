@@ -0,0 +1,250 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use git2::{Delta, DiffFindOptions, Patch};
+use path_slash::PathExt;
+use serde::Serialize;
+
+use crate::authors;
+use crate::classify;
+use crate::diff::diff_options;
+use crate::history::Replacements;
+use crate::options::AnalysisOptions;
+use crate::paths::PathFilter;
+use crate::{open_repository, AnalyzerError};
+
+pub const DEFAULT_CHECKPOINTS_DAYS: &[u32] = &[30, 90, 180, 365, 730];
+
+// Commit that introduced a line
+struct Birth {
+    timestamp: i64,
+    author: String,
+    month: String,
+    counted: bool,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct SurvivalCurve {
+    pub lines: i64,
+    pub deleted: i64,
+    // Kaplan-Meier estimate of the share of lines still alive after N days
+    pub survival: BTreeMap<String, f64>,
+    // Days until half the lines were deleted, if that happened
+    pub median_days: Option<f64>,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct SurvivalReport {
+    pub checkpoints_days: Vec<u32>,
+    pub by_extension: BTreeMap<String, SurvivalCurve>,
+    pub by_author: BTreeMap<String, SurvivalCurve>,
+    // Keyed by the bucket lines were introduced in
+    pub by_cohort: BTreeMap<String, SurvivalCurve>,
+}
+
+// Follows every tracked line along the first-parent history from the root to
+// HEAD and records how long it lived. Merges are diffed against their first
+// parent, so lines brought in by a merge are born at the merge. Lines still
+// present at HEAD count as censored at the HEAD commit's date.
+pub fn code_survival(
+    repo_path: &str,
+    options: &AnalysisOptions,
+    checkpoints_days: &[u32],
+) -> Result<SurvivalReport, AnalyzerError> {
+    let repo = open_repository(repo_path, options)?;
+    let path_filter = PathFilter::for_repo(&repo, options.use_ignore_file)?;
+
+    let replacements = Replacements::load(&repo, !options.ignore_replacements)?;
+    let revwalk = replacements.first_parent_walk(&repo)?;
+
+    let mut births: Vec<Birth> = Vec::new();
+    // path -> (extension, birth index of every current line)
+    let mut files: HashMap<String, (String, Vec<u32>)> = HashMap::new();
+    // (birth index, extension, lifetime in seconds, deleted)
+    let mut lifetimes: Vec<(u32, String, i64, bool)> = Vec::new();
+    let mut last_timestamp = 0;
+
+    for oid in revwalk {
+        let oid = oid?;
+        let commit = replacements.find_commit(&repo, oid)?;
+        let timestamp = commit.author().when().seconds();
+        last_timestamp = timestamp;
+        let birth = births.len() as u32;
+        births.push(Birth {
+            timestamp,
+            author: authors::identity(&commit.author(), options.normalize_authors),
+            month: options.bucket_key(timestamp),
+            counted: options.matches_author(&commit.author()) && options.in_date_range(timestamp),
+        });
+
+        let mut diff_opts = diff_options(options);
+        diff_opts.context_lines(0);
+        let parent_tree = match replacements.parents(&repo, oid, &commit).first() {
+            Some(parent) => Some(parent.tree()?),
+            None => None,
+        };
+        let mut diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), Some(&mut diff_opts))?;
+        diff.find_similar(Some(DiffFindOptions::new().renames(true)))?;
+
+        for idx in 0..diff.deltas().len() {
+            let Some(patch) = Patch::from_diff(&diff, idx)? else {
+                continue;
+            };
+            let delta = patch.delta();
+            let old_path = delta.old_file().path().map(|p| p.to_slash_lossy().into_owned());
+            let new_path = delta.new_file().path().map(|p| p.to_slash_lossy().into_owned());
+
+            // Only tracked files have state, so other files contribute no lines
+            let (old_ext, previous) = match &old_path {
+                Some(old_path) if delta.status() != Delta::Added => files.remove(old_path).unwrap_or_default(),
+                _ => Default::default(),
+            };
+            let tracked = new_path.as_deref().and_then(|path| {
+                let ext = classify::bucket_for(Path::new(path), path, options)?;
+                (!path_filter.is_excluded(Path::new(path))).then_some(ext)
+            });
+
+            let mut current = Vec::with_capacity(previous.len());
+            let mut consumed = 0usize;
+            for hunk_idx in 0..patch.num_hunks() {
+                let (hunk, _) = patch.hunk(hunk_idx)?;
+                // With no context, an insertion's old_start is the line it follows
+                let start = if hunk.old_lines() == 0 {
+                    hunk.old_start() as usize
+                } else {
+                    hunk.old_start().saturating_sub(1) as usize
+                };
+                let start = start.clamp(consumed, previous.len());
+                current.extend_from_slice(&previous[consumed..start]);
+                let end = (start + hunk.old_lines() as usize).min(previous.len());
+                for &born in &previous[start..end] {
+                    lifetimes.push((born, old_ext.clone(), timestamp - births[born as usize].timestamp, true));
+                }
+                consumed = end;
+                current.extend(std::iter::repeat_n(birth, hunk.new_lines() as usize));
+            }
+            current.extend_from_slice(&previous[consumed.min(previous.len())..]);
+
+            if let (Some(new_path), Some(ext), false) = (new_path, tracked, delta.status() == Delta::Deleted) {
+                if !patch.delta().flags().is_binary() {
+                    files.insert(new_path, (ext, current));
+                }
+            }
+        }
+    }
+
+    for (ext, lines) in files.into_values() {
+        for born in lines {
+            lifetimes.push((born, ext.clone(), last_timestamp - births[born as usize].timestamp, false));
+        }
+    }
+
+    let mut by_extension: BTreeMap<String, Vec<(i64, bool)>> = BTreeMap::new();
+    let mut by_author: BTreeMap<String, Vec<(i64, bool)>> = BTreeMap::new();
+    let mut by_cohort: BTreeMap<String, Vec<(i64, bool)>> = BTreeMap::new();
+    for (born, ext, lifetime, deleted) in lifetimes {
+        let birth = &births[born as usize];
+        if !birth.counted {
+            continue;
+        }
+        by_extension.entry(ext).or_default().push((lifetime, deleted));
+        by_author.entry(birth.author.clone()).or_default().push((lifetime, deleted));
+        by_cohort.entry(birth.month.clone()).or_default().push((lifetime, deleted));
+    }
+
+    let curves = |groups: BTreeMap<String, Vec<(i64, bool)>>| {
+        groups
+            .into_iter()
+            .map(|(key, observations)| (key, kaplan_meier(observations, checkpoints_days)))
+            .collect()
+    };
+    Ok(SurvivalReport {
+        checkpoints_days: checkpoints_days.to_vec(),
+        by_extension: curves(by_extension),
+        by_author: curves(by_author),
+        by_cohort: curves(by_cohort),
+    })
+}
+
+fn kaplan_meier(mut observations: Vec<(i64, bool)>, checkpoints_days: &[u32]) -> SurvivalCurve {
+    observations.sort_by_key(|&(lifetime, _)| lifetime);
+    let mut curve = SurvivalCurve {
+        lines: observations.len() as i64,
+        deleted: observations.iter().filter(|(_, deleted)| *deleted).count() as i64,
+        ..Default::default()
+    };
+
+    // (time, survival just after time) at every deletion time
+    let mut steps: Vec<(i64, f64)> = Vec::new();
+    let mut at_risk = observations.len() as f64;
+    let mut survival = 1.0;
+    let mut i = 0;
+    while i < observations.len() {
+        let time = observations[i].0;
+        let (mut deaths, mut total) = (0.0, 0.0);
+        while i < observations.len() && observations[i].0 == time {
+            if observations[i].1 {
+                deaths += 1.0;
+            }
+            total += 1.0;
+            i += 1;
+        }
+        if deaths > 0.0 {
+            survival *= 1.0 - deaths / at_risk;
+            steps.push((time, survival));
+        }
+        at_risk -= total;
+    }
+
+    for &days in checkpoints_days {
+        let seconds = i64::from(days) * 86_400;
+        let value = steps.iter().take_while(|(time, _)| *time <= seconds).last().map_or(1.0, |s| s.1);
+        curve.survival.insert(days.to_string(), (value * 10000.0).round() / 10000.0);
+    }
+    curve.median_days = steps
+        .iter()
+        .find(|(_, value)| *value <= 0.5)
+        .map(|(time, _)| (*time as f64 / 864.0).round() / 100.0);
+    curve
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: i64 = 86_400;
+
+    #[test]
+    fn no_deletions_survive_at_every_checkpoint() {
+        let curve = kaplan_meier(vec![(5 * DAY, false), (50 * DAY, false)], &[30, 90]);
+        assert_eq!(curve.lines, 2);
+        assert_eq!(curve.deleted, 0);
+        assert_eq!(curve.survival["30"], 1.0);
+        assert_eq!(curve.survival["90"], 1.0);
+        assert_eq!(curve.median_days, None);
+    }
+
+    #[test]
+    fn tied_deletions_make_one_step() {
+        // Two of four lines deleted at day 10, one censored at the same time;
+        // the censored line is still at risk at day 10
+        let observations = vec![(10 * DAY, true), (100 * DAY, false), (10 * DAY, false), (10 * DAY, true)];
+        let curve = kaplan_meier(observations, &[5, 10, 30]);
+        assert_eq!(curve.deleted, 2);
+        assert_eq!(curve.survival["5"], 1.0);
+        assert_eq!(curve.survival["10"], 0.5);
+        assert_eq!(curve.survival["30"], 0.5);
+        assert_eq!(curve.median_days, Some(10.0));
+    }
+
+    #[test]
+    fn censored_lines_leave_the_risk_set() {
+        // After the censoring at day 1 only two lines are at risk
+        let observations = vec![(DAY, false), (2 * DAY, true), (3 * DAY, true)];
+        let curve = kaplan_meier(observations, &[1, 2, 3]);
+        assert_eq!(curve.survival["1"], 1.0);
+        assert_eq!(curve.survival["2"], 0.5);
+        assert_eq!(curve.survival["3"], 0.0);
+        assert_eq!(curve.median_days, Some(2.0));
+    }
+}
@@ -0,0 +1,56 @@
+use std::collections::BTreeMap;
+
+use git2::{Commit, Time};
+use serde::Serialize;
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct AuthorTimezones {
+    // Commits per committer UTC offset
+    pub offsets: BTreeMap<String, i32>,
+    // Times the offset changed between consecutive commits, in date order.
+    // Frequent flips point at rewritten history or a shared account.
+    pub flips: i32,
+}
+
+// Distribution of the UTC offsets recorded on commits, per month and per
+// author; a cheap signal of where contributors work from
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct TimezoneReport {
+    pub months: BTreeMap<String, BTreeMap<String, i32>>,
+    pub authors: BTreeMap<String, AuthorTimezones>,
+    #[serde(skip)]
+    history: Vec<(String, i64, i32)>,
+}
+
+impl TimezoneReport {
+    pub fn observe(&mut self, month_key: &str, commit: &Commit, author: &str) {
+        let when = commit.committer().when();
+        let offset = format_offset(&when);
+        *self.months.entry(month_key.to_string()).or_default().entry(offset.clone()).or_default() += 1;
+        *self.authors.entry(author.to_string()).or_default().offsets.entry(offset).or_default() += 1;
+        self.history.push((author.to_string(), when.seconds(), when.offset_minutes()));
+    }
+
+    // The walk runs newest first, so flips are counted once it is over. Commits
+    // with equal timestamps keep their (reversed) walk order.
+    pub fn finish(&mut self) {
+        self.history.reverse();
+        self.history.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.cmp(&b.1)));
+        for pair in self.history.windows(2) {
+            let ((author, _, previous), (next_author, _, offset)) = (&pair[0], &pair[1]);
+            if author == next_author && previous != offset {
+                if let Some(timezones) = self.authors.get_mut(author) {
+                    timezones.flips += 1;
+                }
+            }
+        }
+        self.history = Vec::new();
+    }
+}
+
+// "+05:30", as git prints it but with a colon
+fn format_offset(time: &Time) -> String {
+    let minutes = time.offset_minutes();
+    let sign = if minutes < 0 { '-' } else { '+' };
+    format!("{}{:02}:{:02}", sign, minutes.abs() / 60, minutes.abs() % 60)
+}
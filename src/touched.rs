@@ -0,0 +1,31 @@
+use std::collections::{BTreeMap, HashSet};
+
+use serde::Serialize;
+
+use crate::diff::FileDelta;
+
+// Distinct paths changed during one month, overall and per extension. Unlike
+// the `files` counter, which counts a path only the first time the whole scan
+// sees it, a path counts once in every month it changed.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct TouchedMonth {
+    pub files: usize,
+    pub extensions: BTreeMap<String, usize>,
+    #[serde(skip)]
+    paths: HashSet<String>,
+}
+
+impl TouchedMonth {
+    pub fn observe(&mut self, deltas: &[FileDelta]) {
+        for delta in deltas {
+            if self.paths.insert(delta.path.clone()) {
+                self.files += 1;
+                *self.extensions.entry(delta.ext.clone()).or_default() += 1;
+            }
+        }
+    }
+
+    pub fn finish(&mut self) {
+        self.paths = HashSet::new();
+    }
+}
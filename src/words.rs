@@ -0,0 +1,58 @@
+use std::collections::HashMap;
+
+use crate::diff::Hunk;
+
+// Counts words added and removed within each hunk. Removed and added lines of a
+// hunk are compared as multisets of tokens, so editing one word in a long line
+// counts as one word removed and one added rather than a whole line each.
+pub fn word_changes(hunks: &[Hunk]) -> (i32, i32) {
+    let mut words_added = 0;
+    let mut words_removed = 0;
+
+    for hunk in hunks {
+        let mut removed: HashMap<&str, i32> = HashMap::new();
+        for line in &hunk.removed {
+            for token in tokenize(line) {
+                *removed.entry(token).or_default() += 1;
+            }
+        }
+
+        for line in &hunk.added {
+            for token in tokenize(line) {
+                match removed.get_mut(token) {
+                    Some(count) if *count > 0 => *count -= 1,
+                    _ => words_added += 1,
+                }
+            }
+        }
+
+        words_removed += removed.values().sum::<i32>();
+    }
+
+    (words_added, words_removed)
+}
+
+// Splits a line into identifier-like runs and single punctuation characters,
+// dropping whitespace
+fn tokenize(line: &str) -> impl Iterator<Item = &str> {
+    let mut tokens = Vec::new();
+    let mut start: Option<usize> = None;
+
+    for (idx, ch) in line.char_indices() {
+        if ch.is_alphanumeric() || ch == '_' {
+            start.get_or_insert(idx);
+            continue;
+        }
+        if let Some(s) = start.take() {
+            tokens.push(&line[s..idx]);
+        }
+        if !ch.is_whitespace() {
+            tokens.push(&line[idx..idx + ch.len_utf8()]);
+        }
+    }
+    if let Some(s) = start {
+        tokens.push(&line[s..]);
+    }
+
+    tokens.into_iter()
+}
@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+
+use git2::{Diff, DiffOptions, Repository, Tree};
+
+use crate::diff::{diff_options, file_deltas};
+use crate::options::AnalysisOptions;
+use crate::paths::PathFilter;
+use crate::{open_repository, stats_by_extension, AnalyzerError, FileStats};
+
+// Uncommitted changes: HEAD against the index and working directory, like
+// `git diff HEAD`. `files` counts the changed files of each extension.
+pub fn working_tree_stats(
+    repo_path: &str,
+    options: &AnalysisOptions,
+    include_untracked: bool,
+) -> Result<HashMap<String, FileStats>, AnalyzerError> {
+    let repo = open_repository(repo_path, options)?;
+    let path_filter = PathFilter::for_repo(&repo, options.use_ignore_file)?;
+
+    let head_tree = head_tree(&repo)?;
+    let mut diff_opts = worktree_diff_options(options, include_untracked);
+    let diff = repo.diff_tree_to_workdir_with_index(head_tree.as_ref(), Some(&mut diff_opts))?;
+    extension_stats(&diff, &path_filter, options)
+}
+
+// The same changes split the way `git diff --cached` and `git diff` show
+// them: staged is HEAD against the index, unstaged the index against the
+// working directory. Untracked files count as unstaged.
+pub struct WorkingTreeBreakdown {
+    pub staged: HashMap<String, FileStats>,
+    pub unstaged: HashMap<String, FileStats>,
+}
+
+pub fn working_tree_breakdown(
+    repo_path: &str,
+    options: &AnalysisOptions,
+    include_untracked: bool,
+) -> Result<WorkingTreeBreakdown, AnalyzerError> {
+    let repo = open_repository(repo_path, options)?;
+    let path_filter = PathFilter::for_repo(&repo, options.use_ignore_file)?;
+
+    let head_tree = head_tree(&repo)?;
+    let mut staged_opts = worktree_diff_options(options, false);
+    let staged = repo.diff_tree_to_index(head_tree.as_ref(), None, Some(&mut staged_opts))?;
+    let mut unstaged_opts = worktree_diff_options(options, include_untracked);
+    let unstaged = repo.diff_index_to_workdir(None, Some(&mut unstaged_opts))?;
+
+    Ok(WorkingTreeBreakdown {
+        staged: extension_stats(&staged, &path_filter, options)?,
+        unstaged: extension_stats(&unstaged, &path_filter, options)?,
+    })
+}
+
+// An unborn HEAD compares against the empty tree
+fn head_tree(repo: &Repository) -> Result<Option<Tree<'_>>, AnalyzerError> {
+    match repo.head() {
+        Ok(head) => Ok(Some(head.peel_to_tree()?)),
+        Err(_) => Ok(None),
+    }
+}
+
+fn worktree_diff_options(options: &AnalysisOptions, include_untracked: bool) -> DiffOptions {
+    let mut diff_opts = diff_options(options);
+    if include_untracked {
+        diff_opts
+            .include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .show_untracked_content(true);
+    }
+    diff_opts
+}
+
+fn extension_stats(
+    diff: &Diff,
+    path_filter: &PathFilter,
+    options: &AnalysisOptions,
+) -> Result<HashMap<String, FileStats>, AnalyzerError> {
+    let deltas = file_deltas(diff, path_filter, options)?;
+    let mut stats = stats_by_extension(&deltas);
+    for delta in &deltas {
+        stats.entry(delta.ext.clone()).or_default().files += 1;
+    }
+    Ok(stats)
+}